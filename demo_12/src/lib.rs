@@ -10,4 +10,4 @@ pub mod pipeline;
 pub mod framebuffers;
 pub mod command_pool;
 pub mod command_buffers;
-pub mod sync_objects;
\ No newline at end of file
+pub mod sync_objects;
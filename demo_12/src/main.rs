@@ -28,7 +28,7 @@ fn main() -> Result<()> {
         *control_flow = ControlFlow::Poll;
         match event {
             Event::MainEventsCleared if !destroying => unsafe { 
-                app.render(&window)
+                app.render(Some(&window))
             }.unwrap(),
             
             Event::WindowEvent { event: WindowEvent::CloseRequested, .. } => {
@@ -39,6 +39,19 @@ unsafe fn check_physical_device(
     // get { graphics, present }
     QueueFamilyIndices::get(instance, data, physical_device)?;
 
+    // headless apps render into offscreen images, so no swapchain is required
+    if !data.headless {
+        check_physical_device_swapchain(instance, data, physical_device)?;
+    }
+
+    Ok(())
+}
+
+unsafe fn check_physical_device_swapchain(
+    instance: &Instance,
+    data: &AppData,
+    physical_device: vk::PhysicalDevice,
+) -> Result<()> {
     // check swapchain support
     check_physical_device_extensions(instance, physical_device)?;
 
@@ -10,6 +10,13 @@ use vulkanalia::bytecode::Bytecode;
 use vulkanalia::prelude::v1_0::*;
 
 pub unsafe fn create_render_pass(instance: &Instance, device: &Device, data: &mut AppData) -> Result<()> {
+    // Offscreen render targets are read back instead of presented
+    let final_layout = if data.headless {
+        vk::ImageLayout::TRANSFER_SRC_OPTIMAL
+    } else {
+        vk::ImageLayout::PRESENT_SRC_KHR
+    };
+
     // Attachments
     let color_attachment = vk::AttachmentDescription::builder()
         .format(data.swapchain_format)
@@ -19,7 +26,7 @@ pub unsafe fn create_render_pass(instance: &Instance, device: &Device, data: &mu
         .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
         .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
         .initial_layout(vk::ImageLayout::UNDEFINED)
-        .final_layout(vk::ImageLayout::PRESENT_SRC_KHR);
+        .final_layout(final_layout);
 
     // Subpasses
     let color_attachment_ref = vk::AttachmentReference::builder()
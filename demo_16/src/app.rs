@@ -8,7 +8,12 @@ use app_data::AppData;
 use instance::VALIDATION_ENABLED;
 use sync_objects::MAX_FRAMES_IN_FLIGHT;
 
+use std::rc::Rc;
+
 use anyhow::{anyhow, Result};
+use renderer::commands::Transfer;
+use renderer::memory::Allocator;
+use renderer::offscreen::{self, Frame};
 use winit::window::Window;
 use vulkanalia::loader::{LibloadingLoader, LIBRARY};
 use vulkanalia::prelude::v1_0::*;
@@ -18,13 +23,15 @@ use vulkanalia::vk::KhrSurfaceExtension;
 use vulkanalia::vk::KhrSwapchainExtension;
 
 /// Our Vulkan app.
-#[derive(Clone, Debug)]
+#[derive(Debug)]
 pub struct App {
     entry: Entry,
     instance: Instance,
     data: AppData,
-    device: Device,
+    device: Rc<Device>,
     frame: usize,
+    /// The swapchain image or render target the latest frame was rendered into.
+    image_index: usize,
     pub resized: bool,
 }
 
@@ -36,25 +43,13 @@ impl App {
             |b| anyhow!("{}", b))?;
         let mut data: AppData = AppData::default();
         
-        let instance: Instance = instance::create_instance(window, &entry, &mut data)?;
+        let instance: Instance = instance::create_instance(Some(window), &entry, &mut data)?;
         data.surface = vk_window::create_surface(&instance, &window, &window)?;
         physical_device::pick_physical_device(&instance, &mut data)?;
-        let device: Device = logical_device::create_logical_device(&entry, &instance, &mut data)?;
+        let device: Rc<Device> = Rc::new(logical_device::create_logical_device(&entry, &instance, &mut data)?);
         swapchain::create_swapchain(window, &instance, &device, &mut data)?;
         swapchain::create_swapchain_image_views(&device, &mut data)?;
-        pipeline::create_render_pass(&instance, &device, &mut data)?;
-        pipeline::create_pipeline(&device, &mut data)?;
-        framebuffers::create_framebuffers(&device, &mut data)?;
-        command_pool::create_command_pool(&instance, &device, &mut data)?;
-        
-        // create buffers (vertex & index)
-        buffers::create_vertex_buffer(&instance, &device, &mut data)?;
-        buffers::create_index_buffer(&instance, &device, &mut data)?;
-        // bind buffers (vertex & index) 
-        // use index buffer
-        command_buffers::create_command_buffers(&device, &mut data)?;
-        
-        sync_objects::create_sync_objects(&device, &mut data)?;
+        Self::create_resources(&instance, &device, &mut data)?;
 
         Ok(Self { 
             entry, 
@@ -62,12 +57,66 @@ impl App {
             data, 
             device, 
             frame: 0,
+            image_index: 0,
+            resized: false,
+        })
+    }
+
+    /// Creates our Vulkan app without a window, rendering into `image_count`
+    /// offscreen render targets of the given size.
+    pub unsafe fn create_headless(width: u32, height: u32, image_count: usize) -> Result<Self> {
+        let loader: LibloadingLoader = LibloadingLoader::new(LIBRARY)?;
+        let entry: Entry = Entry::new(loader).map_err(
+            |b| anyhow!("{}", b))?;
+        let mut data: AppData = AppData { headless: true, ..Default::default() };
+
+        let instance: Instance = instance::create_instance(None, &entry, &mut data)?;
+        physical_device::pick_physical_device(&instance, &mut data)?;
+        let device: Rc<Device> = Rc::new(logical_device::create_logical_device(&entry, &instance, &mut data)?);
+        let extent = vk::Extent2D { width, height };
+        data.allocator = Rc::new(Allocator::new(&instance, data.physical_device));
+        for image in offscreen::create_render_target_images(&device, &data.allocator, extent, image_count)? {
+            data.swapchain_images.push(*image);
+            data.render_targets.push(image);
+        }
+        data.swapchain_format = offscreen::RENDER_TARGET_FORMAT;
+        data.swapchain_extent = extent;
+        swapchain::create_swapchain_image_views(&device, &mut data)?;
+        Self::create_resources(&instance, &device, &mut data)?;
+
+        Ok(Self {
+            entry,
+            instance,
+            data,
+            device,
+            frame: 0,
+            image_index: 0,
             resized: false,
         })
     }
 
-    /// Renders a frame for our Vulkan app.
-    pub unsafe fn render(&mut self, window: &Window) -> Result<()> {
+    /// Creates everything that is drawn with once the presentation images exist.
+    unsafe fn create_resources(instance: &Instance, device: &Device, data: &mut AppData) -> Result<()> {
+        pipeline::create_render_pass(instance, device, data)?;
+        pipeline::create_pipeline(device, data)?;
+        framebuffers::create_framebuffers(device, data)?;
+        command_pool::create_command_pool(instance, device, data)?;
+        
+        // create buffers (vertex & index)
+        buffers::create_vertex_buffer(instance, device, data)?;
+        buffers::create_index_buffer(instance, device, data)?;
+        // bind buffers (vertex & index) 
+        // use index buffer
+        command_buffers::create_command_buffers(device, data)?;
+        
+        sync_objects::create_sync_objects(device, data)?;
+
+        Ok(())
+    }
+
+    /// Renders a frame for our Vulkan app (into the next offscreen render
+    /// target when headless, which has no window).
+    pub unsafe fn render(&mut self, window: Option<&Window>) -> Result<()> {
         // Get semaphore and wait
         let in_flight_fence = self.data.in_flight_fences[self.frame];
 
@@ -77,15 +126,20 @@ impl App {
             u64::max_value()
         )?;
 
-        // Get image from swapchain
-        let image_index = self.device
-            .acquire_next_image_khr(
-                self.data.swapchain,
-                u64::max_value(),
-                self.data.image_available_semaphores[self.frame],
-                vk::Fence::null(),
-            )?
-            .0 as usize;
+        // Get image from swapchain (or the next render target when headless)
+        let image_index = if self.data.headless {
+            offscreen::next_render_target(self.image_index, self.data.swapchain_images.len())
+        } else {
+            self.device
+                .acquire_next_image_khr(
+                    self.data.swapchain,
+                    u64::max_value(),
+                    self.data.image_available_semaphores[self.frame],
+                    vk::Fence::null(),
+                )?
+                .0 as usize
+        };
+        self.image_index = image_index;
 
         let image_in_flight = self.data.images_in_flight[image_index];
         if !image_in_flight.is_null() {
@@ -98,11 +152,16 @@ impl App {
 
         self.data.images_in_flight[image_index] = in_flight_fence;
 
-        // Commit command buffer
+        // Commit command buffer (headless frames are neither acquired nor presented)
         let wait_semaphores = &[self.data.image_available_semaphores[self.frame]];
         let wait_stages = &[vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT];
         let command_buffers = &[self.data.command_buffers[image_index]];
         let signal_semaphores = &[self.data.render_finished_semaphores[self.frame]];
+        let (wait_semaphores, signal_semaphores) = if self.data.headless {
+            (&[][..], &[][..])
+        } else {
+            (&wait_semaphores[..], &signal_semaphores[..])
+        };
         let submit_info = vk::SubmitInfo::builder()
             .wait_semaphores(wait_semaphores)
             .wait_dst_stage_mask(wait_stages)
@@ -118,24 +177,26 @@ impl App {
             in_flight_fence
         )?;
 
-        // Present
-        let swapchains = &[self.data.swapchain];
-        let image_indices = &[image_index as u32];
-        let present_info = vk::PresentInfoKHR::builder()
-            .wait_semaphores(signal_semaphores)
-            .swapchains(swapchains)
-            .image_indices(image_indices);
+        // Present (a headless app has no window to present to)
+        if let Some(window) = window {
+            let swapchains = &[self.data.swapchain];
+            let image_indices = &[image_index as u32];
+            let present_info = vk::PresentInfoKHR::builder()
+                .wait_semaphores(signal_semaphores)
+                .swapchains(swapchains)
+                .image_indices(image_indices);
 
-        let result = self.device
-            .queue_present_khr(self.data.present_queue, &present_info);
-        
-        // Recreates the swapchain
-        let changed = result == Ok(vk::SuccessCode::SUBOPTIMAL_KHR) || result == Err(vk::ErrorCode::OUT_OF_DATE_KHR);
-        if self.resized || changed {
-            self.resized = false;
-            self.recreate_swapchain(window)?;
-        } else if let Err(e) = result {
-            return Err(anyhow!(e));
+            let result = self.device
+                .queue_present_khr(self.data.present_queue, &present_info);
+
+            // Recreates the swapchain
+            let changed = result == Ok(vk::SuccessCode::SUBOPTIMAL_KHR) || result == Err(vk::ErrorCode::OUT_OF_DATE_KHR);
+            if self.resized || changed {
+                self.resized = false;
+                self.recreate_swapchain(window)?;
+            } else if let Err(e) = result {
+                return Err(anyhow!(e));
+            }
         }
 
         // Update current frame index
@@ -144,6 +205,22 @@ impl App {
         Ok(())
     }
 
+    /// Reads the render target the latest frame of a headless app was rendered
+    /// into back to CPU memory.
+    pub unsafe fn read_frame(&self) -> Result<Frame> {
+        if !self.data.headless {
+            return Err(anyhow!("Only headless apps can read back render targets."));
+        }
+
+        let transfer = Transfer {
+            allocator: &self.data.allocator,
+            command_pool: self.data.command_pool,
+            queue: self.data.graphics_queue,
+        };
+        let image = self.data.swapchain_images[self.image_index];
+        offscreen::read_render_target_image(&self.device, &transfer, image, self.data.swapchain_extent)
+    }
+
     /// Recreates the swapchain for our Vulkan app.
     #[rustfmt::skip]
     unsafe fn recreate_swapchain(&mut self, window: &Window) -> Result<()> {
@@ -191,8 +268,11 @@ impl App {
         self.device.destroy_buffer(self.data.vertex_buffer, None);
 
         self.device.destroy_command_pool(self.data.command_pool, None);
+        self.data.allocator.destroy(&self.device);
         self.device.destroy_device(None);
-        self.instance.destroy_surface_khr(self.data.surface, None);
+        if !self.data.headless {
+            self.instance.destroy_surface_khr(self.data.surface, None);
+        }
 
         if VALIDATION_ENABLED {
             self.instance.destroy_debug_utils_messenger_ext(self.data.messenger, None);
@@ -217,6 +297,10 @@ impl App {
             .iter()
             .for_each(|v| 
                 self.device.destroy_image_view(*v, None));
-        self.device.destroy_swapchain_khr(self.data.swapchain, None);
+        if self.data.headless {
+            self.data.render_targets.clear();
+        } else {
+            self.device.destroy_swapchain_khr(self.data.swapchain, None);
+        }
     }
 }
\ No newline at end of file
@@ -23,7 +23,7 @@ pub const VALIDATION_LAYER: vk::ExtensionName = vk::ExtensionName::from_bytes(b"
 /// The Vulkan SDK version that started requiring the portability subset extension for macOS.
 pub const PORTABILITY_MACOS_VERSION: Version = Version::new(1, 3, 216);
 
-pub unsafe fn create_instance(window: &Window, entry: &Entry, data: &mut AppData) -> Result<Instance> {
+pub unsafe fn create_instance(window: Option<&Window>, entry: &Entry, data: &mut AppData) -> Result<Instance> {
     // // Application Info
     let application_info = vk::ApplicationInfo::builder()
         .application_name(b"Vulkan Tutorial (Rust)\0")
@@ -49,8 +49,10 @@ pub unsafe fn create_instance(window: &Window, entry: &Entry, data: &mut AppData
         Vec::new()
     };
 
-    // Extensions
-    let mut extensions = vk_window::get_required_instance_extensions(window)
+    // Extensions (headless instances do not need the surface extensions)
+    let mut extensions = window
+        .map(|w| vk_window::get_required_instance_extensions(w))
+        .unwrap_or(&[])
         .iter()
         .map(|e| e.as_ptr())
         .collect::<Vec<_>>();
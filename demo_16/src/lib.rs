@@ -12,4 +12,4 @@ pub mod command_pool;
 pub mod command_buffers;
 pub mod sync_objects;
 pub mod shared;
-pub mod buffers;
\ No newline at end of file
+pub mod buffers;
@@ -30,7 +30,7 @@ fn main() -> Result<()> {
         match event {
             // Render a frame if our Vulkan app is not being destroyed.
             Event::MainEventsCleared if !destroying && !minimized => unsafe { 
-                app.render(&window)
+                app.render(Some(&window))
             }.unwrap(),
             // Mark the window as having been resized.
             Event::WindowEvent { event: WindowEvent::Resized(size), .. } => {
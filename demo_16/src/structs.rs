@@ -31,16 +31,20 @@ impl QueueFamilyIndices {
             .position(|p| p.queue_flags.contains(vk::QueueFlags::GRAPHICS))
             .map(|i| i as u32);
 
-        // present
+        // present (headless apps have no surface, the graphics queue stands in)
         let mut present = None;
-        for (index, properties) in properties.iter().enumerate() {
-            if instance.get_physical_device_surface_support_khr(
-                physical_device,
-                index as u32,
-                data.surface,
-            )? {
-                present = Some(index as u32);
-                break;
+        if data.headless {
+            present = graphics;
+        } else {
+            for (index, properties) in properties.iter().enumerate() {
+                if instance.get_physical_device_surface_support_khr(
+                    physical_device,
+                    index as u32,
+                    data.surface,
+                )? {
+                    present = Some(index as u32);
+                    break;
+                }
             }
         }
         
@@ -1,7 +1,11 @@
+use std::rc::Rc;
+
+use renderer::memory::Allocator;
+use renderer::owned::Image;
 use vulkanalia::prelude::v1_0::*;
 
 /// The Vulkan handles and associated properties used by our Vulkan app.
-#[derive(Clone, Debug, Default)]
+#[derive(Debug, Default)]
 pub struct AppData {
     // Debug
     pub messenger:       vk::DebugUtilsMessengerEXT,
@@ -17,6 +21,10 @@ pub struct AppData {
     pub swapchain:             vk::SwapchainKHR,
     pub swapchain_images:      Vec<vk::Image>,
     pub swapchain_image_views: Vec<vk::ImageView>,
+    // Offscreen (headless render targets stand in for the swapchain images)
+    pub headless:       bool,
+    pub allocator:      Rc<Allocator>,
+    pub render_targets: Vec<Image>,
     // Pipeline
     pub render_pass:           vk::RenderPass,
     pub descriptor_set_layout: vk::DescriptorSetLayout,
@@ -14,4 +14,4 @@ pub mod sync_objects;
 pub mod shared;
 pub mod buffers;
 pub mod descriptor;
-pub mod texture;
\ No newline at end of file
+pub mod texture;
@@ -36,8 +36,8 @@ pub unsafe fn create_logical_device(entry: &Entry, instance: &Instance, data: &m
         vec![]
     };
 
-    // Extensions
-    let mut extensions = DEVICE_EXTENSIONS
+    // Extensions (headless apps do not use a swapchain)
+    let mut extensions = if data.headless { &[] } else { DEVICE_EXTENSIONS }
         .iter()
         .map(|n| n.as_ptr())
         .collect::<Vec<_>>();
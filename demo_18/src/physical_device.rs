@@ -39,6 +39,24 @@ unsafe fn check_physical_device(
     // get { graphics, present }
     QueueFamilyIndices::get(instance, data, physical_device)?;
 
+    // headless apps render into offscreen images, so no swapchain is required
+    if !data.headless {
+        check_physical_device_swapchain(instance, data, physical_device)?;
+    }
+
+    let features = instance.get_physical_device_features(physical_device);
+    if features.sampler_anisotropy != vk::TRUE {
+        return Err(anyhow!(SuitabilityError("No sampler anisotropy.")));
+    }
+
+    Ok(())
+}
+
+unsafe fn check_physical_device_swapchain(
+    instance: &Instance,
+    data: &AppData,
+    physical_device: vk::PhysicalDevice,
+) -> Result<()> {
     // check swapchain support
     check_physical_device_extensions(instance, physical_device)?;
 
@@ -49,11 +67,6 @@ unsafe fn check_physical_device(
         return Err(anyhow!(SuitabilityError("Insufficient swapchain support.")));
     }
 
-    let features = instance.get_physical_device_features(physical_device);
-    if features.sampler_anisotropy != vk::TRUE {
-        return Err(anyhow!(SuitabilityError("No sampler anisotropy.")));
-    }
-
     Ok(())
 }
 
@@ -245,7 +245,7 @@ pub unsafe fn get_memory_type_index(
 }
 
 
-unsafe fn begin_single_time_commands(device: &Device, data: &AppData) -> Result<vk::CommandBuffer> {
+pub unsafe fn begin_single_time_commands(device: &Device, data: &AppData) -> Result<vk::CommandBuffer> {
     // Allocate
 
     let info = vk::CommandBufferAllocateInfo::builder()
@@ -264,7 +264,7 @@ unsafe fn begin_single_time_commands(device: &Device, data: &AppData) -> Result<
     Ok(command_buffer)
 }
 
-unsafe fn end_single_time_commands(device: &Device, data: &AppData, command_buffer: vk::CommandBuffer) -> Result<()> {
+pub unsafe fn end_single_time_commands(device: &Device, data: &AppData, command_buffer: vk::CommandBuffer) -> Result<()> {
     // End
 
     device.end_command_buffer(command_buffer)?;
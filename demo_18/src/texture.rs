@@ -8,14 +8,17 @@ use std::fs::File;
 use std::ptr::copy_nonoverlapping as memcpy;
 
 use anyhow::Result;
+use renderer::assets::Assets;
+use renderer::settings::Settings;
 use vulkanalia::prelude::v1_0::*;
 
 pub unsafe fn create_texture_image(instance: &Instance, device: &Device, data: &mut AppData) -> Result<()> {
     // ----------------------------------------
     // Texture image
     // ----------------------------------------
-    // 1. Load texture image data
-    let image = File::open("./resources/texture.png")?;
+    // 1. Load texture image data (found in the asset roots, wherever the app runs from)
+    let path = Assets::new(&Settings::default()).resolve("resources/texture.png")?;
+    let image = File::open(path)?;
 
     let decoder = png::Decoder::new(image);
     let mut reader = decoder.read_info()?;
@@ -1,18 +0,0 @@
-//================================================
-// Command Pool
-//================================================
-use crate::app_data::AppData;
-use crate::structs::QueueFamilyIndices;
-
-use anyhow::Result;
-use vulkanalia::prelude::v1_0::*;
-
-pub unsafe fn create_command_pool(instance: &Instance, device: &Device, data: &mut AppData) -> Result<()> {
-    let indices = QueueFamilyIndices::get(instance, data, data.physical_device)?;
-
-    let info = vk::CommandPoolCreateInfo::builder().queue_family_index(indices.graphics);
-
-    data.command_pool = device.create_command_pool(&info, None)?;
-
-    Ok(())
-}
\ No newline at end of file
@@ -1,127 +0,0 @@
-//================================================
-// Texture
-//================================================
-use crate::app_data::AppData;
-use crate::shared;
-
-use std::fs::File;
-use std::ptr::copy_nonoverlapping as memcpy;
-
-use anyhow::Result;
-use vulkanalia::prelude::v1_0::*;
-
-pub unsafe fn create_texture_image(instance: &Instance, device: &Device, data: &mut AppData) -> Result<()> {
-    // ----------------------------------------
-    // Texture image
-    // ----------------------------------------
-    // 1. Load texture image data
-    let image = File::open("./resources/viking_room.png")?;
-
-    let decoder = png::Decoder::new(image);
-    let mut reader = decoder.read_info()?;
-
-    let mut pixels = vec![0; reader.info().raw_bytes()];
-    reader.next_frame(&mut pixels)?;
-
-    let size = reader.info().raw_bytes() as u64;
-    let (width, height) = reader.info().size();
-
-    if width != 1024 || height != 1024 || reader.info().color_type != png::ColorType::Rgba {
-        panic!("Invalid texture image (use https://kylemayes.github.io/vulkanalia/images/viking_room.png).");
-    }
-
-    // 2. Create a staging buffer visible to the cpu
-    let (staging_buffer, staging_buffer_memory) = shared::create_buffer(
-        instance,
-        device,
-        data,
-        size,
-        vk::BufferUsageFlags::TRANSFER_SRC,
-        vk::MemoryPropertyFlags::HOST_COHERENT | vk::MemoryPropertyFlags::HOST_VISIBLE,
-    )?;
-
-    // 3. Copy data to staging buffer
-    let memory = device.map_memory(staging_buffer_memory, 0, size, vk::MemoryMapFlags::empty())?;
-
-    memcpy(pixels.as_ptr(), memory.cast(), pixels.len());
-
-    device.unmap_memory(staging_buffer_memory);
-
-    // 4. Create texture image object
-    let (texture_image, texture_image_memory) = shared::create_image(
-        instance,
-        device,
-        data,
-        width,
-        height,
-        vk::Format::R8G8B8A8_SRGB,
-        vk::ImageTiling::OPTIMAL,
-        vk::ImageUsageFlags::SAMPLED | vk::ImageUsageFlags::TRANSFER_DST,
-        vk::MemoryPropertyFlags::DEVICE_LOCAL,
-    )?;
-
-    data.texture_image = texture_image;
-    data.texture_image_memory = texture_image_memory;
-
-    // ----------------------------------------
-    // Layout transition
-    // ----------------------------------------
-    // Transition + Copy (image)
-    shared::transition_image_layout(
-        device,
-        data,
-        data.texture_image,
-        vk::Format::R8G8B8A8_SRGB,
-        vk::ImageLayout::UNDEFINED,
-        vk::ImageLayout::TRANSFER_DST_OPTIMAL,
-    )?;
-
-    // Copy buffer to image
-    shared::copy_buffer_to_image(device, data, staging_buffer, data.texture_image, width, height)?;
-
-    shared::transition_image_layout(
-        device,
-        data,
-        data.texture_image,
-        vk::Format::R8G8B8A8_SRGB,
-        vk::ImageLayout::TRANSFER_DST_OPTIMAL,
-        vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
-    )?;
-
-    // Cleanup
-    device.destroy_buffer(staging_buffer, None);
-    device.free_memory(staging_buffer_memory, None);
-
-    Ok(())
-}
-
-pub unsafe fn create_texture_image_view(device: &Device, data: &mut AppData) -> Result<()> {
-    data.texture_image_view = shared::create_image_view(
-        device,
-        data.texture_image,
-        vk::Format::R8G8B8A8_SRGB,
-        vk::ImageAspectFlags::COLOR,
-    )?;
-
-    Ok(())
-}
-
-pub unsafe fn create_texture_sampler(device: &Device, data: &mut AppData) -> Result<()> {
-    let info = vk::SamplerCreateInfo::builder()
-        .mag_filter(vk::Filter::LINEAR)
-        .min_filter(vk::Filter::LINEAR)
-        .address_mode_u(vk::SamplerAddressMode::REPEAT)
-        .address_mode_v(vk::SamplerAddressMode::REPEAT)
-        .address_mode_w(vk::SamplerAddressMode::REPEAT)
-        .anisotropy_enable(true)
-        .max_anisotropy(16.0)
-        .border_color(vk::BorderColor::INT_OPAQUE_BLACK)
-        .unnormalized_coordinates(false)
-        .compare_enable(false)
-        .compare_op(vk::CompareOp::ALWAYS)
-        .mipmap_mode(vk::SamplerMipmapMode::LINEAR);
-
-    data.texture_sampler = device.create_sampler(&info, None)?;
-
-    Ok(())
-}
\ No newline at end of file
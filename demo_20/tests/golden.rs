@@ -0,0 +1,22 @@
+// Golden-image test for the headless render of demo_20 (needs a Vulkan driver
+// such as lavapipe: `cargo test -p demo_20 -- --ignored`).
+
+use anyhow::Result;
+use golden::Image;
+use mylib::app::App;
+
+#[test]
+#[ignore = "requires a Vulkan driver (e.g. lavapipe)"]
+fn viking_room() -> Result<()> {
+    let frame = unsafe {
+        let mut app = App::create_headless(256, 192, 1)?;
+        app.time = Some(0.25);
+
+        app.render(None)?;
+        let frame = app.read_frame();
+        app.destroy();
+        frame?
+    };
+
+    golden::check("demo_20", &Image::new(frame.width, frame.height, frame.pixels), 2)
+}
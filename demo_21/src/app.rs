@@ -4,16 +4,21 @@
 )]
 
 use crate::*;
+use renderer::*;
 use app_data::AppData;
-use instance::VALIDATION_ENABLED;
-use sync_objects::MAX_FRAMES_IN_FLIGHT;
-use structs::{Mat4, UniformBufferObject};
-
+use settings::Settings;
+use assets::Assets;
+use owned::Owned;
+use pipeline::PipelineInfo;
+use structs::Mat4;
+use uniforms::UniformBufferObject;
+
+use std::rc::Rc;
 use std::time::Instant;
-use std::mem::size_of;
 use std::ptr::copy_nonoverlapping as memcpy;
 
 use anyhow::{anyhow, Result};
+use log::*;
 use cgmath::{point3, vec3, Deg};
 use winit::window::Window;
 use vulkanalia::loader::{LibloadingLoader, LIBRARY};
@@ -23,49 +28,64 @@ use vulkanalia::vk::ExtDebugUtilsExtension;
 use vulkanalia::vk::KhrSurfaceExtension;
 use vulkanalia::vk::KhrSwapchainExtension;
 
+/// The shaders and fixed functions of this chapter's pipeline.
+const PIPELINE: PipelineInfo = PipelineInfo {
+    sources: "shaders/20",
+    vert: include_bytes!("../../shaders/20/vert.spv"),
+    frag: include_bytes!("../../shaders/20/frag.spv"),
+    cache: "target/pipeline_cache/demo_21.bin",
+    blend: false,
+};
+
 /// Our Vulkan app.
-#[derive(Clone, Debug)]
+#[derive(Debug)]
 pub struct App {
     entry: Entry,
     instance: Instance,
     data: AppData,
-    device: Device,
+    device: Rc<Device>,
     frame: usize,
     pub resized: bool,
     start: Instant,
 }
 
 impl App {
-    /// Creates our Vulkan app.
-    pub unsafe fn create(window: &Window) -> Result<Self> {
+    /// Creates our Vulkan app with the given settings.
+    pub unsafe fn create(window: &Window, settings: Settings) -> Result<Self> {
         let loader: LibloadingLoader = LibloadingLoader::new(LIBRARY)?;
         let entry: Entry = Entry::new(loader).map_err(
             |b| anyhow!("{}", b))?;
-        let mut data: AppData = AppData::default();
+        let mut data: AppData = AppData { settings, ..Default::default() };
         
-        let instance: Instance = instance::create_instance(window, &entry, &mut data)?;
+        let instance: Instance = instance::create_instance(Some(window), &entry, &mut data)?;
         data.surface = vk_window::create_surface(&instance, &window, &window)?;
         physical_device::pick_physical_device(&instance, &mut data)?;
-        let device: Device = logical_device::create_logical_device(&entry, &instance, &mut data)?;
+        // Multisampling is only introduced in demo_22
+        data.msaa_samples = vk::SampleCountFlags::_1;
+        let device: Rc<Device> = logical_device::create_logical_device(&entry, &instance, &mut data)?;
         swapchain::create_swapchain(window, &instance, &device, &mut data)?;
         swapchain::create_swapchain_image_views(&device, &mut data)?;
         pipeline::create_render_pass(&instance, &device, &mut data)?;
-        descriptor::create_descriptor_set_layout(&device, &mut data)?;
-        pipeline::create_pipeline(&device, &mut data)?;
+        descriptor::create_descriptor_set_layout(&device, &mut data, &PIPELINE)?;
+        pipeline_cache::create_pipeline_cache(&instance, &device, &mut data, &PIPELINE)?;
+        pipeline::create_pipeline(&device, &mut data, &PIPELINE)?;
         command_pool::create_command_pool(&instance, &device, &mut data)?;
         depth_objects::create_depth_objects(&instance, &device, &mut data)?;
         framebuffers::create_framebuffers(&device, &mut data)?;
-        texture::create_texture_image(&instance, &device, &mut data)?;
+        let assets = Assets::new(&data.settings);
+        texture::create_texture_image(&instance, &device, &mut data, &assets.texture()?, true)?;
         texture::create_texture_image_view(&device, &mut data)?;
-        texture::create_texture_sampler(&device, &mut data)?;
-        model::load_model(&mut data)?;
+        create_texture_sampler(&device, &mut data)?;
+        model::load_model(&mut data, &assets.model()?)?;
         buffers::create_vertex_buffer(&instance, &device, &mut data)?;
         buffers::create_index_buffer(&instance, &device, &mut data)?;
-        buffers::create_uniform_buffers(&instance, &device, &mut data)?;
-        descriptor::create_descriptor_pool(&device, &mut data)?;
-        descriptor::create_descriptor_sets(&device, &mut data)?;
+        let num_images = data.swapchain_images.len();
+        buffers::create_uniform_buffers::<UniformBufferObject>(&instance, &device, &mut data, num_images)?;
+        descriptor::create_descriptor_pool(&device, &mut data, num_images)?;
+        descriptor::create_descriptor_sets::<UniformBufferObject>(&device, &mut data)?;
         command_buffers::create_command_buffers(&device, &mut data)?;
         sync_objects::create_sync_objects(&device, &mut data)?;
+        data.images_in_flight = vec![vk::Fence::null(); data.swapchain_images.len()];
 
         Ok(Self { 
             entry, 
@@ -80,8 +100,18 @@ impl App {
 
     /// Renders a frame for our Vulkan app.
     pub unsafe fn render(&mut self, window: &Window) -> Result<()> {
+        // Rebuild the pipeline when a shader source was edited
+        if self.data.shaders.changed() {
+            self.device.device_wait_idle()?;
+            // A shader that no longer matches the layouts keeps the previous pipeline
+            if let Err(e) = pipeline::create_pipeline(&self.device, &mut self.data, &PIPELINE) {
+                error!("{}", e);
+            }
+            command_buffers::create_command_buffers(&self.device, &mut self.data)?;
+        }
+
         // Get semaphore and wait
-        let in_flight_fence = self.data.in_flight_fences[self.frame];
+        let in_flight_fence = *self.data.in_flight_fences[self.frame];
 
         self.device.wait_for_fences(
             &[in_flight_fence], 
@@ -92,9 +122,9 @@ impl App {
         // Get image from swapchain
         let image_index = self.device
             .acquire_next_image_khr(
-                self.data.swapchain,
+                *self.data.swapchain,
                 u64::max_value(),
-                self.data.image_available_semaphores[self.frame],
+                *self.data.image_available_semaphores[self.frame],
                 vk::Fence::null(),
             )?
             .0 as usize;
@@ -114,10 +144,10 @@ impl App {
         self.update_uniform_buffer(image_index)?;
 
         // Commit command buffer
-        let wait_semaphores = &[self.data.image_available_semaphores[self.frame]];
+        let wait_semaphores = &[*self.data.image_available_semaphores[self.frame]];
         let wait_stages = &[vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT];
         let command_buffers = &[self.data.command_buffers[image_index]];
-        let signal_semaphores = &[self.data.render_finished_semaphores[self.frame]];
+        let signal_semaphores = &[*self.data.render_finished_semaphores[self.frame]];
         let submit_info = vk::SubmitInfo::builder()
             .wait_semaphores(wait_semaphores)
             .wait_dst_stage_mask(wait_stages)
@@ -134,7 +164,7 @@ impl App {
         )?;
 
         // Present
-        let swapchains = &[self.data.swapchain];
+        let swapchains = &[*self.data.swapchain];
         let image_indices = &[image_index as u32];
         let present_info = vk::PresentInfoKHR::builder()
             .wait_semaphores(signal_semaphores)
@@ -154,7 +184,7 @@ impl App {
         }
 
         // Update current frame index
-        self.frame = (self.frame + 1) % MAX_FRAMES_IN_FLIGHT;
+        self.frame = (self.frame + 1) % self.data.in_flight_fences.len();
 
         Ok(())
     }
@@ -194,15 +224,7 @@ impl App {
         let ubo = UniformBufferObject { model, view, proj };
 
         // Update uniform buffer with MVP matrix
-        let memory = self.device.map_memory(
-            self.data.uniform_buffers_memory[image_index],
-            0,
-            size_of::<UniformBufferObject>() as u64,
-            vk::MemoryMapFlags::empty(),
-        )?;
-        memcpy(&ubo, memory.cast(), 1);
-
-        self.device.unmap_memory(self.data.uniform_buffers_memory[image_index]);
+        memcpy(&ubo, self.data.uniform_buffers[image_index].memory().mapped.cast(), 1);
 
         Ok(())
     }
@@ -210,24 +232,35 @@ impl App {
     /// Recreates the swapchain for our Vulkan app.
     #[rustfmt::skip]
     unsafe fn recreate_swapchain(&mut self, window: &Window) -> Result<()> {
-        self.device.device_wait_idle()?;
-        self.destroy_swapchain();  // destory
+        // Only the frames in flight use what is recreated below
+        sync_objects::wait_for_frames(&self.device, &self.data)?;
 
-        // recreate
+        // recreate (replacing a handle destroys the previous one)
+        let format = self.data.swapchain_format;
+        let old_num_images = self.data.swapchain_images.len();
         swapchain::create_swapchain(window, &self.instance, &self.device, &mut self.data)?;
         swapchain::create_swapchain_image_views(&self.device, &mut self.data)?;
-        pipeline::create_render_pass(&self.instance, &self.device, &mut self.data)?;
-        pipeline::create_pipeline(&self.device, &mut self.data)?;
+
+        // The viewport and scissor are dynamic, so only a new format invalidates the pipeline
+        if self.data.swapchain_format != format {
+            pipeline::create_render_pass(&self.instance, &self.device, &mut self.data)?;
+            pipeline::create_pipeline(&self.device, &mut self.data, &PIPELINE)?;
+        }
         depth_objects::create_depth_objects(&self.instance, &self.device, &mut self.data)?;
         framebuffers::create_framebuffers(&self.device, &mut self.data)?;
-        buffers::create_uniform_buffers(&self.instance, &self.device, &mut self.data)?;
-        descriptor::create_descriptor_pool(&self.device, &mut self.data)?;
-        descriptor::create_descriptor_sets(&self.device, &mut self.data)?;
-        
+
+        // The per-image uniform buffers and descriptor sets only depend on the number of images
+        let num_images = self.data.swapchain_images.len();
+        if num_images != old_num_images {
+            buffers::create_uniform_buffers::<UniformBufferObject>(&self.instance, &self.device, &mut self.data, num_images)?;
+            descriptor::create_descriptor_pool(&self.device, &mut self.data, num_images)?;
+            descriptor::create_descriptor_sets::<UniformBufferObject>(&self.device, &mut self.data)?;
+        }
+
         command_buffers::create_command_buffers(&self.device, &mut self.data)?;
-        
-        self.data.images_in_flight
-            .resize(self.data.swapchain_images.len(), vk::Fence::null());
+
+        // The images of the new swapchain are not in flight yet
+        self.data.images_in_flight = vec![vk::Fence::null(); self.data.swapchain_images.len()];
         
         Ok(())
     }
@@ -237,73 +270,47 @@ impl App {
     pub unsafe fn destroy(&mut self) {
         self.device.device_wait_idle().unwrap();
 
-        self.destroy_swapchain();
-        self.data.in_flight_fences
-            .iter()
-            .for_each(|f| 
-                self.device.destroy_fence(*f, None));
-        self.data.render_finished_semaphores
-            .iter()
-            .for_each(|s| 
-                self.device.destroy_semaphore(*s, None));
-        self.data.image_available_semaphores
-            .iter().
-            for_each(|s| 
-                self.device.destroy_semaphore(*s, None));
-        self.device.free_memory(self.data.index_buffer_memory, None);
-        self.device.destroy_buffer(self.data.index_buffer, None);
-        self.device.free_memory(self.data.vertex_buffer_memory, None);
-        self.device.destroy_buffer(self.data.vertex_buffer, None);
-        self.device.destroy_sampler(self.data.texture_sampler, None);
-        self.device.destroy_image_view(self.data.texture_image_view, None);
-        self.device.free_memory(self.data.texture_image_memory, None);
-        self.device.destroy_image(self.data.texture_image, None);
-
-        self.device.destroy_command_pool(self.data.command_pool, None);
-        self.device.destroy_descriptor_set_layout(self.data.descriptor_set_layout, None);
+        if let Err(e) = pipeline_cache::save_pipeline_cache(&self.device, &self.data, &PIPELINE) {
+            warn!("Failed to save the pipeline cache: {}", e);
+        }
+
+        // Dropping the app data destroys the handles it owns (in field order)
+        let AppData { surface, messenger, allocator, .. } = std::mem::take(&mut self.data);
+
+        tracker::report_leaks();
+        allocator.destroy(&self.device);
         self.device.destroy_device(None);
-        self.instance.destroy_surface_khr(self.data.surface, None);
+        self.instance.destroy_surface_khr(surface, None);
 
-        if VALIDATION_ENABLED {
-            self.instance.destroy_debug_utils_messenger_ext(self.data.messenger, None);
+        if !messenger.is_null() {
+            self.instance.destroy_debug_utils_messenger_ext(messenger, None);
         }
 
         self.instance.destroy_instance(None);
     }
+}
 
-    /// Destroys the parts of our Vulkan app related to the swapchain.
-    #[rustfmt::skip]
-    unsafe fn destroy_swapchain(&mut self) {
-        self.device.free_command_buffers(self.data.command_pool, &self.data.command_buffers);
-        
-        // destory descriptor pool
-        self.device.destroy_descriptor_pool(self.data.descriptor_pool, None);
-        // destory uniform buffers
-        self.data.uniform_buffers_memory
-            .iter()
-            .for_each(|m| 
-                self.device.free_memory(*m, None));
-        self.data.uniform_buffers
-            .iter()
-            .for_each(|b| 
-                self.device.destroy_buffer(*b, None));
-        
-        // destory depth image
-        self.device.destroy_image_view(self.data.depth_image_view, None);
-        self.device.free_memory(self.data.depth_image_memory, None);
-        self.device.destroy_image(self.data.depth_image, None);
-
-        self.data.framebuffers
-            .iter()
-            .for_each(|f| 
-                self.device.destroy_framebuffer(*f, None));
-        self.device.destroy_pipeline(self.data.pipeline, None);
-        self.device.destroy_pipeline_layout(self.data.pipeline_layout, None);
-        self.device.destroy_render_pass(self.data.render_pass, None);
-        self.data.swapchain_image_views
-            .iter()
-            .for_each(|v| 
-                self.device.destroy_image_view(*v, None));
-        self.device.destroy_swapchain_khr(self.data.swapchain, None);
-    }
-}
\ No newline at end of file
+/// Creates a texture sampler that skips the more detailed half of the mip
+/// levels (to make the effect of mipmapping visible).
+unsafe fn create_texture_sampler(device: &Rc<Device>, data: &mut AppData) -> Result<()> {
+    let info = vk::SamplerCreateInfo::builder()
+        .mag_filter(vk::Filter::LINEAR)
+        .min_filter(vk::Filter::LINEAR)
+        .address_mode_u(vk::SamplerAddressMode::REPEAT)
+        .address_mode_v(vk::SamplerAddressMode::REPEAT)
+        .address_mode_w(vk::SamplerAddressMode::REPEAT)
+        .anisotropy_enable(true)
+        .max_anisotropy(16.0)
+        .border_color(vk::BorderColor::INT_OPAQUE_BLACK)
+        .unnormalized_coordinates(false)
+        .compare_enable(false)
+        .compare_op(vk::CompareOp::ALWAYS)
+        .mipmap_mode(vk::SamplerMipmapMode::LINEAR)
+        .min_lod(data.mip_levels as f32 / 2.0)
+        .max_lod(data.mip_levels as f32)
+        .mip_lod_bias(0.0);
+
+    data.texture_sampler = Owned::new(device, device.create_sampler(&info, None)?);
+
+    Ok(())
+}
@@ -1,27 +0,0 @@
-//================================================
-// Framebuffers
-//================================================
-use crate::app_data::AppData;
-
-use anyhow::Result;
-use vulkanalia::prelude::v1_0::*;
-
-pub unsafe fn create_framebuffers(device: &Device, data: &mut AppData) -> Result<()> {
-    data.framebuffers = data
-        .swapchain_image_views
-        .iter()
-        .map(|i| {
-            let attachments = &[*i, data.depth_image_view];
-            let create_info = vk::FramebufferCreateInfo::builder()
-                .render_pass(data.render_pass)
-                .attachments(attachments)
-                .width(data.swapchain_extent.width)
-                .height(data.swapchain_extent.height)
-                .layers(1);
-
-            device.create_framebuffer(&create_info, None)
-        })
-        .collect::<Result<Vec<_>, _>>()?;
-
-    Ok(())
-}
\ No newline at end of file
@@ -1,15 +1,21 @@
 //================================================
 // Command Buffers
 //================================================
-use crate::app_data::AppData;
+use renderer::app_data::AppData;
+use renderer::pipeline;
 
 use anyhow::Result;
 use vulkanalia::prelude::v1_0::*;
 
 pub unsafe fn create_command_buffers(device: &Device, data: &mut AppData) -> Result<()> {
+    // Free the command buffers recorded for the previous swapchain (if any)
+    if !data.command_buffers.is_empty() {
+        device.free_command_buffers(*data.command_pool, &data.command_buffers);
+    }
+
     // Allocate
     let allocate_info = vk::CommandBufferAllocateInfo::builder()
-        .command_pool(data.command_pool)
+        .command_pool(*data.command_pool)
         .level(vk::CommandBufferLevel::PRIMARY)
         .command_buffer_count(data.framebuffers.len() as u32);
 
@@ -40,22 +46,23 @@ pub unsafe fn create_command_buffers(device: &Device, data: &mut AppData) -> Res
 
         let clear_values = &[color_clear_value, depth_clear_value];
         let info = vk::RenderPassBeginInfo::builder()
-            .render_pass(data.render_pass)
-            .framebuffer(data.framebuffers[i])
+            .render_pass(*data.render_pass)
+            .framebuffer(*data.framebuffers[i])
             .render_area(render_area)
             .clear_values(clear_values);
 
         device.cmd_begin_render_pass(*command_buffer, &info, vk::SubpassContents::INLINE);
         
         // bind
-        device.cmd_bind_pipeline(*command_buffer, vk::PipelineBindPoint::GRAPHICS, data.pipeline);
-        device.cmd_bind_vertex_buffers(*command_buffer, 0, &[data.vertex_buffer], &[0]);
-        device.cmd_bind_index_buffer(*command_buffer, data.index_buffer, 0, vk::IndexType::UINT32);
+        device.cmd_bind_pipeline(*command_buffer, vk::PipelineBindPoint::GRAPHICS, *data.pipeline);
+        pipeline::set_viewport(device, *command_buffer, data.swapchain_extent);
+        device.cmd_bind_vertex_buffers(*command_buffer, 0, &[*data.vertex_buffer], &[0]);
+        device.cmd_bind_index_buffer(*command_buffer, *data.index_buffer, 0, vk::IndexType::UINT32);
 
         device.cmd_bind_descriptor_sets(
             *command_buffer,
             vk::PipelineBindPoint::GRAPHICS,
-            data.pipeline_layout,
+            *data.pipeline_layout,
             0,
             &[data.descriptor_sets[i]],
             &[],
@@ -5,22 +5,27 @@ use anyhow::Result;
 use winit::dpi::LogicalSize;
 use winit::event::{Event, WindowEvent};
 use winit::event_loop::{ControlFlow, EventLoop};
-use winit::window::{Window, WindowBuilder};
+use winit::window::{Fullscreen, Window, WindowBuilder};
 use mylib::app::App;
+use renderer::settings::Settings;
 
 #[rustfmt::skip]
 fn main() -> Result<()> {
     pretty_env_logger::init();
 
+    // --- Settings ---
+    let settings: Settings = Settings::load()?;
+
     // --- Window ---
     let event_loop: EventLoop<()> = EventLoop::new();
     let window: Window = WindowBuilder::new()
         .with_title("Vulkan Tutorial (Rust)")
-        .with_inner_size(LogicalSize::new(1024, 768))
+        .with_inner_size(LogicalSize::new(settings.width, settings.height))
+        .with_fullscreen(settings.fullscreen.then_some(Fullscreen::Borderless(None)))
         .build(&event_loop)?;
 
     // --- App ---
-    let mut app: App = unsafe { App::create(&window)? };
+    let mut app: App = unsafe { App::create(&window, settings)? };
     let mut destroying: bool = false;
     let mut minimized:  bool = false;
     
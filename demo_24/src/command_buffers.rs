@@ -1,16 +1,21 @@
 //================================================
 // Command Buffers
 //================================================
-use crate::app_data::AppData;
+use renderer::app_data::AppData;
 
 use anyhow::Result;
 use vulkanalia::prelude::v1_0::*;
 
+/// Allocates a command buffer from the pool of each swapchain image that does
+/// not have one yet (the buffers of images that no longer exist were freed
+/// with their pools).
 pub unsafe fn create_command_buffers(device: &Device, data: &mut AppData) -> Result<()> {
     let num_images = data.swapchain_images.len();
-    for image_index in 0..num_images {
+    data.command_buffers.truncate(num_images);
+
+    for image_index in data.command_buffers.len()..num_images {
         let allocate_info = vk::CommandBufferAllocateInfo::builder()
-            .command_pool(data.command_pools[image_index])
+            .command_pool(*data.command_pools[image_index])
             .level(vk::CommandBufferLevel::PRIMARY)
             .command_buffer_count(1);
 
@@ -1,20 +1,3 @@
 pub mod app;
-pub mod app_data;
-pub mod instance;
-pub mod error;
-pub mod structs;
-pub mod physical_device;
-pub mod logical_device;
-pub mod swapchain;
-pub mod pipeline;
-pub mod framebuffers;
-pub mod command_pool;
 pub mod command_buffers;
-pub mod sync_objects;
-pub mod shared;
-pub mod buffers;
-pub mod descriptor;
-pub mod texture;
-pub mod depth_objects;
-pub mod model;
-pub mod color_objects;
\ No newline at end of file
+pub mod uniforms;
\ No newline at end of file
@@ -1,281 +0,0 @@
-//================================================
-// Texture
-//================================================
-use crate::app_data::AppData;
-use crate::shared;
-
-use std::fs::File;
-use std::ptr::copy_nonoverlapping as memcpy;
-
-use anyhow::{Result, anyhow};
-use vulkanalia::prelude::v1_0::*;
-
-pub unsafe fn create_texture_image(instance: &Instance, device: &Device, data: &mut AppData) -> Result<()> {
-    // ----------------------------------------
-    // Texture image
-    // ----------------------------------------
-    // 1. Load texture image data
-    let image = File::open("./resources/viking_room.png")?;
-
-    let decoder = png::Decoder::new(image);
-    let mut reader = decoder.read_info()?;
-
-    let mut pixels = vec![0; reader.info().raw_bytes()];
-    reader.next_frame(&mut pixels)?;
-
-    let size = reader.info().raw_bytes() as u64;
-    let (width, height) = reader.info().size();
-    data.mip_levels = (width.max(height) as f32).log2().floor() as u32 + 1;
-
-    if width != 1024 || height != 1024 || reader.info().color_type != png::ColorType::Rgba {
-        panic!("Invalid texture image (use https://kylemayes.github.io/vulkanalia/images/viking_room.png).");
-    }
-
-    // 2. Create a staging buffer visible to the cpu
-    let (staging_buffer, staging_buffer_memory) = shared::create_buffer(
-        instance,
-        device,
-        data,
-        size,
-        vk::BufferUsageFlags::TRANSFER_SRC,
-        vk::MemoryPropertyFlags::HOST_COHERENT | vk::MemoryPropertyFlags::HOST_VISIBLE,
-    )?;
-
-    // 3. Copy data to staging buffer
-    let memory = device.map_memory(staging_buffer_memory, 0, size, vk::MemoryMapFlags::empty())?;
-
-    memcpy(pixels.as_ptr(), memory.cast(), pixels.len());
-
-    device.unmap_memory(staging_buffer_memory);
-
-    // 4. Create texture image object
-    let (texture_image, texture_image_memory) = shared::create_image(
-        instance,
-        device,
-        data,
-        width,
-        height,
-        data.mip_levels, 
-        vk::SampleCountFlags::_1,
-        vk::Format::R8G8B8A8_SRGB,
-        vk::ImageTiling::OPTIMAL, 
-        vk::ImageUsageFlags::SAMPLED | 
-        vk::ImageUsageFlags::TRANSFER_DST | 
-        vk::ImageUsageFlags::TRANSFER_SRC,
-        vk::MemoryPropertyFlags::DEVICE_LOCAL,
-    )?;
-
-    data.texture_image = texture_image;
-    data.texture_image_memory = texture_image_memory;
-
-    // ----------------------------------------
-    // Layout transition
-    // ----------------------------------------
-    // Transition + Copy (image)
-    shared::transition_image_layout(
-        device,
-        data,
-        data.texture_image,
-        vk::Format::R8G8B8A8_SRGB,
-        vk::ImageLayout::UNDEFINED,
-        vk::ImageLayout::TRANSFER_DST_OPTIMAL, 
-        data.mip_levels,
-    )?;
-
-    // Copy buffer to image
-    shared::copy_buffer_to_image(device, data, staging_buffer, data.texture_image, width, height)?;
-
-    // Cleanup
-    device.destroy_buffer(staging_buffer, None);
-    device.free_memory(staging_buffer_memory, None);
-
-    // Mipmaps
-    generate_mipmaps(
-        instance,
-        device,
-        data,
-        data.texture_image,
-        vk::Format::R8G8B8A8_SRGB,
-        width,
-        height,
-        data.mip_levels,
-    )?;
-
-    Ok(())
-}
-
-unsafe fn generate_mipmaps(
-    instance: &Instance,
-    device: &Device,
-    data: &AppData,
-    image: vk::Image,
-    format: vk::Format,
-    width: u32,
-    height: u32,
-    mip_levels: u32,
-) -> Result<()> {
-    // Support
-    if !instance
-        .get_physical_device_format_properties(data.physical_device, format)
-        .optimal_tiling_features
-        .contains(vk::FormatFeatureFlags::SAMPLED_IMAGE_FILTER_LINEAR)
-    {
-        return Err(anyhow!("Texture image format does not support linear blitting!"));
-    }
-
-    // Mipmaps
-    let command_buffer = shared::begin_single_time_commands(device, data)?;
-
-    let subresource = vk::ImageSubresourceRange::builder()
-        .aspect_mask(vk::ImageAspectFlags::COLOR)
-        .base_array_layer(0)
-        .layer_count(1)
-        .level_count(1);
-
-    let mut barrier = vk::ImageMemoryBarrier::builder()
-        .image(image)
-        .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
-        .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
-        .subresource_range(subresource);
-
-    let mut mip_width = width;
-    let mut mip_height = height;
-
-    for i in 1..mip_levels {
-        barrier.subresource_range.base_mip_level = i - 1;
-        barrier.old_layout = vk::ImageLayout::TRANSFER_DST_OPTIMAL;
-        barrier.new_layout = vk::ImageLayout::TRANSFER_SRC_OPTIMAL;
-        barrier.src_access_mask = vk::AccessFlags::TRANSFER_WRITE;
-        barrier.dst_access_mask = vk::AccessFlags::TRANSFER_READ;
-
-        device.cmd_pipeline_barrier(
-            command_buffer,
-            vk::PipelineStageFlags::TRANSFER,
-            vk::PipelineStageFlags::TRANSFER,
-            vk::DependencyFlags::empty(),
-            &[] as &[vk::MemoryBarrier],
-            &[] as &[vk::BufferMemoryBarrier],
-            &[barrier],
-        );
-
-        let src_subresource = vk::ImageSubresourceLayers::builder()
-            .aspect_mask(vk::ImageAspectFlags::COLOR)
-            .mip_level(i - 1)
-            .base_array_layer(0)
-            .layer_count(1);
-
-        let dst_subresource = vk::ImageSubresourceLayers::builder()
-            .aspect_mask(vk::ImageAspectFlags::COLOR)
-            .mip_level(i)
-            .base_array_layer(0)
-            .layer_count(1);
-
-        let blit = vk::ImageBlit::builder()
-            .src_offsets([
-                vk::Offset3D { x: 0, y: 0, z: 0 },
-                vk::Offset3D {
-                    x: mip_width as i32,
-                    y: mip_height as i32,
-                    z: 1,
-                },
-            ])
-            .src_subresource(src_subresource)
-            .dst_offsets([
-                vk::Offset3D { x: 0, y: 0, z: 0 },
-                vk::Offset3D {
-                    x: (if mip_width > 1 { mip_width / 2 } else { 1 }) as i32,
-                    y: (if mip_height > 1 { mip_height / 2 } else { 1 }) as i32,
-                    z: 1,
-                },
-            ])
-            .dst_subresource(dst_subresource);
-
-        device.cmd_blit_image(
-            command_buffer,
-            image,
-            vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
-            image,
-            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
-            &[blit],
-            vk::Filter::LINEAR,
-        );
-
-        barrier.old_layout = vk::ImageLayout::TRANSFER_SRC_OPTIMAL;
-        barrier.new_layout = vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL;
-        barrier.src_access_mask = vk::AccessFlags::TRANSFER_READ;
-        barrier.dst_access_mask = vk::AccessFlags::SHADER_READ;
-
-        device.cmd_pipeline_barrier(
-            command_buffer,
-            vk::PipelineStageFlags::TRANSFER,
-            vk::PipelineStageFlags::FRAGMENT_SHADER,
-            vk::DependencyFlags::empty(),
-            &[] as &[vk::MemoryBarrier],
-            &[] as &[vk::BufferMemoryBarrier],
-            &[barrier],
-        );
-
-        if mip_width > 1 {
-            mip_width /= 2;
-        }
-
-        if mip_height > 1 {
-            mip_height /= 2;
-        }
-    }
-
-    barrier.subresource_range.base_mip_level = mip_levels - 1;
-    barrier.old_layout = vk::ImageLayout::TRANSFER_DST_OPTIMAL;
-    barrier.new_layout = vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL;
-    barrier.src_access_mask = vk::AccessFlags::TRANSFER_WRITE;
-    barrier.dst_access_mask = vk::AccessFlags::SHADER_READ;
-
-    device.cmd_pipeline_barrier(
-        command_buffer,
-        vk::PipelineStageFlags::TRANSFER,
-        vk::PipelineStageFlags::FRAGMENT_SHADER,
-        vk::DependencyFlags::empty(),
-        &[] as &[vk::MemoryBarrier],
-        &[] as &[vk::BufferMemoryBarrier],
-        &[barrier],
-    );
-
-    shared::end_single_time_commands(device, data, command_buffer)?;
-
-    Ok(())
-}
-
-pub unsafe fn create_texture_image_view(device: &Device, data: &mut AppData) -> Result<()> {
-    data.texture_image_view = shared::create_image_view(
-        device,
-        data.texture_image,
-        vk::Format::R8G8B8A8_SRGB,
-        vk::ImageAspectFlags::COLOR,
-        data.mip_levels,
-    )?;
-
-    Ok(())
-}
-
-pub unsafe fn create_texture_sampler(device: &Device, data: &mut AppData) -> Result<()> {
-    let info = vk::SamplerCreateInfo::builder()
-        .mag_filter(vk::Filter::LINEAR)
-        .min_filter(vk::Filter::LINEAR)
-        .address_mode_u(vk::SamplerAddressMode::REPEAT)
-        .address_mode_v(vk::SamplerAddressMode::REPEAT)
-        .address_mode_w(vk::SamplerAddressMode::REPEAT)
-        .anisotropy_enable(true)
-        .max_anisotropy(16.0)
-        .border_color(vk::BorderColor::INT_OPAQUE_BLACK)
-        .unnormalized_coordinates(false)
-        .compare_enable(false)
-        .compare_op(vk::CompareOp::ALWAYS)
-        .mipmap_mode(vk::SamplerMipmapMode::LINEAR)
-        .min_lod(0.0)
-        .max_lod(data.mip_levels as f32)
-        .mip_lod_bias(0.0);
-
-    data.texture_sampler = device.create_sampler(&info, None)?;
-
-    Ok(())
-}
\ No newline at end of file
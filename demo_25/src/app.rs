@@ -4,16 +4,27 @@
 )]
 
 use crate::*;
+use renderer::*;
 use app_data::AppData;
-use instance::VALIDATION_ENABLED;
-use sync_objects::MAX_FRAMES_IN_FLIGHT;
-use structs::{Mat4, UniformBufferObject};
-
-use std::time::Instant;
+use settings::Settings;
+use assets::Assets;
+use texture::TextureSource;
+use camera::{Camera, CameraController};
+use input::{Action, Bindings, Input};
+use pipeline::PipelineInfo;
+use structs::Mat4;
+use uniforms::UniformBufferObject;
+use offscreen::Frame;
+use frame_stats::FrameStats;
+use queries::DrawStatistics;
+
+use std::rc::Rc;
+use std::time::{Duration, Instant};
 use std::mem::size_of;
 use std::ptr::{copy_nonoverlapping as memcpy, slice_from_raw_parts};
 
 use anyhow::{anyhow, Result};
+use log::*;
 use cgmath::{point3, vec3, Deg};
 use winit::window::Window;
 use vulkanalia::loader::{LibloadingLoader, LIBRARY};
@@ -23,53 +34,67 @@ use vulkanalia::vk::ExtDebugUtilsExtension;
 use vulkanalia::vk::KhrSurfaceExtension;
 use vulkanalia::vk::KhrSwapchainExtension;
 
+/// The shaders and fixed functions of this chapter's pipeline.
+const PIPELINE: PipelineInfo = PipelineInfo {
+    sources: "shaders/23",
+    vert: include_bytes!("../../shaders/23/vert.spv"),
+    frag: include_bytes!("../../shaders/23/frag.spv"),
+    cache: "target/pipeline_cache/demo_25.bin",
+    blend: true,
+};
+
+/// The most models that are drawn (each with its own secondary command buffer and queries).
+const MAX_MODELS: usize = 4;
+
 /// Our Vulkan app.
-#[derive(Clone, Debug)]
+#[derive(Debug)]
 pub struct App {
     entry: Entry,
     instance: Instance,
     data: AppData,
-    device: Device,
+    device: Rc<Device>,
     frame: usize,
+    /// The swapchain image or render target the latest frame was rendered into.
+    image_index: usize,
     pub resized: bool,
+    /// Saves the next rendered frame as a PNG screenshot.
+    pub screenshot: bool,
     start: Instant,
+    /// When the previous frame started (camera movement scales with the time since).
+    last_frame: Instant,
+    /// The timings of the rendered frames.
+    pub stats: FrameStats,
+    /// When the window title last showed the frame stats.
+    last_title: Instant,
+    /// What the GPU did for each model in the latest frame that finished (by
+    /// model index, `None` for the models that were not drawn).
+    pub draws: Vec<Option<DrawStatistics>>,
+    pub camera: Camera,
+    /// Moves the camera from the actions of the input.
+    pub controller: CameraController,
+    /// The window's input since the previous frame.
+    pub input: Input,
+    /// Overrides the animation time in seconds (for reproducible renders).
+    pub time: Option<f32>,
     pub models: usize,
 }
 
 impl App {
-    /// Creates our Vulkan app.
-    pub unsafe fn create(window: &Window) -> Result<Self> {
+    /// Creates our Vulkan app with the given settings.
+    pub unsafe fn create(window: &Window, settings: Settings) -> Result<Self> {
         let loader: LibloadingLoader = LibloadingLoader::new(LIBRARY)?;
         let entry: Entry = Entry::new(loader).map_err(
             |b| anyhow!("{}", b))?;
-        let mut data: AppData = AppData::default();
+        let bindings = Bindings::load(settings.bindings.as_deref())?;
+        let mut data: AppData = AppData { settings, ..Default::default() };
         
-        let instance: Instance = instance::create_instance(window, &entry, &mut data)?;
+        let instance: Instance = instance::create_instance(Some(window), &entry, &mut data)?;
         data.surface = vk_window::create_surface(&instance, &window, &window)?;
         physical_device::pick_physical_device(&instance, &mut data)?;
-        let device: Device = logical_device::create_logical_device(&entry, &instance, &mut data)?;
+        let device: Rc<Device> = logical_device::create_logical_device(&entry, &instance, &mut data)?;
         swapchain::create_swapchain(window, &instance, &device, &mut data)?;
         swapchain::create_swapchain_image_views(&device, &mut data)?;
-        pipeline::create_render_pass(&instance, &device, &mut data)?;
-        descriptor::create_descriptor_set_layout(&device, &mut data)?;
-        pipeline::create_pipeline(&device, &mut data)?;
-        
-        command_pool::create_command_pools(&instance, &device, &mut data)?;
-        
-        color_objects::create_color_objects(&instance, &device, &mut data)?;
-        depth_objects::create_depth_objects(&instance, &device, &mut data)?;
-        framebuffers::create_framebuffers(&device, &mut data)?;
-        texture::create_texture_image(&instance, &device, &mut data)?;
-        texture::create_texture_image_view(&device, &mut data)?;
-        texture::create_texture_sampler(&device, &mut data)?;
-        model::load_model(&mut data)?;
-        buffers::create_vertex_buffer(&instance, &device, &mut data)?;
-        buffers::create_index_buffer(&instance, &device, &mut data)?;
-        buffers::create_uniform_buffers(&instance, &device, &mut data)?;
-        descriptor::create_descriptor_pool(&device, &mut data)?;
-        descriptor::create_descriptor_sets(&device, &mut data)?;
-        command_buffers::create_command_buffers(&device, &mut data)?;
-        sync_objects::create_sync_objects(&device, &mut data)?;
+        Self::create_resources(&instance, &device, &mut data)?;
 
         Ok(Self { 
             entry, 
@@ -77,16 +102,121 @@ impl App {
             data, 
             device, 
             frame: 0,
+            image_index: 0,
+            resized: false,
+            screenshot: false,
+            start: Instant::now(),
+            last_frame: Instant::now(),
+            stats: FrameStats::default(),
+            last_title: Instant::now(),
+            draws: vec![],
+            camera: Camera::look_at(point3(6.0, 0.0, 2.0), point3(0.0, 0.0, 0.0)),
+            controller: CameraController::default(),
+            input: Input::new(bindings),
+            time: None,
+            models: 1,
+        })
+    }
+
+    /// Creates our Vulkan app without a window, rendering into `image_count`
+    /// offscreen render targets of the given size.
+    pub unsafe fn create_headless(width: u32, height: u32, image_count: usize) -> Result<Self> {
+        let loader: LibloadingLoader = LibloadingLoader::new(LIBRARY)?;
+        let entry: Entry = Entry::new(loader).map_err(
+            |b| anyhow!("{}", b))?;
+        let mut data: AppData = AppData { headless: true, ..Default::default() };
+
+        let instance: Instance = instance::create_instance(None, &entry, &mut data)?;
+        physical_device::pick_physical_device(&instance, &mut data)?;
+        let device: Rc<Device> = logical_device::create_logical_device(&entry, &instance, &mut data)?;
+        let extent = vk::Extent2D { width, height };
+        offscreen::create_render_targets(&instance, &device, &mut data, extent, image_count)?;
+        Self::create_resources(&instance, &device, &mut data)?;
+
+        Ok(Self {
+            entry,
+            instance,
+            data,
+            device,
+            frame: 0,
+            image_index: 0,
             resized: false,
+            screenshot: false,
             start: Instant::now(),
+            last_frame: Instant::now(),
+            stats: FrameStats::default(),
+            last_title: Instant::now(),
+            draws: vec![],
+            camera: Camera::look_at(point3(6.0, 0.0, 2.0), point3(0.0, 0.0, 0.0)),
+            controller: CameraController::default(),
+            input: Input::default(),
+            time: None,
             models: 1,
         })
     }
 
-    /// Renders a frame for our Vulkan app.
-    pub unsafe fn render(&mut self, window: &Window) -> Result<()> {
+    /// Creates everything that is drawn with once the presentation images exist.
+    unsafe fn create_resources(instance: &Instance, device: &Rc<Device>, data: &mut AppData) -> Result<()> {
+        pipeline::create_render_pass(instance, device, data)?;
+        descriptor::create_descriptor_set_layout(device, data, &PIPELINE)?;
+        pipeline_cache::create_pipeline_cache(instance, device, data, &PIPELINE)?;
+        pipeline::create_pipeline(device, data, &PIPELINE)?;
+        
+        // The resources recorded or written every frame only exist per frame in flight
+        let frames_in_flight = data.settings.frames_in_flight;
+        command_pool::create_command_pools(instance, device, data, frames_in_flight)?;
+        
+        color_objects::create_color_objects(instance, device, data)?;
+        depth_objects::create_depth_objects(instance, device, data)?;
+        framebuffers::create_framebuffers(device, data)?;
+        texture::create_texture_sampler(device, data)?;
+        // Each object of the model is drawn with the texture of its material (or the one given)
+        let assets = Assets::new(&data.settings);
+        model::load_model(data, &assets.model()?)?;
+        if data.settings.texture.is_some() {
+            let texture = TextureSource::File(assets.texture()?);
+            for material in &mut data.materials {
+                material.diffuse_texture = Some(texture.clone());
+            }
+        }
+        texture::create_material_textures(instance, device, data)?;
+        buffers::create_vertex_buffer(instance, device, data)?;
+        buffers::create_index_buffer(instance, device, data)?;
+        buffers::create_uniform_buffers::<UniformBufferObject>(instance, device, data, frames_in_flight)?;
+        descriptor::create_descriptor_pool(device, data, frames_in_flight * data.materials.len())?;
+        descriptor::create_material_descriptor_sets::<UniformBufferObject>(device, data)?;
+        command_buffers::create_command_buffers(device, data)?;
+        sync_objects::create_sync_objects(device, data)?;
+        frame_stats::create_timestamp_queries(instance, device, data, frames_in_flight)?;
+        queries::create_query_pools(device, data, frames_in_flight, MAX_MODELS as u32)?;
+
+        for heap in data.allocator.stats() {
+            debug!("{}", heap);
+        }
+
+        Ok(())
+    }
+
+    /// Renders a frame for our Vulkan app (into the next offscreen render
+    /// target when headless, which has no window).
+    pub unsafe fn render(&mut self, window: Option<&Window>) -> Result<()> {
+        let start = Instant::now();
+        let delta = start - self.last_frame;
+        self.last_frame = start;
+
+        // Rebuild the pipeline when a shader source was edited
+        if self.data.shaders.changed() {
+            self.device.device_wait_idle()?;
+            // A shader that no longer matches the layouts keeps the previous pipeline
+            if let Err(e) = pipeline::create_pipeline(&self.device, &mut self.data, &PIPELINE) {
+                error!("{}", e);
+            }
+        }
+
+        self.handle_input(window, delta)?;
+
         // Get semaphore and wait
-        let in_flight_fence = self.data.in_flight_fences[self.frame];
+        let in_flight_fence = *self.data.in_flight_fences[self.frame];
 
         self.device.wait_for_fences(
             &[in_flight_fence], 
@@ -94,36 +224,47 @@ impl App {
             u64::max_value()
         )?;
 
-        // Get image from swapchain
-        let image_index = self.device
-            .acquire_next_image_khr(
-                self.data.swapchain,
-                u64::max_value(),
-                self.data.image_available_semaphores[self.frame],
-                vk::Fence::null(),
-            )?
-            .0 as usize;
-
-        let image_in_flight = self.data.images_in_flight[image_index];
-        if !image_in_flight.is_null() {
-            self.device.wait_for_fences(
-                &[image_in_flight], 
-                true, 
-                u64::max_value()
-            )?;
+        // The frame that used this frame in flight before has finished on the GPU
+        let count = self.data.in_flight_fences.len();
+        if let Some(number) = self.stats.len().checked_sub(count) {
+            if let Some(gpu) = frame_stats::get_gpu_time(&self.device, &self.data, self.frame)? {
+                self.stats.record_gpu(number, gpu);
+            }
+
+            self.draws = queries::get_draw_statistics(&self.device, &self.data, self.frame, MAX_MODELS)?;
         }
 
-        self.data.images_in_flight[image_index] = in_flight_fence;
+        // Get image from swapchain (or the next render target when headless)
+        let image_index = if self.data.headless {
+            offscreen::next_render_target(self.image_index, self.data.swapchain_images.len())
+        } else {
+            self.device
+                .acquire_next_image_khr(
+                    *self.data.swapchain,
+                    u64::max_value(),
+                    *self.data.image_available_semaphores[self.frame],
+                    vk::Fence::null(),
+                )?
+                .0 as usize
+        };
+        self.image_index = image_index;
 
+        // The fence of this frame guards its command buffers and uniform buffer
+        // (the image itself is guarded by the acquire semaphore)
         self.update_command_buffer(image_index)?;
         // Update uniform buffer with new transformation matrix
-        self.update_uniform_buffer(image_index)?;
+        self.update_uniform_buffer()?;
 
-        // Commit command buffer
-        let wait_semaphores = &[self.data.image_available_semaphores[self.frame]];
+        // Commit command buffer (headless frames are neither acquired nor presented)
+        let wait_semaphores = &[*self.data.image_available_semaphores[self.frame]];
         let wait_stages = &[vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT];
-        let command_buffers = &[self.data.command_buffers[image_index]];
-        let signal_semaphores = &[self.data.render_finished_semaphores[self.frame]];
+        let command_buffers = &[self.data.command_buffers[self.frame]];
+        let signal_semaphores = &[*self.data.render_finished_semaphores[self.frame]];
+        let (wait_semaphores, signal_semaphores) = if self.data.headless {
+            (&[][..], &[][..])
+        } else {
+            (&wait_semaphores[..], &signal_semaphores[..])
+        };
         let submit_info = vk::SubmitInfo::builder()
             .wait_semaphores(wait_semaphores)
             .wait_dst_stage_mask(wait_stages)
@@ -139,46 +280,142 @@ impl App {
             in_flight_fence
         )?;
 
-        // Present
-        let swapchains = &[self.data.swapchain];
-        let image_indices = &[image_index as u32];
-        let present_info = vk::PresentInfoKHR::builder()
-            .wait_semaphores(signal_semaphores)
-            .swapchains(swapchains)
-            .image_indices(image_indices);
+        // Capture the frame before it is handed to the presentation engine
+        if self.screenshot {
+            self.screenshot = false;
+            self.save_screenshot(image_index);
+        }
 
-        let result = self.device
-            .queue_present_khr(self.data.present_queue, &present_info);
-        
-        // Recreates the swapchain
-        let changed = result == Ok(vk::SuccessCode::SUBOPTIMAL_KHR) || result == Err(vk::ErrorCode::OUT_OF_DATE_KHR);
-        if self.resized || changed {
-            self.resized = false;
-            self.recreate_swapchain(window)?;
-        } else if let Err(e) = result {
-            return Err(anyhow!(e));
+        // Present (a headless app has no window to present to)
+        if let Some(window) = window {
+            let swapchains = &[*self.data.swapchain];
+            let image_indices = &[image_index as u32];
+            let present_info = vk::PresentInfoKHR::builder()
+                .wait_semaphores(signal_semaphores)
+                .swapchains(swapchains)
+                .image_indices(image_indices);
+
+            let result = self.device
+                .queue_present_khr(self.data.present_queue, &present_info);
+
+            // Recreates the swapchain
+            let changed = result == Ok(vk::SuccessCode::SUBOPTIMAL_KHR) || result == Err(vk::ErrorCode::OUT_OF_DATE_KHR);
+            if self.resized || changed {
+                self.resized = false;
+                self.recreate_swapchain(window)?;
+            } else if let Err(e) = result {
+                return Err(anyhow!(e));
+            }
         }
 
         // Update current frame index
-        self.frame = (self.frame + 1) % MAX_FRAMES_IN_FLIGHT;
+        self.frame = (self.frame + 1) % self.data.in_flight_fences.len();
+
+        self.stats.record(delta.as_secs_f32() * 1000.0, start.elapsed().as_secs_f32() * 1000.0);
+        if let Some(window) = window {
+            self.show_stats(window);
+        }
 
         Ok(())
     }
 
+    /// Shows the frame stats in the window title and logs what the GPU did for
+    /// each model (twice a second).
+    fn show_stats(&mut self, window: &Window) {
+        if self.last_title.elapsed() < Duration::from_millis(500) {
+            return;
+        }
+
+        if let Some(summary) = self.stats.summary() {
+            window.set_title(&format!("Vulkan Tutorial (Rust) | {}", summary));
+            self.last_title = Instant::now();
+        }
+
+        for (model_index, draw) in self.draws.iter().enumerate() {
+            if let Some(draw) = draw {
+                debug!("Model {}: {}", model_index, draw);
+            }
+        }
+    }
+
+    /// Applies the actions of the input since the previous frame.
+    unsafe fn handle_input(&mut self, window: Option<&Window>, delta: Duration) -> Result<()> {
+        if self.input.was_pressed(Action::AddModel) && self.models < MAX_MODELS {
+            self.models += 1;
+        }
+        if self.input.was_pressed(Action::RemoveModel) && self.models > 1 {
+            self.models -= 1;
+        }
+        if self.input.was_pressed(Action::Screenshot) {
+            self.screenshot = true;
+        }
+
+        // Grab the cursor for mouse look, and release it when the window loses focus
+        let grab = if self.input.grabbed() {
+            !self.input.was_pressed(Action::ReleaseCursor) && self.input.focused()
+        } else {
+            self.input.was_pressed(Action::GrabCursor)
+        };
+        if let Some(window) = window.filter(|_| grab != self.input.grabbed()) {
+            if let Err(e) = self.input.set_grabbed(window, grab) {
+                warn!("Failed to grab the cursor: {}", e);
+            }
+        }
+
+        // Switch to the next MSAA sample count the device supports
+        if self.input.was_pressed(Action::CycleMsaa) {
+            let supported = physical_device::get_supported_msaa_samples(&self.instance, &self.data);
+            let samples = physical_device::next_msaa_samples(self.data.msaa_samples, supported);
+            pipeline::set_msaa_samples(&self.instance, &self.device, &mut self.data, samples, &PIPELINE)?;
+            info!("Switched to {:?} MSAA.", samples);
+        }
+
+        // Move the camera by the input since the previous frame
+        self.controller.update(&mut self.camera, &self.input, delta.as_secs_f32());
+
+        self.input.end_frame();
+
+        Ok(())
+    }
+
+    /// Reads the render target the latest frame of a headless app was rendered
+    /// into back to CPU memory.
+    pub unsafe fn read_frame(&self) -> Result<Frame> {
+        if !self.data.headless {
+            return Err(anyhow!("Only headless apps can read back render targets."));
+        }
+
+        offscreen::read_render_target(&self.instance, &self.device, &self.data, self.image_index)
+    }
     
-    /// Updates a command buffer for our Vulkan app.
+    /// Saves a rendered (but not yet presented) swapchain image as a PNG.
+    unsafe fn save_screenshot(&self, image_index: usize) {
+        let path = screenshot::screenshot_path(&Assets::new(&self.data.settings));
+        let result = screenshot::capture_swapchain_image(&self.instance, &self.device, &self.data, image_index)
+            .and_then(|frame| screenshot::save_png(&frame, &path));
+
+        match result {
+            Ok(()) => info!("Saved screenshot to `{}`.", path.display()),
+            Err(e) => error!("Failed to save screenshot: {}", e),
+        }
+    }
+
+    /// Updates the command buffer of the current frame to render into a
+    /// swapchain image for our Vulkan app.
     #[rustfmt::skip]
     unsafe fn update_command_buffer(&mut self, image_index: usize) -> Result<()> {
         // Reset
-        let command_pool = self.data.command_pools[image_index];
+        let command_pool = *self.data.command_pools[self.frame];
         self.device.reset_command_pool(command_pool, vk::CommandPoolResetFlags::empty())?;
 
-        let command_buffer = self.data.command_buffers[image_index];
+        let command_buffer = self.data.command_buffers[self.frame];
 
         // Commands
         let info = vk::CommandBufferBeginInfo::builder().flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
 
         self.device.begin_command_buffer(command_buffer, &info)?;
+        frame_stats::cmd_begin_timestamps(&self.device, command_buffer, &self.data, self.frame);
+        queries::cmd_reset_queries(&self.device, command_buffer, &self.data, self.frame);
 
         let render_area = vk::Rect2D::builder()
             .offset(vk::Offset2D::default())
@@ -196,8 +433,8 @@ impl App {
 
         let clear_values = &[color_clear_value, depth_clear_value];
         let info = vk::RenderPassBeginInfo::builder()
-            .render_pass(self.data.render_pass)
-            .framebuffer(self.data.framebuffers[image_index])
+            .render_pass(*self.data.render_pass)
+            .framebuffer(*self.data.framebuffers[image_index])
             .render_area(render_area)
             .clear_values(clear_values);
 
@@ -211,6 +448,7 @@ impl App {
 
         self.device.cmd_end_render_pass(command_buffer);
 
+        frame_stats::cmd_end_timestamps(&self.device, command_buffer, &self.data, self.frame);
         self.device.end_command_buffer(command_buffer)?;
 
         Ok(())
@@ -225,10 +463,10 @@ impl App {
     ) -> Result<vk::CommandBuffer> {
         // Allocate
 
-        let command_buffers = &mut self.data.secondary_command_buffers[image_index];
+        let command_buffers = &mut self.data.secondary_command_buffers[self.frame];
         while model_index >= command_buffers.len() {
             let allocate_info = vk::CommandBufferAllocateInfo::builder()
-                .command_pool(self.data.command_pools[image_index])
+                .command_pool(*self.data.command_pools[self.frame])
                 .level(vk::CommandBufferLevel::SECONDARY)
                 .command_buffer_count(1);
 
@@ -242,107 +480,72 @@ impl App {
         let y = (((model_index % 2) as f32) * 2.5) - 1.25;
         let z = (((model_index / 2) as f32) * -2.0) + 1.0;
 
-        let time = self.start.elapsed().as_secs_f32();
+        let time = self.time.unwrap_or_else(|| self.start.elapsed().as_secs_f32());
 
         let model = Mat4::from_translation(vec3(0.0, y, z)) * Mat4::from_axis_angle(
             vec3(0.0, 0.0, 1.0),
             Deg(90.0) * time
         );
 
-        let model_bytes = &*slice_from_raw_parts(
-            &model as *const Mat4 as *const u8,
-            size_of::<Mat4>()
-        );
-
         let opacity = (model_index + 1) as f32 * 0.25;
         let opacity_bytes = &opacity.to_ne_bytes()[..];
 
         // Commands
         let inheritance_info = vk::CommandBufferInheritanceInfo::builder()
-            .render_pass(self.data.render_pass)
+            .render_pass(*self.data.render_pass)
             .subpass(0)
-            .framebuffer(self.data.framebuffers[image_index]);
+            .framebuffer(*self.data.framebuffers[image_index]);
 
         let info = vk::CommandBufferBeginInfo::builder()
             .flags(vk::CommandBufferUsageFlags::RENDER_PASS_CONTINUE)
             .inheritance_info(&inheritance_info);
 
         self.device.begin_command_buffer(command_buffer, &info)?;
-        self.device.cmd_bind_pipeline(command_buffer, vk::PipelineBindPoint::GRAPHICS, self.data.pipeline);
-        self.device.cmd_bind_vertex_buffers(command_buffer, 0, &[self.data.vertex_buffer], &[0]);
-        self.device.cmd_bind_index_buffer(command_buffer, self.data.index_buffer, 0, vk::IndexType::UINT32);
-        self.device.cmd_bind_descriptor_sets(
-            command_buffer,
-            vk::PipelineBindPoint::GRAPHICS,
-            self.data.pipeline_layout,
-            0,
-            &[self.data.descriptor_sets[image_index]],
-            &[],
-        );
-        self.device.cmd_push_constants(
-            command_buffer,
-            self.data.pipeline_layout,
-            vk::ShaderStageFlags::VERTEX,
-            0,
-            model_bytes,
-        );
-        self.device.cmd_push_constants(
-            command_buffer,
-            self.data.pipeline_layout,
-            vk::ShaderStageFlags::FRAGMENT,
-            64,
-            opacity_bytes,
-        );
-        self.device.cmd_draw_indexed(command_buffer, self.data.indices.len() as u32, 1, 0, 0, 0);
+        self.device.cmd_bind_pipeline(command_buffer, vk::PipelineBindPoint::GRAPHICS, *self.data.pipeline);
+        pipeline::set_viewport(&self.device, command_buffer, self.data.swapchain_extent);
+        self.device.cmd_bind_vertex_buffers(command_buffer, 0, &[*self.data.vertex_buffer], &[0]);
+        self.device.cmd_bind_index_buffer(command_buffer, *self.data.index_buffer, 0, vk::IndexType::UINT32);
+        pipeline::cmd_push_constant(&self.device, command_buffer, &self.data, "opacity", opacity_bytes)?;
+        queries::cmd_begin_queries(&self.device, command_buffer, &self.data, self.frame, model_index);
+        // Each node's meshes are drawn with its transform
+        for node in self.data.nodes.iter().filter(|n| !n.meshes.is_empty()) {
+            let world = model * node.world;
+            let world_bytes = &*slice_from_raw_parts(
+                &world as *const Mat4 as *const u8,
+                size_of::<Mat4>()
+            );
+
+            pipeline::cmd_push_constant(&self.device, command_buffer, &self.data, "model", world_bytes)?;
+
+            for mesh in node.meshes.iter().map(|m| &self.data.meshes[*m]) {
+                self.device.cmd_bind_descriptor_sets(
+                    command_buffer,
+                    vk::PipelineBindPoint::GRAPHICS,
+                    *self.data.pipeline_layout,
+                    0,
+                    &[self.data.material_descriptor_sets[self.frame][mesh.material]],
+                    &[],
+                );
+                self.device.cmd_draw_indexed(command_buffer, mesh.index_count, 1, mesh.first_index, 0, 0);
+            }
+        }
+        queries::cmd_end_queries(&self.device, command_buffer, &self.data, self.frame, model_index);
 
         self.device.end_command_buffer(command_buffer)?;
 
         Ok(command_buffer)
     }
 
-    /// Updates the uniform buffer object for our Vulkan app.
-    unsafe fn update_uniform_buffer(&self, image_index: usize) -> Result<()> {
-        // Create MVP matrix
-
-        // let time = self.start.elapsed().as_secs_f32();
-        // let model = Mat4::from_axis_angle(
-        //     vec3(0.0, 0.0, 1.0), 
-        //     Deg(90.0) * time,
-        // );
-
-        let view = Mat4::look_at_rh(
-            point3::<f32>(6.0, 0.0, 2.0),
-            point3::<f32>(0.0, 0.0, 0.0),
-            vec3(0.0, 0.0, 1.0),
-        );
-
-        #[rustfmt::skip]
-        let correction = Mat4::new(
-            1.0,  0.0,       0.0, 0.0,
-            0.0, -1.0,       0.0, 0.0,
-            0.0,  0.0, 1.0 / 2.0, 0.0,
-            0.0,  0.0, 1.0 / 2.0, 1.0,
-        );
-
-        let proj = correction * cgmath::perspective(
-            Deg(45.0),
-            self.data.swapchain_extent.width as f32 / self.data.swapchain_extent.height as f32,
-            0.1,
-            10.0,
-        );
+    /// Updates the uniform buffer object of the current frame for our Vulkan app.
+    unsafe fn update_uniform_buffer(&self) -> Result<()> {
+        let extent = self.data.swapchain_extent;
+        let view = self.camera.view();
+        let proj = self.camera.projection(extent.width as f32 / extent.height as f32);
 
         let ubo = UniformBufferObject { view, proj };
 
         // Update uniform buffer with MVP matrix
-        let memory = self.device.map_memory(
-            self.data.uniform_buffers_memory[image_index],
-            0,
-            size_of::<UniformBufferObject>() as u64,
-            vk::MemoryMapFlags::empty(),
-        )?;
-        memcpy(&ubo, memory.cast(), 1);
-
-        self.device.unmap_memory(self.data.uniform_buffers_memory[image_index]);
+        memcpy(&ubo, self.data.uniform_buffers[self.frame].memory().mapped.cast(), 1);
 
         Ok(())
     }
@@ -350,27 +553,26 @@ impl App {
     /// Recreates the swapchain for our Vulkan app.
     #[rustfmt::skip]
     unsafe fn recreate_swapchain(&mut self, window: &Window) -> Result<()> {
-        self.device.device_wait_idle()?;
-        self.destroy_swapchain();  // destory
+        // Only the frames in flight use what is recreated below
+        sync_objects::wait_for_frames(&self.device, &self.data)?;
 
-        // recreate
+        // recreate (replacing a handle destroys the previous one)
+        let format = self.data.swapchain_format;
         swapchain::create_swapchain(window, &self.instance, &self.device, &mut self.data)?;
         swapchain::create_swapchain_image_views(&self.device, &mut self.data)?;
-        pipeline::create_render_pass(&self.instance, &self.device, &mut self.data)?;
-        pipeline::create_pipeline(&self.device, &mut self.data)?;
+
+        // The viewport and scissor are dynamic, so only a new format invalidates the pipeline
+        if self.data.swapchain_format != format {
+            pipeline::create_render_pass(&self.instance, &self.device, &mut self.data)?;
+            pipeline::create_pipeline(&self.device, &mut self.data, &PIPELINE)?;
+        }
         
         color_objects::create_color_objects(&self.instance, &self.device, &mut self.data)?;
         
         depth_objects::create_depth_objects(&self.instance, &self.device, &mut self.data)?;
         framebuffers::create_framebuffers(&self.device, &mut self.data)?;
-        buffers::create_uniform_buffers(&self.instance, &self.device, &mut self.data)?;
-        descriptor::create_descriptor_pool(&self.device, &mut self.data)?;
-        descriptor::create_descriptor_sets(&self.device, &mut self.data)?;
-        
-        command_buffers::create_command_buffers(&self.device, &mut self.data)?;
-        
-        self.data.images_in_flight
-            .resize(self.data.swapchain_images.len(), vk::Fence::null());
+
+        // The per-frame command buffers, uniform buffers and descriptor sets are kept
         
         Ok(())
     }
@@ -380,79 +582,31 @@ impl App {
     pub unsafe fn destroy(&mut self) {
         self.device.device_wait_idle().unwrap();
 
-        self.destroy_swapchain();
-        self.data.in_flight_fences
-            .iter()
-            .for_each(|f| 
-                self.device.destroy_fence(*f, None));
-        self.data.render_finished_semaphores
-            .iter()
-            .for_each(|s| 
-                self.device.destroy_semaphore(*s, None));
-        self.data.image_available_semaphores
-            .iter().
-            for_each(|s| 
-                self.device.destroy_semaphore(*s, None));
-        self.data.command_pools
-            .iter()
-            .for_each(|p| 
-                self.device.destroy_command_pool(*p, None));
-        self.device.free_memory(self.data.index_buffer_memory, None);
-        self.device.destroy_buffer(self.data.index_buffer, None);
-        self.device.free_memory(self.data.vertex_buffer_memory, None);
-        self.device.destroy_buffer(self.data.vertex_buffer, None);
-        self.device.destroy_sampler(self.data.texture_sampler, None);
-        self.device.destroy_image_view(self.data.texture_image_view, None);
-        self.device.free_memory(self.data.texture_image_memory, None);
-        self.device.destroy_image(self.data.texture_image, None);
-
-        self.device.destroy_command_pool(self.data.command_pool, None);
-        self.device.destroy_descriptor_set_layout(self.data.descriptor_set_layout, None);
+        if let Err(e) = pipeline_cache::save_pipeline_cache(&self.device, &self.data, &PIPELINE) {
+            warn!("Failed to save the pipeline cache: {}", e);
+        }
+
+        if let Some(path) = &self.data.settings.stats_csv {
+            match self.stats.write_csv(path) {
+                Ok(()) => info!("Saved the frame timings to `{}`.", path.display()),
+                Err(e) => warn!("Failed to save the frame timings: {}", e),
+            }
+        }
+
+        // Dropping the app data destroys the handles it owns (in field order)
+        let AppData { surface, messenger, allocator, headless, .. } = std::mem::take(&mut self.data);
+
+        tracker::report_leaks();
+        allocator.destroy(&self.device);
         self.device.destroy_device(None);
-        self.instance.destroy_surface_khr(self.data.surface, None);
+        if !headless {
+            self.instance.destroy_surface_khr(surface, None);
+        }
 
-        if VALIDATION_ENABLED {
-            self.instance.destroy_debug_utils_messenger_ext(self.data.messenger, None);
+        if !messenger.is_null() {
+            self.instance.destroy_debug_utils_messenger_ext(messenger, None);
         }
 
         self.instance.destroy_instance(None);
     }
-
-    /// Destroys the parts of our Vulkan app related to the swapchain.
-    #[rustfmt::skip]
-    unsafe fn destroy_swapchain(&mut self) {
-        // destory descriptor pool
-        self.device.destroy_descriptor_pool(self.data.descriptor_pool, None);
-        // destory uniform buffers
-        self.data.uniform_buffers_memory
-            .iter()
-            .for_each(|m| 
-                self.device.free_memory(*m, None));
-        self.data.uniform_buffers
-            .iter()
-            .for_each(|b| 
-                self.device.destroy_buffer(*b, None));
-        
-        // destory depth image
-        self.device.destroy_image_view(self.data.depth_image_view, None);
-        self.device.free_memory(self.data.depth_image_memory, None);
-        self.device.destroy_image(self.data.depth_image, None);
-
-        self.device.destroy_image_view(self.data.color_image_view, None);
-        self.device.free_memory(self.data.color_image_memory, None);
-        self.device.destroy_image(self.data.color_image, None);
-
-        self.data.framebuffers
-            .iter()
-            .for_each(|f| 
-                self.device.destroy_framebuffer(*f, None));
-        self.device.destroy_pipeline(self.data.pipeline, None);
-        self.device.destroy_pipeline_layout(self.data.pipeline_layout, None);
-        self.device.destroy_render_pass(self.data.render_pass, None);
-        self.data.swapchain_image_views
-            .iter()
-            .for_each(|v| 
-                self.device.destroy_image_view(*v, None));
-        self.device.destroy_swapchain_khr(self.data.swapchain, None);
-    }
 }
\ No newline at end of file
@@ -1,16 +1,18 @@
 //================================================
 // Command Buffers
 //================================================
-use crate::app_data::AppData;
+use renderer::app_data::AppData;
 
 use anyhow::Result;
 use vulkanalia::prelude::v1_0::*;
 
+/// Allocates a command buffer from the pool of each frame in flight.
 pub unsafe fn create_command_buffers(device: &Device, data: &mut AppData) -> Result<()> {
-    let num_images = data.swapchain_images.len();
-    for image_index in 0..num_images {
+    data.command_buffers.clear();
+
+    for command_pool in &data.command_pools {
         let allocate_info = vk::CommandBufferAllocateInfo::builder()
-            .command_pool(data.command_pools[image_index])
+            .command_pool(**command_pool)
             .level(vk::CommandBufferLevel::PRIMARY)
             .command_buffer_count(1);
 
@@ -18,7 +20,7 @@ pub unsafe fn create_command_buffers(device: &Device, data: &mut AppData) -> Res
         data.command_buffers.push(command_buffer);
     }
 
-    data.secondary_command_buffers = vec![vec![]; data.swapchain_images.len()];
+    data.secondary_command_buffers = vec![vec![]; data.command_pools.len()];
 
     Ok(())
 }
\ No newline at end of file
@@ -1,5 +0,0 @@
-use thiserror::Error;
-
-#[derive(Debug, Error)]
-#[error("{0}")]
-pub struct SuitabilityError(pub &'static str);
\ No newline at end of file
@@ -3,34 +3,46 @@
 
 use anyhow::Result;
 use winit::dpi::LogicalSize;
-use winit::event::{ElementState, Event, VirtualKeyCode, WindowEvent};
+use winit::event::{DeviceEvent, Event, WindowEvent};
 use winit::event_loop::{ControlFlow, EventLoop};
-use winit::window::{Window, WindowBuilder};
+use winit::window::{Fullscreen, Window, WindowBuilder};
 use mylib::app::App;
+use renderer::settings::Settings;
 
 #[rustfmt::skip]
 fn main() -> Result<()> {
     pretty_env_logger::init();
 
+    // --- Settings ---
+    let settings: Settings = Settings::load()?;
+
     // --- Window ---
     let event_loop: EventLoop<()> = EventLoop::new();
     let window: Window = WindowBuilder::new()
         .with_title("Vulkan Tutorial (Rust)")
-        .with_inner_size(LogicalSize::new(1024, 768))
+        .with_inner_size(LogicalSize::new(settings.width, settings.height))
+        .with_fullscreen(settings.fullscreen.then_some(Fullscreen::Borderless(None)))
         .build(&event_loop)?;
 
     // --- App ---
-    let mut app: App = unsafe { App::create(&window)? };
+    let mut app: App = unsafe { App::create(&window, settings)? };
     let mut destroying: bool = false;
     let mut minimized:  bool = false;
     
     // --- Event ---
     event_loop.run(move |event, _, control_flow| {
         *control_flow = ControlFlow::Poll;
+        // The app applies the keyboard and mouse input when it renders a frame.
+        match &event {
+            Event::WindowEvent { event, .. } => app.input.handle_window_event(event),
+            Event::DeviceEvent { event: DeviceEvent::MouseMotion { delta }, .. } => app.input.mouse_motion(*delta),
+            _ => {}
+        }
+
         match event {
             // Render a frame if our Vulkan app is not being destroyed.
             Event::MainEventsCleared if !destroying && !minimized => unsafe { 
-                app.render(&window)
+                app.render(Some(&window))
             }.unwrap(),
             // Mark the window as having been resized.
             Event::WindowEvent { event: WindowEvent::Resized(size), .. } => {
@@ -47,17 +59,8 @@ fn main() -> Result<()> {
                 *control_flow = ControlFlow::Exit;
                 unsafe { app.destroy(); }
             }
-            // Handle keyboard events.
-            Event::WindowEvent { event: WindowEvent::KeyboardInput { input, .. }, .. } => {
-                if input.state == ElementState::Pressed {
-                    match input.virtual_keycode {
-                        Some(VirtualKeyCode::Left) if app.models > 1 => app.models -= 1,
-                        Some(VirtualKeyCode::Right) if app.models < 4 => app.models += 1,
-                        _ => { }
-                    }
-                }
-            }
             _ => {}
         }
     });
 }
+
@@ -0,0 +1,11 @@
+//================================================
+// Uniforms
+//================================================
+use renderer::structs::Mat4;
+
+#[repr(C)]
+#[derive(Copy, Clone, Debug)]
+pub struct UniformBufferObject {
+    pub view:  Mat4,
+    pub proj:  Mat4,
+}
\ No newline at end of file
@@ -0,0 +1,212 @@
+//================================================
+// Golden images
+//================================================
+//! Compares headless demo renders against stored reference images.
+//!
+//! References live in `golden/references/<name>.png`. When a render does not
+//! match, the actual frame and a diff image are written to `target/golden/`.
+//! Run the tests with `GOLDEN_UPDATE=1` to (re)write the references instead.
+
+use std::env;
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Result};
+use log::*;
+
+/// The environment variable that makes [`check`] overwrite the references.
+pub const UPDATE_VARIABLE: &str = "GOLDEN_UPDATE";
+
+/// A tightly packed RGBA8 image.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Image {
+    pub width:  u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl Image {
+    pub fn new(width: u32, height: u32, pixels: Vec<u8>) -> Self {
+        Self { width, height, pixels }
+    }
+
+    pub fn read_png(path: &Path) -> Result<Self> {
+        let decoder = png::Decoder::new(File::open(path)?);
+        let mut reader = decoder.read_info()?;
+
+        let mut pixels = vec![0; reader.output_buffer_size()];
+        let info = reader.next_frame(&mut pixels)?;
+        pixels.truncate(info.buffer_size());
+
+        if info.color_type != png::ColorType::Rgba || info.bit_depth != png::BitDepth::Eight {
+            return Err(anyhow!("Reference image `{}` is not 8-bit RGBA.", path.display()));
+        }
+
+        Ok(Self::new(info.width, info.height, pixels))
+    }
+
+    pub fn write_png(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        let writer = BufWriter::new(File::create(path)?);
+        let mut encoder = png::Encoder::new(writer, self.width, self.height);
+        encoder.set_color(png::ColorType::Rgba);
+        encoder.set_depth(png::BitDepth::Eight);
+
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&self.pixels)?;
+
+        Ok(())
+    }
+}
+
+/// The result of comparing a render against its reference.
+#[derive(Clone, Debug)]
+pub struct Comparison {
+    /// The number of pixels with a channel outside the tolerance.
+    pub mismatched: usize,
+    /// The largest channel difference over all pixels.
+    pub max_difference: u8,
+    /// Mismatched pixels in red over a dimmed copy of the reference.
+    pub diff: Image,
+}
+
+/// Compares two images of the same size, allowing each channel of each pixel
+/// to differ by up to `tolerance`.
+pub fn compare(actual: &Image, expected: &Image, tolerance: u8) -> Result<Comparison> {
+    if actual.width != expected.width || actual.height != expected.height {
+        return Err(anyhow!(
+            "Image size mismatch (actual {}x{}, expected {}x{}).",
+            actual.width, actual.height, expected.width, expected.height,
+        ));
+    }
+
+    let mut mismatched = 0;
+    let mut max_difference = 0;
+    let mut diff = Vec::with_capacity(expected.pixels.len());
+
+    for (a, e) in actual.pixels.chunks_exact(4).zip(expected.pixels.chunks_exact(4)) {
+        let difference = a.iter().zip(e).map(|(a, e)| a.abs_diff(*e)).max().unwrap_or(0);
+        max_difference = max_difference.max(difference);
+
+        if difference > tolerance {
+            mismatched += 1;
+            diff.extend_from_slice(&[255, 0, 0, 255]);
+        } else {
+            let luma = ((e[0] as u32 * 3 + e[1] as u32 * 6 + e[2] as u32) / 10 / 4) as u8;
+            diff.extend_from_slice(&[luma, luma, luma, 255]);
+        }
+    }
+
+    let diff = Image::new(expected.width, expected.height, diff);
+    Ok(Comparison { mismatched, max_difference, diff })
+}
+
+/// Checks a render against the reference image called `name`.
+pub fn check(name: &str, actual: &Image, tolerance: u8) -> Result<()> {
+    let reference = reference_dir().join(format!("{}.png", name));
+
+    if env::var_os(UPDATE_VARIABLE).is_some() {
+        info!("Updating reference image `{}`.", reference.display());
+        return actual.write_png(&reference);
+    }
+
+    if !reference.exists() {
+        let path = output_dir().join(format!("{}.actual.png", name));
+        actual.write_png(&path)?;
+        return Err(anyhow!(
+            "Missing reference image `{}` (actual frame written to `{}`, rerun with {}=1 to accept it).",
+            reference.display(), path.display(), UPDATE_VARIABLE,
+        ));
+    }
+
+    let expected = Image::read_png(&reference)?;
+    let comparison = compare(actual, &expected, tolerance)?;
+    if comparison.mismatched == 0 {
+        return Ok(());
+    }
+
+    let actual_path = output_dir().join(format!("{}.actual.png", name));
+    let diff_path = output_dir().join(format!("{}.diff.png", name));
+    actual.write_png(&actual_path)?;
+    comparison.diff.write_png(&diff_path)?;
+
+    Err(anyhow!(
+        "`{}` differs from its reference in {} pixels (max difference {}, tolerance {}); see `{}` and `{}`.",
+        name, comparison.mismatched, comparison.max_difference, tolerance,
+        actual_path.display(), diff_path.display(),
+    ))
+}
+
+/// The workspace root, which the demos load their resources relative to.
+pub fn workspace_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("..")
+}
+
+fn reference_dir() -> PathBuf {
+    Path::new(env!("CARGO_MANIFEST_DIR")).join("references")
+}
+
+fn output_dir() -> PathBuf {
+    env::var_os("CARGO_TARGET_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| workspace_dir().join("target"))
+        .join("golden")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn solid(width: u32, height: u32, pixel: [u8; 4]) -> Image {
+        Image::new(width, height, pixel.repeat((width * height) as usize))
+    }
+
+    #[test]
+    fn identical_images_match() {
+        let image = solid(4, 3, [10, 20, 30, 255]);
+        let comparison = compare(&image, &image, 0).unwrap();
+        assert_eq!(comparison.mismatched, 0);
+        assert_eq!(comparison.max_difference, 0);
+    }
+
+    #[test]
+    fn differences_within_tolerance_match() {
+        let actual = solid(4, 3, [12, 18, 30, 255]);
+        let expected = solid(4, 3, [10, 20, 30, 255]);
+        let comparison = compare(&actual, &expected, 2).unwrap();
+        assert_eq!(comparison.mismatched, 0);
+        assert_eq!(comparison.max_difference, 2);
+    }
+
+    #[test]
+    fn differences_beyond_tolerance_are_marked() {
+        let expected = solid(2, 2, [0, 0, 0, 255]);
+        let mut actual = expected.clone();
+        actual.pixels[4..8].copy_from_slice(&[0, 50, 0, 255]);
+
+        let comparison = compare(&actual, &expected, 2).unwrap();
+        assert_eq!(comparison.mismatched, 1);
+        assert_eq!(comparison.max_difference, 50);
+        assert_eq!(&comparison.diff.pixels[4..8], &[255, 0, 0, 255]);
+        assert_eq!(&comparison.diff.pixels[0..4], &[0, 0, 0, 255]);
+    }
+
+    #[test]
+    fn size_mismatch_is_an_error() {
+        assert!(compare(&solid(2, 2, [0; 4]), &solid(2, 3, [0; 4]), 0).is_err());
+    }
+
+    #[test]
+    fn png_round_trip() {
+        let image = Image::new(2, 1, vec![1, 2, 3, 4, 5, 6, 7, 8]);
+        let path = env::temp_dir().join(format!("golden-round-trip-{}.png", std::process::id()));
+        image.write_png(&path).unwrap();
+        let read = Image::read_png(&path).unwrap();
+        fs::remove_file(&path).unwrap();
+        assert_eq!(read, image);
+    }
+}
@@ -0,0 +1,105 @@
+use crate::memory::Allocator;
+use crate::model::{Material, Mesh, Node};
+use crate::owned::{Buffer, Image, Owned};
+use crate::reflect::PushConstant;
+use crate::settings::Settings;
+use crate::shaders::ShaderCache;
+use crate::structs::Vertex;
+use crate::texture::Texture;
+
+use std::rc::Rc;
+
+use vulkanalia::prelude::v1_0::*;
+
+/// The Vulkan handles and associated properties used by our Vulkan app.
+///
+/// The [`Owned`] handles are destroyed when they are replaced, or in the order
+/// of the fields below when the app data is dropped (which has to happen before
+/// the logical device is destroyed).
+#[derive(Debug, Default)]
+pub struct AppData {
+    // Settings
+    pub settings:        Settings,
+    // Debug
+    pub messenger:       vk::DebugUtilsMessengerEXT,
+    // Surface
+    pub surface:         vk::SurfaceKHR,
+    // Physical Device / Logical Device
+    pub physical_device: vk::PhysicalDevice,
+    pub msaa_samples:    vk::SampleCountFlags,
+    pub pipeline_statistics_query: bool,
+    pub graphics_queue:  vk::Queue,
+    pub present_queue:   vk::Queue,
+    // Memory
+    pub allocator:       Rc<Allocator>,
+    // Swapchain
+    pub swapchain_format:      vk::Format,
+    pub swapchain_extent:      vk::Extent2D,
+    pub swapchain_image_views: Vec<Owned<vk::ImageView>>,
+    pub swapchain:             Owned<vk::SwapchainKHR>,
+    pub retired_swapchain:     Owned<vk::SwapchainKHR>,
+    pub swapchain_images:      Vec<vk::Image>,
+    pub swapchain_usage:       vk::ImageUsageFlags,
+    // Offscreen (headless render targets stand in for the swapchain images)
+    pub headless:       bool,
+    pub render_targets: Vec<Image>,
+    // Pipeline
+    pub shaders:               ShaderCache,
+    pub pipeline_cache:        Owned<vk::PipelineCache>,
+    pub render_pass:           Owned<vk::RenderPass>,
+    pub descriptor_set_layout: Owned<vk::DescriptorSetLayout>,
+    pub descriptor_bindings:   Vec<vk::DescriptorSetLayoutBinding>,
+    pub pipeline_layout:       Owned<vk::PipelineLayout>,
+    pub push_constants:        Vec<PushConstant>,
+    pub pipeline:              Owned<vk::Pipeline>,
+    // Framebuffers
+    pub framebuffers: Vec<Owned<vk::Framebuffer>>,
+    // Command Pool
+    pub command_pool: Owned<vk::CommandPool>,
+    // Color
+    pub color_image:      Image,
+    pub color_image_view: Owned<vk::ImageView>,
+    // Depth
+    pub depth_image:      Image,
+    pub depth_image_view: Owned<vk::ImageView>,
+    // Texture (RGBA8 or the format of a compressed texture file)
+    pub mip_levels:         u32,
+    pub texture_format:     vk::Format,
+    pub texture_image:      Image,
+    pub texture_image_view: Owned<vk::ImageView>,
+    pub texture_sampler:    Owned<vk::Sampler>,
+    // Model
+    pub vertices:  Vec<Vertex>,
+    pub indices:   Vec<u32>,
+    pub meshes:    Vec<Mesh>,
+    pub materials: Vec<Material>,
+    pub nodes:     Vec<Node>,
+    // Materials (a texture per material and a descriptor set per material of each frame in flight)
+    pub material_textures:        Vec<Texture>,
+    pub material_descriptor_sets: Vec<Vec<vk::DescriptorSet>>,
+    // Buffers
+    pub vertex_buffer:   Buffer,
+    pub index_buffer:    Buffer,
+    pub uniform_buffers: Vec<Buffer>,
+    // Descriptors
+    pub descriptor_pool: Owned<vk::DescriptorPool>,
+    pub descriptor_sets: Vec<vk::DescriptorSet>,
+    // Command Buffers
+    pub command_pools:             Vec<Owned<vk::CommandPool>>,
+    pub command_buffers:           Vec<vk::CommandBuffer>,
+    pub secondary_command_buffers: Vec<Vec<vk::CommandBuffer>>,
+    // Sync Objects
+    pub image_available_semaphores: Vec<Owned<vk::Semaphore>>,
+    pub render_finished_semaphores: Vec<Owned<vk::Semaphore>>,
+    pub in_flight_fences: Vec<Owned<vk::Fence>>,
+    // The fence that last rendered into each swapchain image (only used by the apps that record a command buffer per image)
+    pub images_in_flight: Vec<vk::Fence>,
+    // Queries (two timestamps per frame in flight, see `frame_stats`)
+    pub timestamp_query_pool: Owned<vk::QueryPool>,
+    pub timestamp_period:     f32,
+    pub timestamp_mask:       u64,
+    // Queries (a pipeline statistics and occlusion query per draw of each frame in flight, see `queries`)
+    pub statistics_query_pool: Owned<vk::QueryPool>,
+    pub occlusion_query_pool:  Owned<vk::QueryPool>,
+    pub queries_per_frame:     u32,
+}
\ No newline at end of file
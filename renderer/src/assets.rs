@@ -0,0 +1,212 @@
+//================================================
+// Assets
+//================================================
+//! Finds the models and textures the apps load, so they do not depend on the
+//! directory they are started from.
+//!
+//! A relative asset path is looked up in each asset root in turn: the one
+//! given with `--assets`, the one in the [`ASSETS_ENV`] environment variable,
+//! the working directory, the executable's directory (and the directories
+//! above it, e.g. the workspace when it runs from `target/debug`) and the
+//! workspace this crate was built in. The first root containing it wins.
+//!
+//! Shader sources are looked up the same way, and the config and bindings
+//! files read when none is given in every root but `--assets` (see
+//! [`find_default`]). The files the apps write (screenshots and pipeline
+//! caches) go next to the assets (see [`Assets::output`]).
+
+use crate::error::AssetNotFound;
+use crate::settings::Settings;
+
+use std::env;
+use std::ffi::OsStr;
+use std::path::{Path, PathBuf};
+
+/// The environment variable that names an asset root.
+pub const ASSETS_ENV: &str = "VULKAN_TUTORIAL_ASSETS";
+
+/// The model that is loaded when `--model` is not given.
+pub const DEFAULT_MODEL: &str = "resources/viking_room.obj";
+/// The texture that is loaded when `--texture` is not given.
+pub const DEFAULT_TEXTURE: &str = "resources/viking_room.png";
+
+/// The directory the models and textures of the workspace are in.
+pub const RESOURCES: &str = "resources";
+
+/// How many directories above the executable's are asset roots.
+const EXECUTABLE_ANCESTORS: usize = 3;
+
+/// The asset roots and the model and texture an app loads.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Assets {
+    roots: Vec<PathBuf>,
+    model: PathBuf,
+    texture: PathBuf,
+}
+
+impl Assets {
+    /// Finds assets in the asset roots of the settings and environment.
+    pub fn new(settings: &Settings) -> Self {
+        let mut roots = vec![];
+        roots.extend(settings.assets.clone());
+        roots.extend(default_roots());
+
+        Self::with_roots(roots, settings)
+    }
+
+    /// Finds assets in the given roots (in order).
+    pub fn with_roots(roots: Vec<PathBuf>, settings: &Settings) -> Self {
+        let mut unique = Vec::with_capacity(roots.len());
+        for root in roots {
+            if !unique.contains(&root) {
+                unique.push(root);
+            }
+        }
+
+        Self {
+            roots: unique,
+            model: settings.model.clone().unwrap_or_else(|| DEFAULT_MODEL.into()),
+            texture: settings.texture.clone().unwrap_or_else(|| DEFAULT_TEXTURE.into()),
+        }
+    }
+
+    pub fn roots(&self) -> &[PathBuf] {
+        &self.roots
+    }
+
+    /// The path of an asset: an absolute path if it exists, or a relative path
+    /// joined to the first asset root it exists in.
+    pub fn resolve(&self, path: impl AsRef<Path>) -> Result<PathBuf, AssetNotFound> {
+        let path = path.as_ref();
+        let attempts = if path.is_absolute() {
+            vec![path.to_path_buf()]
+        } else {
+            self.roots.iter().map(|r| r.join(path)).collect()
+        };
+
+        match attempts.iter().find(|a| a.is_file()) {
+            Some(found) => Ok(found.clone()),
+            None => Err(AssetNotFound { path: path.into(), attempts }),
+        }
+    }
+
+    /// The path an output file (e.g. a screenshot) is written to: a relative
+    /// path is joined to the first asset root that contains its top directory,
+    /// or else to the first one with a [`RESOURCES`] directory, so outputs end
+    /// up next to the assets rather than in the working directory.
+    pub fn output(&self, path: impl AsRef<Path>) -> PathBuf {
+        let path = path.as_ref();
+        let top = path.components().next().map(|c| c.as_os_str()).unwrap_or_default();
+
+        let root = [top, OsStr::new(RESOURCES)]
+            .into_iter()
+            .find_map(|directory| self.roots.iter().find(|r| r.join(directory).is_dir()));
+
+        match root {
+            Some(root) => root.join(path),
+            None => path.into(),
+        }
+    }
+
+    /// The path of the model selected with `--model`.
+    pub fn model(&self) -> Result<PathBuf, AssetNotFound> {
+        self.resolve(&self.model)
+    }
+
+    /// The path of the texture selected with `--texture`.
+    pub fn texture(&self) -> Result<PathBuf, AssetNotFound> {
+        self.resolve(&self.texture)
+    }
+}
+
+/// The asset roots that do not depend on the settings (all of them except
+/// the `--assets` directory), in order.
+pub fn default_roots() -> Vec<PathBuf> {
+    let mut roots = vec![];
+    roots.extend(env::var_os(ASSETS_ENV).map(PathBuf::from));
+    roots.push(PathBuf::from("."));
+
+    if let Some(directory) = env::current_exe().ok().as_deref().and_then(Path::parent) {
+        roots.extend(directory.ancestors().take(EXECUTABLE_ANCESTORS + 1).map(PathBuf::from));
+    }
+
+    // The workspace (this crate's parent directory)
+    roots.extend(Path::new(env!("CARGO_MANIFEST_DIR")).parent().map(PathBuf::from));
+
+    roots
+}
+
+/// Finds a file that is read when no other one is given (e.g. the config file)
+/// in the first of the [`default_roots`] that contains it.
+pub fn find_default(path: impl AsRef<Path>) -> Option<PathBuf> {
+    default_roots().into_iter().map(|r| r.join(&path)).find(|p| p.is_file())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+
+    use anyhow::Result;
+
+    #[test]
+    fn assets_are_found_in_the_first_root_containing_them() -> Result<()> {
+        let directory = env::temp_dir().join(format!("assets_{}", std::process::id()));
+        let (first, second) = (directory.join("first"), directory.join("second"));
+        fs::create_dir_all(first.join("models"))?;
+        fs::create_dir_all(second.join("models"))?;
+        fs::write(first.join("models/a.obj"), "")?;
+        fs::write(second.join("models/a.obj"), "")?;
+        fs::write(second.join("models/b.obj"), "")?;
+
+        let settings = Settings { model: Some("models/b.obj".into()), ..Default::default() };
+        let assets = Assets::with_roots(vec![first.clone(), second.clone(), first.clone()], &settings);
+        assert_eq!(assets.roots(), [first.clone(), second.clone()]);
+
+        let resolved = (assets.resolve("models/a.obj"), assets.model(), assets.resolve(second.join("models/a.obj")));
+        fs::remove_dir_all(&directory)?;
+
+        assert_eq!(resolved.0?, first.join("models/a.obj"));
+        assert_eq!(resolved.1?, second.join("models/b.obj"));
+        assert_eq!(resolved.2?, second.join("models/a.obj"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn outputs_are_written_next_to_the_assets() -> Result<()> {
+        let directory = env::temp_dir().join(format!("outputs_{}", std::process::id()));
+        let (first, second, third) = (directory.join("first"), directory.join("second"), directory.join("third"));
+        fs::create_dir_all(first.join("empty"))?;
+        fs::create_dir_all(second.join(RESOURCES))?;
+        fs::create_dir_all(third.join("target"))?;
+
+        let assets = Assets::with_roots(vec![first.clone(), second.clone(), third.clone()], &Settings::default());
+        let outputs = (assets.output("target/cache.bin"), assets.output("screenshots/a.png"));
+        fs::remove_dir_all(&directory)?;
+
+        assert_eq!(outputs.0, third.join("target/cache.bin"));
+        assert_eq!(outputs.1, second.join("screenshots/a.png"));
+        assert_eq!(assets.output(directory.join("a.png")), directory.join("a.png"));
+
+        Ok(())
+    }
+
+    #[test]
+    fn missing_assets_name_the_attempted_paths() {
+        let assets = Assets::with_roots(vec!["one".into(), "two".into()], &Settings::default());
+
+        let error = assets.texture().unwrap_err();
+        assert_eq!(error.attempts, [Path::new("one").join(DEFAULT_TEXTURE), Path::new("two").join(DEFAULT_TEXTURE)]);
+        assert_eq!(
+            error.to_string(),
+            format!(
+                "Asset `{}` was not found (tried `{}`, `{}`).",
+                DEFAULT_TEXTURE,
+                Path::new("one").join(DEFAULT_TEXTURE).display(),
+                Path::new("two").join(DEFAULT_TEXTURE).display(),
+            ),
+        );
+    }
+}
@@ -0,0 +1,178 @@
+//================================================
+// Buffers
+//================================================
+#![allow(unused_variables)]
+
+use crate::app_data::AppData;
+use crate::commands;
+use crate::memory::Allocator;
+use crate::owned::{Buffer, Owned};
+use crate::structs::Vertex;
+
+use std::mem::size_of;
+use std::ptr::copy_nonoverlapping as memcpy;
+use std::rc::Rc;
+
+use anyhow::Result;
+use vulkanalia::prelude::v1_0::*;
+
+#[track_caller]
+pub unsafe fn create_buffer(
+    instance: &Instance,
+    device: &Rc<Device>,
+    data: &AppData,
+    size: vk::DeviceSize,
+    usage: vk::BufferUsageFlags,
+    properties: vk::MemoryPropertyFlags,
+) -> Result<Buffer> {
+    allocate_buffer(device, &data.allocator, size, usage, properties)
+}
+
+/// Creates a buffer in memory from `allocator`.
+#[track_caller]
+pub unsafe fn allocate_buffer(
+    device: &Rc<Device>,
+    allocator: &Rc<Allocator>,
+    size: vk::DeviceSize,
+    usage: vk::BufferUsageFlags,
+    properties: vk::MemoryPropertyFlags,
+) -> Result<Buffer> {
+    // 1. Create Buffer
+    let buffer_info = vk::BufferCreateInfo::builder()
+        .size(size)
+        .usage(usage)
+        .sharing_mode(vk::SharingMode::EXCLUSIVE);
+
+    let buffer = Owned::new(device, device.create_buffer(&buffer_info, None)?);
+
+    // 2. Allocate Memory
+    let requirements = device.get_buffer_memory_requirements(*buffer);
+
+    let buffer_memory = allocator.allocate(device, requirements, properties, true)?;
+    let buffer = Buffer::new(buffer, allocator, buffer_memory);
+
+    // 3. Bind
+    device.bind_buffer_memory(*buffer, buffer_memory.memory, buffer_memory.offset)?;
+
+    Ok(buffer)
+}
+
+pub unsafe fn copy_buffer(
+    device: &Device,
+    data: &AppData,
+    source: vk::Buffer,
+    destination: vk::Buffer,
+    size: vk::DeviceSize,
+) -> Result<()> {
+    let command_buffer = commands::begin_single_time_commands(device, data)?;
+
+    let regions = vk::BufferCopy::builder().size(size);
+    device.cmd_copy_buffer(command_buffer, source, destination, &[regions]);
+
+    commands::end_single_time_commands(device, data, command_buffer)?;
+
+    Ok(())
+}
+
+pub unsafe fn create_vertex_buffer(instance: &Instance, device: &Rc<Device>, data: &mut AppData) -> Result<()> {
+    //================================================
+    // Stage Buffer: CPU accessible
+    //================================================
+    // 1. Create Buffer
+    let size = (size_of::<Vertex>() * data.vertices.len()) as u64;
+    let staging_buffer = create_buffer(
+        instance,
+        device,
+        data,
+        size,
+        vk::BufferUsageFlags::TRANSFER_SRC,
+        vk::MemoryPropertyFlags::HOST_COHERENT | vk::MemoryPropertyFlags::HOST_VISIBLE,
+    )?;  // 1. create buffer, 2. allocate memory, 3. bind
+
+    // 2. Copy Data: VERTICES -> staging_buffer memory
+    memcpy(data.vertices.as_ptr(), staging_buffer.memory().mapped.cast(), data.vertices.len());
+
+    //================================================
+    // Vertex Buffer: CPU is not accessible
+    //================================================
+    // 1. Create Buffer
+    let vertex_buffer = create_buffer(
+        instance,
+        device,
+        data,
+        size,
+        vk::BufferUsageFlags::TRANSFER_DST | vk::BufferUsageFlags::VERTEX_BUFFER,
+        vk::MemoryPropertyFlags::DEVICE_LOCAL,
+    )?;
+
+    // 2. Copy Data (staging_buffer -> vertex_buffer)
+    copy_buffer(device, data, *staging_buffer, *vertex_buffer, size)?;
+
+    // The staging buffer is released when it goes out of scope
+    data.vertex_buffer = vertex_buffer;
+
+    Ok(())
+}
+
+
+pub unsafe fn create_index_buffer(instance: &Instance, device: &Rc<Device>, data: &mut AppData) -> Result<()> {
+    //================================================
+    // Stage Buffer: CPU accessible
+    //================================================
+    // 1. Create buffer
+    let size = (size_of::<u32>() * data.indices.len()) as u64;
+
+    let staging_buffer = create_buffer(
+        instance,
+        device,
+        data,
+        size,
+        vk::BufferUsageFlags::TRANSFER_SRC,
+        vk::MemoryPropertyFlags::HOST_COHERENT | vk::MemoryPropertyFlags::HOST_VISIBLE,
+    )?;
+
+    // Copy data
+    memcpy(data.indices.as_ptr(), staging_buffer.memory().mapped.cast(), data.indices.len());
+
+    //================================================
+    // Index Buffer: CPU is not accessible
+    //================================================
+    // 1. Create buffer 
+    let index_buffer = create_buffer(
+        instance,
+        device,
+        data,
+        size,
+        vk::BufferUsageFlags::TRANSFER_DST | vk::BufferUsageFlags::INDEX_BUFFER,
+        vk::MemoryPropertyFlags::DEVICE_LOCAL,
+    )?;
+
+    // Copy data
+    copy_buffer(device, data, *staging_buffer, *index_buffer, size)?;
+
+    data.index_buffer = index_buffer;
+
+    Ok(())
+}
+
+
+/// Creates `count` host visible uniform buffers (one per swapchain image or
+/// frame in flight), sized for the uniform buffer object `U` of the app.
+pub unsafe fn create_uniform_buffers<U>(instance: &Instance, device: &Rc<Device>, data: &mut AppData, count: usize) -> Result<()> {
+    data.uniform_buffers.clear();
+
+    for _ in 0..count {
+        let uniform_buffer = create_buffer(
+            instance,
+            device,
+            data,
+            size_of::<U>() as u64,
+            vk::BufferUsageFlags::UNIFORM_BUFFER,
+            vk::MemoryPropertyFlags::HOST_COHERENT | vk::MemoryPropertyFlags::HOST_VISIBLE,
+        )?;
+
+        data.uniform_buffers.push(uniform_buffer);
+    }
+
+    Ok(())
+}
\ No newline at end of file
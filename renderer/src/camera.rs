@@ -0,0 +1,305 @@
+//================================================
+// Camera
+//================================================
+//! A camera that either orbits a point in front of it or flies freely, and a
+//! controller that moves it from the keyboard and mouse input of a frame.
+//!
+//! Neither holds any Vulkan objects, so they can be used (and tested) without
+//! a GPU: the app only feeds the camera's view and projection matrices into
+//! its uniform buffer.
+
+use crate::structs::Mat4;
+
+use crate::input::{Action, Input};
+
+use cgmath::{vec3, Deg, InnerSpace, Point3, Vector3};
+
+type Vec3 = Vector3<f32>;
+
+/// The up direction of the world (the models are Z-up).
+pub const UP: Vec3 = Vector3 { x: 0.0, y: 0.0, z: 1.0 };
+
+/// Converts the OpenGL clip space of [`cgmath::perspective`] to Vulkan's
+/// (Y pointing down and depth from 0 to 1).
+#[rustfmt::skip]
+pub const CORRECTION: Mat4 = Mat4::new(
+    1.0,  0.0,       0.0, 0.0,
+    0.0, -1.0,       0.0, 0.0,
+    0.0,  0.0, 1.0 / 2.0, 0.0,
+    0.0,  0.0, 1.0 / 2.0, 1.0,
+);
+
+/// The largest pitch in degrees (looking straight up or down flips the view).
+const MAX_PITCH: f32 = 89.0;
+/// The range of the orbit distance.
+const MIN_DISTANCE: f32 = 0.5;
+const MAX_DISTANCE: f32 = 50.0;
+/// The range of the vertical field of view in degrees.
+const MIN_FOV: f32 = 15.0;
+const MAX_FOV: f32 = 90.0;
+
+/// How the camera moves when it is rotated or zoomed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum CameraMode {
+    /// Rotating moves the eye around the point `distance` in front of it and
+    /// zooming changes that distance.
+    Orbit,
+    /// Rotating turns the eye in place and zooming narrows the field of view.
+    Fly,
+}
+
+impl CameraMode {
+    /// The other mode.
+    pub fn toggled(self) -> Self {
+        match self {
+            Self::Orbit => Self::Fly,
+            Self::Fly => Self::Orbit,
+        }
+    }
+}
+
+/// A perspective camera.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Camera {
+    pub mode: CameraMode,
+    /// The position of the eye.
+    pub position: Point3<f32>,
+    /// The angle of the view direction around [`UP`] in degrees (0 looks along +X).
+    pub yaw: f32,
+    /// The angle of the view direction above the horizon in degrees.
+    pub pitch: f32,
+    /// The distance of the orbited point in front of the eye.
+    pub distance: f32,
+    /// The vertical field of view in degrees.
+    pub fov: f32,
+    pub near: f32,
+    pub far: f32,
+}
+
+impl Camera {
+    /// Creates an orbit camera at `position` looking at `target`.
+    pub fn look_at(position: Point3<f32>, target: Point3<f32>) -> Self {
+        let offset = target - position;
+        let distance = offset.magnitude();
+
+        Self {
+            mode: CameraMode::Orbit,
+            position,
+            yaw: offset.y.atan2(offset.x).to_degrees(),
+            pitch: (offset.z / distance).asin().to_degrees(),
+            distance,
+            fov: 45.0,
+            near: 0.1,
+            far: 100.0,
+        }
+    }
+
+    /// The (unit) direction the camera looks in.
+    pub fn forward(&self) -> Vec3 {
+        let (yaw, pitch) = (self.yaw.to_radians(), self.pitch.to_radians());
+        vec3(pitch.cos() * yaw.cos(), pitch.cos() * yaw.sin(), pitch.sin())
+    }
+
+    /// The (unit) horizontal direction to the right of the view.
+    pub fn right(&self) -> Vec3 {
+        self.forward().cross(UP).normalize()
+    }
+
+    /// The point the camera orbits around.
+    pub fn target(&self) -> Point3<f32> {
+        self.position + self.forward() * self.distance
+    }
+
+    /// Turns the view by the given angles in degrees (around the target in
+    /// orbit mode), keeping the pitch short of straight up or down.
+    pub fn rotate(&mut self, yaw: f32, pitch: f32) {
+        let target = self.target();
+        self.yaw = (self.yaw + yaw) % 360.0;
+        self.pitch = (self.pitch + pitch).clamp(-MAX_PITCH, MAX_PITCH);
+
+        if self.mode == CameraMode::Orbit {
+            self.position = target - self.forward() * self.distance;
+        }
+    }
+
+    /// Zooms in by `steps` scroll wheel steps (out for negative steps).
+    pub fn zoom(&mut self, steps: f32) {
+        match self.mode {
+            CameraMode::Orbit => {
+                let target = self.target();
+                self.distance = (self.distance * 0.9f32.powf(steps)).clamp(MIN_DISTANCE, MAX_DISTANCE);
+                self.position = target - self.forward() * self.distance;
+            }
+            CameraMode::Fly => self.fov = (self.fov - steps * 5.0).clamp(MIN_FOV, MAX_FOV),
+        }
+    }
+
+    /// Moves the eye (and with it the orbited point) by a world space offset.
+    pub fn translate(&mut self, offset: Vec3) {
+        self.position += offset;
+    }
+
+    pub fn view(&self) -> Mat4 {
+        Mat4::look_at_rh(self.position, self.target(), UP)
+    }
+
+    /// The Vulkan projection matrix for a viewport with the given aspect ratio.
+    pub fn projection(&self, aspect: f32) -> Mat4 {
+        CORRECTION * cgmath::perspective(Deg(self.fov), aspect, self.near, self.far)
+    }
+}
+
+//================================================
+// Controller
+//================================================
+
+/// Moves a [`Camera`] from the actions of an [`Input`]: the move actions move
+/// it, the grabbed mouse rotates it, the scroll wheel zooms and
+/// [`Action::ToggleCameraMode`] switches between orbit and fly mode.
+#[derive(Copy, Clone, Debug)]
+pub struct CameraController {
+    /// The movement speed in units per second.
+    pub speed: f32,
+    /// The rotation in degrees per pixel of mouse movement.
+    pub sensitivity: f32,
+}
+
+impl Default for CameraController {
+    fn default() -> Self {
+        Self { speed: 2.0, sensitivity: 0.2 }
+    }
+}
+
+impl CameraController {
+    /// Applies the input of a frame to a camera, moving it by `speed` times
+    /// the `delta` seconds the frame took.
+    pub fn update(&self, camera: &mut Camera, input: &Input, delta: f32) {
+        if input.was_pressed(Action::ToggleCameraMode) {
+            camera.mode = camera.mode.toggled();
+        }
+
+        let (x, y) = input.mouse_delta();
+        if x != 0.0 || y != 0.0 {
+            camera.rotate(-x * self.sensitivity, -y * self.sensitivity);
+        }
+
+        if input.scroll() != 0.0 {
+            camera.zoom(input.scroll());
+        }
+
+        let axis = |positive, negative| {
+            input.is_held(positive) as i32 as f32 - input.is_held(negative) as i32 as f32
+        };
+
+        // Orbit mode moves on the horizontal plane, fly mode where the camera looks
+        let forward = match camera.mode {
+            CameraMode::Orbit => UP.cross(camera.right()),
+            CameraMode::Fly => camera.forward(),
+        };
+
+        let direction = forward * axis(Action::MoveForward, Action::MoveBack)
+            + camera.right() * axis(Action::MoveRight, Action::MoveLeft)
+            + UP * axis(Action::MoveUp, Action::MoveDown);
+
+        if direction.magnitude2() > 0.0 {
+            camera.translate(direction.normalize() * self.speed * delta);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::input::Trigger;
+
+    use cgmath::{point3, EuclideanSpace, MetricSpace};
+    use winit::event::VirtualKeyCode;
+
+    fn assert_near(a: Point3<f32>, b: Point3<f32>) {
+        assert!((a - b).magnitude() < 1e-4, "{:?} != {:?}", a, b);
+    }
+
+    #[test]
+    fn look_at_matches_the_view_matrix() {
+        let (eye, target) = (point3(6.0, 0.0, 2.0), Point3::origin());
+        let camera = Camera::look_at(eye, target);
+
+        assert_near(camera.target(), target);
+        let (view, expected) = (camera.view(), Mat4::look_at_rh(eye, target, UP));
+        for (a, b) in AsRef::<[f32; 16]>::as_ref(&view).iter().zip(AsRef::<[f32; 16]>::as_ref(&expected)) {
+            assert!((a - b).abs() < 1e-5, "{:?} != {:?}", view, expected);
+        }
+    }
+
+    #[test]
+    fn orbiting_keeps_the_target_and_distance() {
+        let mut camera = Camera::look_at(point3(6.0, 0.0, 2.0), Point3::origin());
+        camera.rotate(90.0, 200.0);
+
+        assert_near(camera.target(), Point3::origin());
+        assert!((camera.position.distance(Point3::origin()) - 40f32.sqrt()).abs() < 1e-4);
+        assert_eq!(camera.pitch, MAX_PITCH);
+
+        camera.zoom(100.0);
+        assert_near(camera.target(), Point3::origin());
+        assert_eq!(camera.distance, MIN_DISTANCE);
+    }
+
+    #[test]
+    fn flying_turns_in_place() {
+        let mut camera = Camera::look_at(point3(6.0, 0.0, 2.0), Point3::origin());
+        camera.mode = CameraMode::Fly;
+        camera.rotate(45.0, 0.0);
+        camera.zoom(-100.0);
+
+        assert_near(camera.position, point3(6.0, 0.0, 2.0));
+        assert_eq!(camera.fov, MAX_FOV);
+    }
+
+    #[test]
+    fn movement_does_not_depend_on_the_frame_time() {
+        let start = Camera::look_at(point3(6.0, 0.0, 2.0), Point3::origin());
+
+        let controller = CameraController::default();
+        let mut input = Input::default();
+        input.trigger(Trigger::Key(VirtualKeyCode::W), true);
+        input.trigger(Trigger::Key(VirtualKeyCode::D), true);
+
+        let mut one_frame = start;
+        controller.update(&mut one_frame, &input, 1.0);
+        let mut many_frames = start;
+        for _ in 0..100 {
+            controller.update(&mut many_frames, &input, 0.01);
+        }
+
+        assert_near(one_frame.position, many_frames.position);
+        assert!((one_frame.position.distance(start.position) - controller.speed).abs() < 1e-4);
+        // Orbit mode moves horizontally
+        assert!((one_frame.position.z - start.position.z).abs() < 1e-6);
+
+        input.trigger(Trigger::Key(VirtualKeyCode::W), false);
+        input.trigger(Trigger::Key(VirtualKeyCode::D), false);
+        let mut idle = start;
+        controller.update(&mut idle, &input, 1.0);
+        assert_eq!(idle, start);
+    }
+
+    #[test]
+    fn the_mode_toggles_once_per_press() {
+        let mut camera = Camera::look_at(point3(6.0, 0.0, 2.0), Point3::origin());
+        let controller = CameraController::default();
+        let mut input = Input::default();
+
+        // The mouse only rotates a grabbed cursor
+        input.mouse_motion((10.0, 0.0));
+        input.trigger(Trigger::Key(VirtualKeyCode::Tab), true);
+        controller.update(&mut camera, &input, 0.1);
+        assert_eq!(camera.mode, CameraMode::Fly);
+        assert_eq!(camera.yaw, 180.0);
+
+        input.end_frame();
+        controller.update(&mut camera, &input, 0.1);
+        assert_eq!(camera.mode, CameraMode::Fly);
+    }
+}
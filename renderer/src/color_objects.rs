@@ -0,0 +1,57 @@
+//================================================
+// Color Objects
+//================================================
+use crate::app_data::AppData;
+use crate::images;
+use crate::owned::{Image, Owned};
+
+use std::rc::Rc;
+
+use anyhow::Result;
+use vulkanalia::prelude::v1_0::*;
+
+/// Creates the multisampled color attachment (without multisampling the
+/// swapchain images are rendered to directly, so there is none).
+pub unsafe fn create_color_objects(instance: &Instance, device: &Rc<Device>, data: &mut AppData) -> Result<()> {
+    (data.color_image, data.color_image_view) = build_color_objects(instance, device, data, data.msaa_samples)?;
+
+    Ok(())
+}
+
+/// Builds a color attachment with `samples` samples (a null one for 1).
+pub unsafe fn build_color_objects(
+    instance: &Instance,
+    device: &Rc<Device>,
+    data: &AppData,
+    samples: vk::SampleCountFlags,
+) -> Result<(Image, Owned<vk::ImageView>)> {
+    if samples == vk::SampleCountFlags::_1 {
+        return Ok((Image::default(), Owned::default()));
+    }
+
+    // Image + Image Memory
+    let image = images::create_image(
+        instance,
+        device,
+        data,
+        data.swapchain_extent.width,
+        data.swapchain_extent.height,
+        1,
+        samples,
+        data.swapchain_format,
+        vk::ImageTiling::OPTIMAL,
+        vk::ImageUsageFlags::COLOR_ATTACHMENT | vk::ImageUsageFlags::TRANSIENT_ATTACHMENT,
+        vk::MemoryPropertyFlags::DEVICE_LOCAL,
+    )?;
+
+    // Image View
+    let image_view = images::create_image_view(
+        device,
+        *image,
+        data.swapchain_format,
+        vk::ImageAspectFlags::COLOR,
+        1,
+    )?;
+
+    Ok((image, image_view))
+}
\ No newline at end of file
@@ -0,0 +1,58 @@
+//================================================
+// Command Pool
+//================================================
+use crate::app_data::AppData;
+use crate::owned::Owned;
+use crate::structs::QueueFamilyIndices;
+
+use std::rc::Rc;
+
+use anyhow::Result;
+use vulkanalia::prelude::v1_0::*;
+
+/// Creates the global command pool (used for one-time commands and buffers
+/// that are recorded once).
+pub unsafe fn create_command_pool(instance: &Instance, device: &Rc<Device>, data: &mut AppData) -> Result<()> {
+    let indices = QueueFamilyIndices::get(instance, data, data.physical_device)?;
+
+    let info = vk::CommandPoolCreateInfo::builder().queue_family_index(indices.graphics);
+
+    data.command_pool = Owned::new(device, device.create_command_pool(&info, None)?);
+
+    Ok(())
+}
+
+/// Creates the global command pool and `count` transient command pools (one
+/// per swapchain image or frame in flight, for command buffers that are
+/// re-recorded every frame).
+pub unsafe fn create_command_pools(instance: &Instance, device: &Rc<Device>, data: &mut AppData, count: usize) -> Result<()> {
+    // Global
+    create_command_pool(instance, device, data)?;
+
+    // Per-frame
+    create_transient_command_pools(instance, device, data, count)
+}
+
+/// Creates transient command pools until there are `count` of them, or
+/// releases the extra ones (after the swapchain was recreated with a different
+/// number of images).
+pub unsafe fn create_transient_command_pools(instance: &Instance, device: &Rc<Device>, data: &mut AppData, count: usize) -> Result<()> {
+    data.command_pools.truncate(count);
+
+    while data.command_pools.len() < count {
+        let command_pool = create_transient_command_pool(instance, device, data)?;
+        data.command_pools.push(command_pool);
+    }
+
+    Ok(())
+}
+
+unsafe fn create_transient_command_pool(instance: &Instance, device: &Rc<Device>, data: &mut AppData) -> Result<Owned<vk::CommandPool>> {
+    let indices = QueueFamilyIndices::get(instance, data, data.physical_device)?;
+
+    let info = vk::CommandPoolCreateInfo::builder()
+        .flags(vk::CommandPoolCreateFlags::TRANSIENT)
+        .queue_family_index(indices.graphics);
+
+    Ok(Owned::new(device, device.create_command_pool(&info, None)?))
+}
\ No newline at end of file
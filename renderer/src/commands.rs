@@ -0,0 +1,75 @@
+//================================================
+// Commands
+//================================================
+use crate::app_data::AppData;
+use crate::memory::Allocator;
+
+use std::rc::Rc;
+
+use anyhow::Result;
+use vulkanalia::prelude::v1_0::*;
+
+/// What one-time commands need: the pool they are allocated from, the queue
+/// they are submitted to and the allocator of the buffers they copy through.
+///
+/// The chapters that keep their own app data with plain handles build one
+/// from those, the others from their [`AppData`].
+#[derive(Copy, Clone, Debug)]
+pub struct Transfer<'a> {
+    pub allocator:    &'a Rc<Allocator>,
+    pub command_pool: vk::CommandPool,
+    pub queue:        vk::Queue,
+}
+
+impl<'a> From<&'a AppData> for Transfer<'a> {
+    fn from(data: &'a AppData) -> Self {
+        Self {
+            allocator: &data.allocator,
+            command_pool: *data.command_pool,
+            queue: data.graphics_queue,
+        }
+    }
+}
+
+pub unsafe fn begin_single_time_commands(device: &Device, data: &AppData) -> Result<vk::CommandBuffer> {
+    begin_transfer_commands(device, &Transfer::from(data))
+}
+
+pub unsafe fn end_single_time_commands(device: &Device, data: &AppData, command_buffer: vk::CommandBuffer) -> Result<()> {
+    end_transfer_commands(device, &Transfer::from(data), command_buffer)
+}
+
+pub unsafe fn begin_transfer_commands(device: &Device, transfer: &Transfer) -> Result<vk::CommandBuffer> {
+    // Allocate
+    let info = vk::CommandBufferAllocateInfo::builder()
+        .level(vk::CommandBufferLevel::PRIMARY)
+        .command_pool(transfer.command_pool)
+        .command_buffer_count(1);
+
+    let command_buffer = device.allocate_command_buffers(&info)?[0];
+
+    // Begin
+
+    let info = vk::CommandBufferBeginInfo::builder().flags(vk::CommandBufferUsageFlags::ONE_TIME_SUBMIT);
+
+    device.begin_command_buffer(command_buffer, &info)?;
+
+    Ok(command_buffer)
+}
+
+pub unsafe fn end_transfer_commands(device: &Device, transfer: &Transfer, command_buffer: vk::CommandBuffer) -> Result<()> {
+    // End
+    device.end_command_buffer(command_buffer)?;
+
+    // Submit
+    let command_buffers = &[command_buffer];
+    let info = vk::SubmitInfo::builder().command_buffers(command_buffers);
+
+    device.queue_submit(transfer.queue, &[info], vk::Fence::null())?;
+    device.queue_wait_idle(transfer.queue)?;
+
+    // Cleanup
+    device.free_command_buffers(transfer.command_pool, &[command_buffer]);
+
+    Ok(())
+}
@@ -0,0 +1,491 @@
+//================================================
+// Compressed
+//================================================
+//! Block compressed textures stored in KTX2 and DDS files, uploaded as they
+//! are (with every mip level and array layer the file contains) instead of
+//! being decoded to RGBA8 and mipmapped on the GPU.
+//!
+//! KTX2 files may contain any BC1–BC7, ETC2/EAC or ASTC format (which the
+//! device has to support, see [`check_format`]), DDS files any BC1–BC7
+//! format. Supercompressed KTX2 files (Basis Universal or zstd) and 3D
+//! textures are not supported.
+
+use crate::app_data::AppData;
+use crate::buffers;
+use crate::images::{self, ImageInfo};
+use crate::owned::Image;
+use crate::texture::Texture;
+
+use std::fs;
+use std::ops::Range;
+use std::path::Path;
+use std::ptr::copy_nonoverlapping as memcpy;
+use std::rc::Rc;
+
+use anyhow::{anyhow, Context, Result};
+use ddsfile::DxgiFormat;
+use vulkanalia::prelude::v1_0::*;
+
+/// The texels of a compressed image, level by level.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CompressedImage {
+    pub format: vk::Format,
+    pub width:  u32,
+    pub height: u32,
+    /// The number of array layers (six per cube map).
+    pub layers: u32,
+    /// Whether the layers are the faces of cube maps.
+    pub cube:   bool,
+    /// The range of each mip level in `data`, which holds the level of every
+    /// layer one after another (the largest level first).
+    pub levels: Vec<Range<usize>>,
+    pub data:   Vec<u8>,
+}
+
+impl CompressedImage {
+    pub fn mip_levels(&self) -> u32 {
+        self.levels.len() as u32
+    }
+}
+
+/// Whether a file is loaded with [`load_compressed`] (by its extension).
+pub fn is_compressed_file(path: &Path) -> bool {
+    matches!(
+        path.extension().and_then(|e| e.to_str()),
+        Some(e) if e.eq_ignore_ascii_case("ktx2") || e.eq_ignore_ascii_case("dds"),
+    )
+}
+
+/// Reads a KTX2 or DDS file (recognized by its contents).
+pub fn load_compressed(path: &Path) -> Result<CompressedImage> {
+    let bytes = fs::read(path).with_context(|| format!("Failed to read texture `{}`.", path.display()))?;
+    let image = if bytes.starts_with(b"\xabKTX 20\xbb\r\n\x1a\n") {
+        parse_ktx2(&bytes)
+    } else if bytes.starts_with(b"DDS ") {
+        parse_dds(&bytes)
+    } else {
+        Err(anyhow!("Unsupported texture format (expected KTX2 or DDS)."))
+    };
+
+    image.with_context(|| format!("Invalid texture `{}`.", path.display()))
+}
+
+pub fn parse_ktx2(bytes: &[u8]) -> Result<CompressedImage> {
+    let reader = ktx2::Reader::new(bytes).map_err(|e| anyhow!("{}", e))?;
+    let header = reader.header();
+
+    if let Some(scheme) = header.supercompression_scheme {
+        return Err(anyhow!("Supercompressed ({:?}) KTX2 files are not supported.", scheme));
+    } else if header.pixel_depth > 1 {
+        return Err(anyhow!("3D textures are not supported."));
+    }
+
+    let format = match header.format {
+        Some(format) => vk::Format::from_raw(format.value() as i32),
+        None => return Err(anyhow!("KTX2 files without a Vulkan format are not supported.")),
+    };
+
+    let cube = header.face_count == 6;
+    let layers = header.layer_count.max(1) * header.face_count.max(1);
+
+    let mut data = vec![];
+    let mut levels = vec![];
+    for level in reader.levels() {
+        levels.push(data.len()..data.len() + level.data.len());
+        data.extend_from_slice(level.data);
+    }
+
+    let image = CompressedImage {
+        format,
+        width: header.pixel_width,
+        height: header.pixel_height.max(1),
+        layers,
+        cube,
+        levels,
+        data,
+    };
+
+    check_level_sizes(&image)?;
+
+    Ok(image)
+}
+
+pub fn parse_dds(bytes: &[u8]) -> Result<CompressedImage> {
+    let dds = ddsfile::Dds::read(bytes).map_err(|e| anyhow!("{}", e))?;
+
+    let format = match dds.get_dxgi_format() {
+        Some(format) => dxgi_format(format)?,
+        None => return Err(anyhow!("Unsupported DDS format {:?}.", dds.get_d3d_format())),
+    };
+
+    if dds.get_depth() > 1 {
+        return Err(anyhow!("3D textures are not supported."));
+    }
+
+    // DX10 headers count cube maps rather than faces
+    let cube = dds.header.caps2.contains(ddsfile::Caps2::CUBEMAP)
+        || dds.header10.as_ref().is_some_and(|h| h.misc_flag.contains(ddsfile::MiscFlag::TEXTURECUBE));
+    let layers = match &dds.header10 {
+        Some(header10) if cube => header10.array_size.max(1) * 6,
+        Some(header10) => header10.array_size.max(1),
+        None if cube => 6,
+        None => 1,
+    };
+
+    let (width, height) = (dds.get_width(), dds.get_height());
+    let sizes = (0..dds.get_num_mipmap_levels().max(1))
+        .map(|level| level_size(format, width >> level, height >> level))
+        .collect::<Result<Vec<_>>>()?;
+
+    // DDS files store every level of a layer before the next layer
+    let layer_size = sizes.iter().sum::<usize>();
+    if dds.data.len() < layer_size * layers as usize {
+        return Err(anyhow!("Expected {} bytes of texels, found {}.", layer_size * layers as usize, dds.data.len()));
+    }
+
+    let mut data = Vec::with_capacity(layer_size * layers as usize);
+    let mut levels = vec![];
+    for (level, size) in sizes.iter().enumerate() {
+        let offset = sizes[..level].iter().sum::<usize>();
+        levels.push(data.len()..data.len() + size * layers as usize);
+        for layer in 0..layers as usize {
+            let start = layer * layer_size + offset;
+            data.extend_from_slice(&dds.data[start..start + size]);
+        }
+    }
+
+    Ok(CompressedImage { format, width, height, layers, cube, levels, data })
+}
+
+/// The Vulkan format of a DXGI format.
+fn dxgi_format(format: DxgiFormat) -> Result<vk::Format> {
+    Ok(match format {
+        DxgiFormat::BC1_UNorm => vk::Format::BC1_RGBA_UNORM_BLOCK,
+        DxgiFormat::BC1_UNorm_sRGB => vk::Format::BC1_RGBA_SRGB_BLOCK,
+        DxgiFormat::BC2_UNorm => vk::Format::BC2_UNORM_BLOCK,
+        DxgiFormat::BC2_UNorm_sRGB => vk::Format::BC2_SRGB_BLOCK,
+        DxgiFormat::BC3_UNorm => vk::Format::BC3_UNORM_BLOCK,
+        DxgiFormat::BC3_UNorm_sRGB => vk::Format::BC3_SRGB_BLOCK,
+        DxgiFormat::BC4_UNorm => vk::Format::BC4_UNORM_BLOCK,
+        DxgiFormat::BC4_SNorm => vk::Format::BC4_SNORM_BLOCK,
+        DxgiFormat::BC5_UNorm => vk::Format::BC5_UNORM_BLOCK,
+        DxgiFormat::BC5_SNorm => vk::Format::BC5_SNORM_BLOCK,
+        DxgiFormat::BC6H_UF16 => vk::Format::BC6H_UFLOAT_BLOCK,
+        DxgiFormat::BC6H_SF16 => vk::Format::BC6H_SFLOAT_BLOCK,
+        DxgiFormat::BC7_UNorm => vk::Format::BC7_UNORM_BLOCK,
+        DxgiFormat::BC7_UNorm_sRGB => vk::Format::BC7_SRGB_BLOCK,
+        DxgiFormat::R8G8B8A8_UNorm => vk::Format::R8G8B8A8_UNORM,
+        DxgiFormat::R8G8B8A8_UNorm_sRGB => vk::Format::R8G8B8A8_SRGB,
+        format => return Err(anyhow!("Unsupported DDS format {:?}.", format)),
+    })
+}
+
+/// The width and height of a format's blocks (of texels) and their size in
+/// bytes (`None` for formats that cannot be loaded).
+fn block_info(format: vk::Format) -> Option<(u32, u32, usize)> {
+    use vk::Format as F;
+
+    Some(match format {
+        F::R8G8B8A8_UNORM | F::R8G8B8A8_SRGB => (1, 1, 4),
+        F::BC1_RGB_UNORM_BLOCK
+        | F::BC1_RGB_SRGB_BLOCK
+        | F::BC1_RGBA_UNORM_BLOCK
+        | F::BC1_RGBA_SRGB_BLOCK
+        | F::BC4_UNORM_BLOCK
+        | F::BC4_SNORM_BLOCK
+        | F::ETC2_R8G8B8_UNORM_BLOCK
+        | F::ETC2_R8G8B8_SRGB_BLOCK
+        | F::ETC2_R8G8B8A1_UNORM_BLOCK
+        | F::ETC2_R8G8B8A1_SRGB_BLOCK
+        | F::EAC_R11_UNORM_BLOCK
+        | F::EAC_R11_SNORM_BLOCK => (4, 4, 8),
+        F::BC2_UNORM_BLOCK
+        | F::BC2_SRGB_BLOCK
+        | F::BC3_UNORM_BLOCK
+        | F::BC3_SRGB_BLOCK
+        | F::BC5_UNORM_BLOCK
+        | F::BC5_SNORM_BLOCK
+        | F::BC6H_UFLOAT_BLOCK
+        | F::BC6H_SFLOAT_BLOCK
+        | F::BC7_UNORM_BLOCK
+        | F::BC7_SRGB_BLOCK
+        | F::ETC2_R8G8B8A8_UNORM_BLOCK
+        | F::ETC2_R8G8B8A8_SRGB_BLOCK
+        | F::EAC_R11G11_UNORM_BLOCK
+        | F::EAC_R11G11_SNORM_BLOCK => (4, 4, 16),
+        // Every ASTC block is 16 bytes, whatever its size in texels
+        F::ASTC_4X4_UNORM_BLOCK | F::ASTC_4X4_SRGB_BLOCK => (4, 4, 16),
+        F::ASTC_5X4_UNORM_BLOCK | F::ASTC_5X4_SRGB_BLOCK => (5, 4, 16),
+        F::ASTC_5X5_UNORM_BLOCK | F::ASTC_5X5_SRGB_BLOCK => (5, 5, 16),
+        F::ASTC_6X5_UNORM_BLOCK | F::ASTC_6X5_SRGB_BLOCK => (6, 5, 16),
+        F::ASTC_6X6_UNORM_BLOCK | F::ASTC_6X6_SRGB_BLOCK => (6, 6, 16),
+        F::ASTC_8X5_UNORM_BLOCK | F::ASTC_8X5_SRGB_BLOCK => (8, 5, 16),
+        F::ASTC_8X6_UNORM_BLOCK | F::ASTC_8X6_SRGB_BLOCK => (8, 6, 16),
+        F::ASTC_8X8_UNORM_BLOCK | F::ASTC_8X8_SRGB_BLOCK => (8, 8, 16),
+        F::ASTC_10X5_UNORM_BLOCK | F::ASTC_10X5_SRGB_BLOCK => (10, 5, 16),
+        F::ASTC_10X6_UNORM_BLOCK | F::ASTC_10X6_SRGB_BLOCK => (10, 6, 16),
+        F::ASTC_10X8_UNORM_BLOCK | F::ASTC_10X8_SRGB_BLOCK => (10, 8, 16),
+        F::ASTC_10X10_UNORM_BLOCK | F::ASTC_10X10_SRGB_BLOCK => (10, 10, 16),
+        F::ASTC_12X10_UNORM_BLOCK | F::ASTC_12X10_SRGB_BLOCK => (12, 10, 16),
+        F::ASTC_12X12_UNORM_BLOCK | F::ASTC_12X12_SRGB_BLOCK => (12, 12, 16),
+        _ => return None,
+    })
+}
+
+/// The size in bytes of one layer of a level (partial blocks at the right and
+/// bottom edges are stored as whole blocks).
+pub fn level_size(format: vk::Format, width: u32, height: u32) -> Result<usize> {
+    let (block_width, block_height, block_size) =
+        block_info(format).ok_or_else(|| anyhow!("Unsupported texture format {:?}.", format))?;
+    let blocks = width.max(1).div_ceil(block_width) * height.max(1).div_ceil(block_height);
+    Ok(blocks as usize * block_size)
+}
+
+/// Checks that every level holds exactly the texels of each layer.
+fn check_level_sizes(image: &CompressedImage) -> Result<()> {
+    for (level, range) in image.levels.iter().enumerate() {
+        let expected = level_size(image.format, image.width >> level, image.height >> level)? * image.layers as usize;
+        if range.len() != expected {
+            return Err(anyhow!("Expected {} bytes for mip level {}, found {}.", expected, level, range.len()));
+        }
+    }
+
+    Ok(())
+}
+
+/// Checks that the device can sample images in a compressed format (they are
+/// never uploaded in another one, as that would change their color space).
+pub unsafe fn check_format(instance: &Instance, data: &AppData, format: vk::Format) -> Result<()> {
+    images::get_supported_format(
+        instance,
+        data,
+        &[format],
+        vk::ImageTiling::OPTIMAL,
+        vk::FormatFeatureFlags::SAMPLED_IMAGE,
+    )
+    .map_err(|_| anyhow!("The device cannot sample {:?} textures.", format))?;
+
+    Ok(())
+}
+
+/// Copies every level and layer of a compressed image into a new image (in its
+/// format) ready for sampling.
+pub unsafe fn upload_compressed_image(
+    instance: &Instance,
+    device: &Rc<Device>,
+    data: &AppData,
+    compressed: &CompressedImage,
+) -> Result<Image> {
+    check_format(instance, data, compressed.format)?;
+    let CompressedImage { format, width, height, layers, cube, .. } = *compressed;
+    let mip_levels = compressed.mip_levels();
+
+    // Staging buffer
+    let staging_buffer = buffers::create_buffer(
+        instance,
+        device,
+        data,
+        compressed.data.len() as u64,
+        vk::BufferUsageFlags::TRANSFER_SRC,
+        vk::MemoryPropertyFlags::HOST_COHERENT | vk::MemoryPropertyFlags::HOST_VISIBLE,
+    )?;
+
+    memcpy(compressed.data.as_ptr(), staging_buffer.memory().mapped.cast(), compressed.data.len());
+
+    // Image
+    let flags = if cube { vk::ImageCreateFlags::CUBE_COMPATIBLE } else { vk::ImageCreateFlags::empty() };
+    let info = ImageInfo {
+        extent: vk::Extent2D { width, height },
+        mip_levels,
+        layers,
+        flags,
+        samples: vk::SampleCountFlags::_1,
+        format,
+        tiling: vk::ImageTiling::OPTIMAL,
+        usage: vk::ImageUsageFlags::SAMPLED | vk::ImageUsageFlags::TRANSFER_DST,
+    };
+    let image = images::create_image_array(instance, device, data, &info, vk::MemoryPropertyFlags::DEVICE_LOCAL)?;
+
+    images::transition_image_layers(
+        device,
+        data,
+        *image,
+        vk::ImageLayout::UNDEFINED,
+        vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+        mip_levels,
+        layers,
+    )?;
+
+    // Copy (a region per level, covering all of its layers)
+    let regions = compressed
+        .levels
+        .iter()
+        .enumerate()
+        .map(|(level, range)| {
+            let subresource = vk::ImageSubresourceLayers::builder()
+                .aspect_mask(vk::ImageAspectFlags::COLOR)
+                .mip_level(level as u32)
+                .base_array_layer(0)
+                .layer_count(layers);
+
+            vk::BufferImageCopy::builder()
+                .buffer_offset(range.start as u64)
+                .buffer_row_length(0)
+                .buffer_image_height(0)
+                .image_subresource(subresource)
+                .image_offset(vk::Offset3D { x: 0, y: 0, z: 0 })
+                .image_extent(vk::Extent3D {
+                    width: (width >> level).max(1),
+                    height: (height >> level).max(1),
+                    depth: 1,
+                })
+                .build()
+        })
+        .collect::<Vec<_>>();
+
+    images::copy_buffer_to_image_regions(device, data, *staging_buffer, *image, &regions)?;
+
+    // Cleanup
+    drop(staging_buffer);
+
+    images::transition_image_layers(
+        device,
+        data,
+        *image,
+        vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+        vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+        mip_levels,
+        layers,
+    )?;
+
+    Ok(image)
+}
+
+/// Creates a texture from a compressed image, with a 2D view of its first
+/// layer (which is what the shaders sample).
+pub unsafe fn create_compressed_texture(
+    instance: &Instance,
+    device: &Rc<Device>,
+    data: &AppData,
+    compressed: &CompressedImage,
+) -> Result<Texture> {
+    let image = upload_compressed_image(instance, device, data, compressed)?;
+    let (format, mip_levels) = (compressed.format, compressed.mip_levels());
+    let view = images::create_image_view(device, *image, format, vk::ImageAspectFlags::COLOR, mip_levels)?;
+
+    Ok(Texture { image, view, format, mip_levels })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A KTX2 file with the given levels (the level index lists the largest
+    /// level first, the data stores the smallest first).
+    fn ktx2(format: vk::Format, width: u32, height: u32, layers: u32, faces: u32, levels: &[Vec<u8>]) -> Vec<u8> {
+        let index_end = 80 + 24 * levels.len();
+        let dfd = [28u32.to_le_bytes(), [0; 4], [0; 4], [0; 4], [0; 4], [0; 4], [0; 4]].concat();
+        let mut level_offset = (index_end + dfd.len()).next_multiple_of(16);
+
+        let mut index = vec![0u8; levels.len() * 24];
+        for (level, texels) in levels.iter().enumerate().rev() {
+            let entry = &mut index[level * 24..level * 24 + 24];
+            entry[..8].copy_from_slice(&(level_offset as u64).to_le_bytes());
+            entry[8..16].copy_from_slice(&(texels.len() as u64).to_le_bytes());
+            entry[16..].copy_from_slice(&(texels.len() as u64).to_le_bytes());
+            level_offset = (level_offset + texels.len()).next_multiple_of(16);
+        }
+
+        let mut bytes = b"\xabKTX 20\xbb\r\n\x1a\n".to_vec();
+        let fields = [format.as_raw() as u32, 1, width, height, 0, layers, faces, levels.len() as u32, 0];
+        bytes.extend(fields.iter().flat_map(|f| f.to_le_bytes()));
+        bytes.extend((index_end as u32).to_le_bytes());
+        bytes.extend((dfd.len() as u32).to_le_bytes());
+        bytes.extend([0; 24]);
+        bytes.extend(index);
+        bytes.extend(dfd);
+        for texels in levels.iter().rev() {
+            bytes.resize(bytes.len().next_multiple_of(16), 0);
+            bytes.extend(texels);
+        }
+        bytes.push(0);
+
+        bytes
+    }
+
+    #[test]
+    fn ktx2_files_keep_every_level_and_layer() -> Result<()> {
+        let format = vk::Format::BC7_SRGB_BLOCK;
+        // 8x4 texels in two layers: 2 blocks, then 1 block (4x2 and 2x1)
+        let levels = [vec![1; 2 * 2 * 16], vec![2; 2 * 16], vec![3; 2 * 16]];
+        let image = parse_ktx2(&ktx2(format, 8, 4, 2, 1, &levels))?;
+
+        assert_eq!((image.format, image.width, image.height), (format, 8, 4));
+        assert_eq!((image.layers, image.cube, image.mip_levels()), (2, false, 3));
+        assert_eq!(image.levels, [0..64, 64..96, 96..128]);
+        assert_eq!(&image.data[60..68], [1, 1, 1, 1, 2, 2, 2, 2]);
+
+        // Cube maps have six faces
+        let faces = parse_ktx2(&ktx2(vk::Format::ASTC_8X8_UNORM_BLOCK, 10, 10, 0, 6, &[vec![0; 6 * 4 * 16]]))?;
+        assert_eq!((faces.layers, faces.cube), (6, true));
+
+        // Levels of the wrong size
+        assert!(parse_ktx2(&ktx2(format, 8, 4, 2, 1, &[vec![0; 16]])).is_err());
+        // Formats without a known block size
+        assert!(parse_ktx2(&ktx2(vk::Format::R32_SFLOAT, 1, 1, 0, 1, &[vec![0; 4]])).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn dds_files_are_reordered_level_by_level() -> Result<()> {
+        let mut dds = ddsfile::Dds::new_dxgi(ddsfile::NewDxgiParams {
+            height: 8,
+            width: 8,
+            depth: None,
+            format: DxgiFormat::BC1_UNorm_sRGB,
+            mipmap_levels: Some(4),
+            array_layers: Some(2),
+            caps2: None,
+            is_cubemap: false,
+            resource_dimension: ddsfile::D3D10ResourceDimension::Texture2D,
+            alpha_mode: ddsfile::AlphaMode::Unknown,
+        })?;
+
+        // 4, 1, 1 and 1 blocks of 8 bytes per layer
+        assert_eq!(dds.data.len(), 2 * 7 * 8);
+        for (layer, texels) in dds.data.chunks_exact_mut(7 * 8).enumerate() {
+            for (level, range) in [0..32, 32..40, 40..48, 48..56].into_iter().enumerate() {
+                texels[range].fill(10 * layer as u8 + level as u8);
+            }
+        }
+
+        let mut bytes = vec![];
+        dds.write(&mut bytes)?;
+        let image = parse_dds(&bytes)?;
+
+        assert_eq!((image.format, image.width, image.height), (vk::Format::BC1_RGBA_SRGB_BLOCK, 8, 8));
+        assert_eq!((image.layers, image.mip_levels()), (2, 4));
+        assert_eq!(image.levels, [0..64, 64..80, 80..96, 96..112]);
+        assert_eq!(image.data[..64], [[0; 32], [10; 32]].concat());
+        assert_eq!(image.data[64..80], [[1; 8], [11; 8]].concat());
+        assert_eq!(image.data[96..], [[3; 8], [13; 8]].concat());
+
+        Ok(())
+    }
+
+    #[test]
+    fn level_sizes_round_up_to_whole_blocks() -> Result<()> {
+        assert_eq!(level_size(vk::Format::BC1_RGB_UNORM_BLOCK, 5, 3)?, 2 * 8);
+        assert_eq!(level_size(vk::Format::BC3_SRGB_BLOCK, 1, 1)?, 16);
+        assert_eq!(level_size(vk::Format::ASTC_10X5_SRGB_BLOCK, 20, 6)?, 2 * 2 * 16);
+        assert_eq!(level_size(vk::Format::R8G8B8A8_SRGB, 3, 2)?, 24);
+        assert!(level_size(vk::Format::D32_SFLOAT, 1, 1).is_err());
+
+        assert!(is_compressed_file(Path::new("textures/rock.KTX2")));
+        assert!(is_compressed_file(Path::new("rock.dds")));
+        assert!(!is_compressed_file(Path::new("rock.png")));
+
+        Ok(())
+    }
+}
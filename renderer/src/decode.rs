@@ -0,0 +1,191 @@
+//================================================
+// Decode
+//================================================
+//! Decodes PNG and JPEG files of any size and color type into RGBA8 pixels,
+//! which is the only format textures are uploaded in.
+//!
+//! Palette and low bit depth PNGs are expanded by the `png` crate, and the
+//! remaining grayscale, RGB and 16-bit channels are converted here (as are
+//! grayscale and CMYK JPEGs), so none of this needs a GPU.
+
+use std::fs;
+use std::path::Path;
+
+use anyhow::{anyhow, Context, Result};
+
+/// The RGBA8 pixels of an image, row by row.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Pixels {
+    pub width:  u32,
+    pub height: u32,
+    pub data:   Vec<u8>,
+}
+
+/// Reads and decodes a PNG or JPEG file (recognized by its contents rather
+/// than its extension).
+pub fn load_image(path: &Path) -> Result<Pixels> {
+    let bytes = fs::read(path).with_context(|| format!("Failed to read image `{}`.", path.display()))?;
+    decode_image(&bytes).with_context(|| format!("Invalid image `{}`.", path.display()))
+}
+
+/// Decodes a PNG or JPEG image.
+pub fn decode_image(bytes: &[u8]) -> Result<Pixels> {
+    if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        decode_png(bytes)
+    } else if bytes.starts_with(&[0xff, 0xd8, 0xff]) {
+        decode_jpeg(bytes)
+    } else {
+        Err(anyhow!("Unsupported image format (expected PNG or JPEG)."))
+    }
+}
+
+pub fn decode_png(bytes: &[u8]) -> Result<Pixels> {
+    let mut decoder = png::Decoder::new(bytes);
+    // Palettes become RGB(A) and 1, 2 and 4-bit grayscale becomes 8-bit
+    decoder.set_transformations(png::Transformations::EXPAND);
+    let mut reader = decoder.read_info()?;
+
+    let mut buffer = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut buffer)?;
+    buffer.truncate(info.buffer_size());
+
+    let channels = info.color_type.samples();
+    let samples = match info.bit_depth {
+        png::BitDepth::Eight => buffer,
+        // 16-bit samples are big endian, their most significant byte is kept
+        png::BitDepth::Sixteen => buffer.chunks_exact(2).map(|s| s[0]).collect(),
+        depth => return Err(anyhow!("Unexpected {:?} bit PNG after expansion.", depth)),
+    };
+
+    Ok(Pixels { width: info.width, height: info.height, data: expand_to_rgba(&samples, channels)? })
+}
+
+pub fn decode_jpeg(bytes: &[u8]) -> Result<Pixels> {
+    let mut decoder = jpeg_decoder::Decoder::new(bytes);
+    let samples = decoder.decode()?;
+    let info = decoder.info().ok_or_else(|| anyhow!("Missing JPEG header."))?;
+
+    let data = match info.pixel_format {
+        jpeg_decoder::PixelFormat::L8 => expand_to_rgba(&samples, 1)?,
+        jpeg_decoder::PixelFormat::L16 => {
+            let samples = samples.chunks_exact(2).map(|s| s[0]).collect::<Vec<_>>();
+            expand_to_rgba(&samples, 1)?
+        }
+        jpeg_decoder::PixelFormat::RGB24 => expand_to_rgba(&samples, 3)?,
+        // Adobe CMYK JPEGs store inverted values
+        jpeg_decoder::PixelFormat::CMYK32 => samples
+            .chunks_exact(4)
+            .flat_map(|cmyk| {
+                let k = cmyk[3] as u32;
+                let channel = |c: u8| (c as u32 * k / 255) as u8;
+                [channel(cmyk[0]), channel(cmyk[1]), channel(cmyk[2]), 255]
+            })
+            .collect(),
+    };
+
+    Ok(Pixels { width: info.width as u32, height: info.height as u32, data })
+}
+
+/// Converts 8-bit samples with 1 (gray), 2 (gray and alpha), 3 (RGB) or 4
+/// (RGBA) channels per pixel to RGBA.
+pub fn expand_to_rgba(samples: &[u8], channels: usize) -> Result<Vec<u8>> {
+    if !(1..=4).contains(&channels) || !samples.len().is_multiple_of(channels) {
+        return Err(anyhow!("Invalid image data ({} bytes of {} channel pixels).", samples.len(), channels));
+    }
+
+    let mut rgba = Vec::with_capacity(samples.len() / channels * 4);
+    for pixel in samples.chunks_exact(channels) {
+        rgba.extend_from_slice(&match *pixel {
+            [l] => [l, l, l, 255],
+            [l, a] => [l, l, l, a],
+            [r, g, b] => [r, g, b, 255],
+            [r, g, b, a] => [r, g, b, a],
+            _ => unreachable!(),
+        });
+    }
+
+    Ok(rgba)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn png(width: u32, height: u32, color: png::ColorType, depth: png::BitDepth, palette: Option<&[u8]>, data: &[u8]) -> Vec<u8> {
+        let mut bytes = vec![];
+        let mut encoder = png::Encoder::new(&mut bytes, width, height);
+        encoder.set_color(color);
+        encoder.set_depth(depth);
+        if let Some(palette) = palette {
+            encoder.set_palette(palette.to_vec());
+        }
+
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(data).unwrap();
+        drop(writer);
+        bytes
+    }
+
+    #[test]
+    fn pngs_of_every_color_type_become_rgba() -> Result<()> {
+        use png::{BitDepth, ColorType};
+
+        let gray = png(2, 1, ColorType::Grayscale, BitDepth::Eight, None, &[0, 200]);
+        assert_eq!(decode_image(&gray)?.data, [0, 0, 0, 255, 200, 200, 200, 255]);
+
+        let gray_alpha = png(1, 1, ColorType::GrayscaleAlpha, BitDepth::Eight, None, &[50, 128]);
+        assert_eq!(decode_image(&gray_alpha)?.data, [50, 50, 50, 128]);
+
+        let rgb = png(1, 1, ColorType::Rgb, BitDepth::Eight, None, &[1, 2, 3]);
+        assert_eq!(decode_image(&rgb)?.data, [1, 2, 3, 255]);
+
+        let rgba16 = png(1, 1, ColorType::Rgba, BitDepth::Sixteen, None, &[0x12, 0x34, 0xff, 0x00, 0x00, 0xff, 0x80, 0x00]);
+        assert_eq!(decode_image(&rgba16)?.data, [0x12, 0xff, 0x00, 0x80]);
+
+        // Four 2-bit palette indices in one byte
+        let palette = png(4, 1, ColorType::Indexed, BitDepth::Two, Some(&[255, 0, 0, 0, 255, 0, 0, 0, 255, 9, 9, 9]), &[0b00_01_10_11]);
+        let pixels = decode_image(&palette)?;
+        assert_eq!((pixels.width, pixels.height), (4, 1));
+        assert_eq!(pixels.data, [255, 0, 0, 255, 0, 255, 0, 255, 0, 0, 255, 255, 9, 9, 9, 255]);
+
+        // Any size
+        let odd = png(3, 5, ColorType::Grayscale, BitDepth::One, None, &[0b1010_0000; 5]);
+        let pixels = decode_image(&odd)?;
+        assert_eq!((pixels.width, pixels.height, pixels.data.len()), (3, 5, 3 * 5 * 4));
+        assert_eq!(pixels.data[..8], [255, 255, 255, 255, 0, 0, 0, 255]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn jpegs_are_decoded() -> Result<()> {
+        let mut bytes = vec![];
+        let rgb = [200u8, 100, 50].repeat(8 * 8);
+        jpeg_encoder::Encoder::new(&mut bytes, 100).encode(&rgb, 8, 8, jpeg_encoder::ColorType::Rgb)?;
+
+        let pixels = decode_image(&bytes)?;
+        assert_eq!((pixels.width, pixels.height), (8, 8));
+        // Lossy, but close
+        for pixel in pixels.data.chunks_exact(4) {
+            for (actual, expected) in pixel.iter().zip([200, 100, 50, 255]) {
+                assert!((*actual as i32 - expected).abs() <= 3, "{:?}", pixel);
+            }
+        }
+
+        let mut bytes = vec![];
+        jpeg_encoder::Encoder::new(&mut bytes, 100).encode(&[128; 16], 4, 4, jpeg_encoder::ColorType::Luma)?;
+        let pixels = decode_image(&bytes)?;
+        assert!(pixels.data.chunks_exact(4).all(|p| p[0] == p[1] && p[1] == p[2] && p[3] == 255));
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_images_are_errors() {
+        assert!(decode_image(b"GIF89a").is_err());
+        assert!(decode_image(b"\x89PNG\r\n\x1a\ntruncated").is_err());
+        assert!(decode_image(&[0xff, 0xd8, 0xff, 0x00]).is_err());
+        assert!(expand_to_rgba(&[1, 2, 3], 2).is_err());
+        assert!(load_image(Path::new("missing.png")).unwrap_err().to_string().contains("missing.png"));
+    }
+}
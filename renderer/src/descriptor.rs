@@ -0,0 +1,152 @@
+//================================================
+// Descriptors
+//================================================
+use crate::app_data::AppData;
+use crate::pipeline::{self, PipelineInfo};
+use crate::reflect;
+use crate::owned::Owned;
+
+use std::mem::size_of;
+use std::rc::Rc;
+
+use anyhow::{anyhow, Result};
+use vulkanalia::prelude::v1_0::*;
+
+/// Creates the descriptor set layout from the bindings the shaders of
+/// `pipeline` declare (see [`crate::reflect`]).
+pub unsafe fn create_descriptor_set_layout(device: &Rc<Device>, data: &mut AppData, pipeline: &PipelineInfo) -> Result<()> {
+    // binding info
+    let shaders = pipeline::load_shaders(data, pipeline)?;
+    let bindings = reflect::descriptor_set_layout_bindings(&shaders.modules)?;
+    let info = vk::DescriptorSetLayoutCreateInfo::builder().bindings(&bindings);
+
+    // create
+    data.descriptor_set_layout = Owned::new(device, device.create_descriptor_set_layout(&info, None)?);
+    data.descriptor_bindings = bindings;
+
+    Ok(())
+}
+
+/// Creates a descriptor pool with room for `count` descriptor sets.
+pub unsafe fn create_descriptor_pool(device: &Rc<Device>, data: &mut AppData, count: usize) -> Result<()> {
+    let pool_sizes = data
+        .descriptor_bindings
+        .iter()
+        .map(|b| {
+            vk::DescriptorPoolSize::builder()
+                .type_(b.descriptor_type)
+                .descriptor_count(b.descriptor_count * count as u32)
+                .build()
+        })
+        .collect::<Vec<_>>();
+
+    let info = vk::DescriptorPoolCreateInfo::builder()
+        .pool_sizes(&pool_sizes)
+        .max_sets(count as u32);
+
+    data.descriptor_pool = Owned::new(device, device.create_descriptor_pool(&info, None)?);
+
+    Ok(())
+}
+
+/// Creates a descriptor set per uniform buffer, binding that buffer (holding a
+/// `U`), the texture and the texture sampler.
+pub unsafe fn create_descriptor_sets<U>(device: &Device, data: &mut AppData) -> Result<()> {
+    // 1. Allocate
+    let layouts = vec![*data.descriptor_set_layout; data.uniform_buffers.len()];
+    let info = vk::DescriptorSetAllocateInfo::builder()
+        .descriptor_pool(*data.descriptor_pool)
+        .set_layouts(&layouts);
+
+    data.descriptor_sets = device.allocate_descriptor_sets(&info)?;
+
+    // 2. Update
+    // Create a corresponding descriptor set for each uniform buffer using multiple identical descriptor layouts
+    for i in 0..data.uniform_buffers.len() {
+        write_descriptor_set::<U>(
+            device,
+            &data.descriptor_bindings,
+            data.descriptor_sets[i],
+            *data.uniform_buffers[i],
+            *data.texture_image_view,
+            *data.texture_sampler,
+        )?;
+    }
+
+    Ok(())
+}
+
+/// Creates a descriptor set for each material of each uniform buffer (frame in
+/// flight), binding that buffer (holding a `U`) and the material's texture.
+pub unsafe fn create_material_descriptor_sets<U>(device: &Device, data: &mut AppData) -> Result<()> {
+    let layouts = vec![*data.descriptor_set_layout; data.material_textures.len()];
+    let info = vk::DescriptorSetAllocateInfo::builder()
+        .descriptor_pool(*data.descriptor_pool)
+        .set_layouts(&layouts);
+
+    let mut sets = Vec::with_capacity(data.uniform_buffers.len());
+    for uniform_buffer in &data.uniform_buffers {
+        let material_sets = device.allocate_descriptor_sets(&info)?;
+        for (set, texture) in material_sets.iter().zip(&data.material_textures) {
+            let (view, sampler) = (*texture.view, *data.texture_sampler);
+            write_descriptor_set::<U>(device, &data.descriptor_bindings, *set, **uniform_buffer, view, sampler)?;
+        }
+        sets.push(material_sets);
+    }
+
+    data.material_descriptor_sets = sets;
+
+    Ok(())
+}
+
+/// Binds a uniform buffer (holding a `U`) and a sampled image to the bindings
+/// of a descriptor set the shaders declare (see [`create_descriptor_set_layout`]).
+unsafe fn write_descriptor_set<U>(
+    device: &Device,
+    bindings: &[vk::DescriptorSetLayoutBinding],
+    set: vk::DescriptorSet,
+    uniform_buffer: vk::Buffer,
+    image_view: vk::ImageView,
+    sampler: vk::Sampler,
+) -> Result<()> {
+    let info = vk::DescriptorBufferInfo::builder()
+        .buffer(uniform_buffer)
+        .offset(0)
+        .range(size_of::<U>() as u64);
+
+    let buffer_info = &[info];
+
+    // Sampled images ignore the sampler and samplers ignore the image
+    let info = vk::DescriptorImageInfo::builder()
+        .image_layout(vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL)
+        .image_view(image_view)
+        .sampler(sampler);
+
+    let image_info = &[info];
+
+    let writes = bindings
+        .iter()
+        .map(|b| {
+            let write = vk::WriteDescriptorSet::builder()
+                .dst_set(set)
+                .dst_binding(b.binding)
+                .dst_array_element(0)
+                .descriptor_type(b.descriptor_type);
+
+            match b.descriptor_type {
+                _ if b.descriptor_count != 1 => {
+                    Err(anyhow!("Descriptor binding {} is an array of {}.", b.binding, b.descriptor_count))
+                }
+                vk::DescriptorType::UNIFORM_BUFFER => Ok(write.buffer_info(buffer_info).build()),
+                vk::DescriptorType::COMBINED_IMAGE_SAMPLER
+                | vk::DescriptorType::SAMPLED_IMAGE
+                | vk::DescriptorType::SAMPLER => Ok(write.image_info(image_info).build()),
+                type_ => Err(anyhow!("Descriptor binding {} is an unsupported {:?}.", b.binding, type_)),
+            }
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    device.update_descriptor_sets(&writes, &[] as &[vk::CopyDescriptorSet]);
+
+    Ok(())
+}
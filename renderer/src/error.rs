@@ -0,0 +1,16 @@
+use std::path::PathBuf;
+
+use thiserror::Error;
+
+#[derive(Debug, Error)]
+#[error("{0}")]
+pub struct SuitabilityError(pub &'static str);
+
+/// An asset that is in none of the places it was looked for.
+#[derive(Debug, Error)]
+#[error("Asset `{}` was not found (tried {}).", .path.display(), .attempts.iter().map(|a| format!("`{}`", a.display())).collect::<Vec<_>>().join(", "))]
+pub struct AssetNotFound {
+    pub path: PathBuf,
+    /// The paths that were tried, in order.
+    pub attempts: Vec<PathBuf>,
+}
@@ -0,0 +1,277 @@
+//================================================
+// Frame Stats
+//================================================
+//! How long frames take: the time between frames and the time the CPU spends
+//! on each (measured by the app), and the time the GPU spends rendering each
+//! (measured with timestamp queries around its commands).
+//!
+//! The GPU time of a frame is only known once its fence was signaled, which is
+//! when the app reuses its frame in flight.
+
+use crate::app_data::AppData;
+use crate::owned::Owned;
+use crate::structs::QueueFamilyIndices;
+
+use std::fmt;
+use std::fs;
+use std::path::Path;
+use std::rc::Rc;
+
+use anyhow::{Context, Result};
+use log::*;
+use vulkanalia::prelude::v1_0::*;
+
+/// The number of recent frames the averages and percentiles are computed over.
+pub const WINDOW: usize = 120;
+
+/// The timings of a frame in milliseconds.
+#[derive(Copy, Clone, Debug, Default, PartialEq)]
+pub struct FrameTiming {
+    /// The time since the previous frame started.
+    pub frame: f32,
+    /// The time the CPU spent on the frame.
+    pub cpu: f32,
+    /// The time the GPU spent on the frame (if it was measured).
+    pub gpu: Option<f32>,
+}
+
+/// The averages and percentiles of the recent frames.
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub struct Summary {
+    pub fps: f32,
+    pub frame_average: f32,
+    pub frame_p50: f32,
+    pub frame_p99: f32,
+    pub cpu_average: f32,
+    pub gpu_average: Option<f32>,
+}
+
+impl fmt::Display for Summary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:.0} fps | frame {:.2} ms (p50 {:.2}, p99 {:.2}) | cpu {:.2} ms",
+            self.fps, self.frame_average, self.frame_p50, self.frame_p99, self.cpu_average,
+        )?;
+
+        match self.gpu_average {
+            Some(gpu) => write!(f, " | gpu {:.2} ms", gpu),
+            None => Ok(()),
+        }
+    }
+}
+
+/// The timings of every frame rendered so far.
+#[derive(Clone, Debug, Default)]
+pub struct FrameStats {
+    timings: Vec<FrameTiming>,
+}
+
+impl FrameStats {
+    /// The number of frames recorded so far (which is also the number of the
+    /// next frame).
+    pub fn len(&self) -> usize {
+        self.timings.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.timings.is_empty()
+    }
+
+    pub fn timings(&self) -> &[FrameTiming] {
+        &self.timings
+    }
+
+    /// Records the frame and CPU time of the next frame.
+    pub fn record(&mut self, frame: f32, cpu: f32) {
+        self.timings.push(FrameTiming { frame, cpu, gpu: None });
+    }
+
+    /// Records the GPU time of an earlier frame (by its number).
+    pub fn record_gpu(&mut self, number: usize, gpu: f32) {
+        if let Some(timing) = self.timings.get_mut(number) {
+            timing.gpu = Some(gpu);
+        }
+    }
+
+    /// Summarizes the last [`WINDOW`] frames (`None` before the first frame).
+    pub fn summary(&self) -> Option<Summary> {
+        let recent = &self.timings[self.timings.len().saturating_sub(WINDOW)..];
+        if recent.is_empty() {
+            return None;
+        }
+
+        let mut frames = recent.iter().map(|t| t.frame).collect::<Vec<_>>();
+        let frame_average = average(&frames);
+        let gpus = recent.iter().filter_map(|t| t.gpu).collect::<Vec<_>>();
+
+        Some(Summary {
+            fps: if frame_average > 0.0 { 1000.0 / frame_average } else { 0.0 },
+            frame_average,
+            frame_p50: percentile(&mut frames, 50.0),
+            frame_p99: percentile(&mut frames, 99.0),
+            cpu_average: average(&recent.iter().map(|t| t.cpu).collect::<Vec<_>>()),
+            gpu_average: (!gpus.is_empty()).then(|| average(&gpus)),
+        })
+    }
+
+    /// Writes the timings of every frame as CSV (with an empty GPU time for
+    /// frames that were not measured).
+    pub fn write_csv(&self, path: &Path) -> Result<()> {
+        let mut csv = String::from("frame,frame_ms,cpu_ms,gpu_ms\n");
+        for (number, timing) in self.timings.iter().enumerate() {
+            let gpu = timing.gpu.map(|g| g.to_string()).unwrap_or_default();
+            csv += &format!("{},{},{},{}\n", number, timing.frame, timing.cpu, gpu);
+        }
+
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+
+        fs::write(path, csv).with_context(|| format!("Failed to write `{}`.", path.display()))
+    }
+}
+
+fn average(values: &[f32]) -> f32 {
+    values.iter().sum::<f32>() / values.len() as f32
+}
+
+/// The nearest-rank percentile (0 to 100) of some values (sorting them).
+pub fn percentile(values: &mut [f32], percentile: f32) -> f32 {
+    if values.is_empty() {
+        return 0.0;
+    }
+
+    values.sort_by(f32::total_cmp);
+    let rank = (percentile / 100.0 * values.len() as f32).ceil() as usize;
+    values[rank.clamp(1, values.len()) - 1]
+}
+
+//================================================
+// Timestamp Queries
+//================================================
+
+/// Creates a pool with two timestamp queries (the start and end of the
+/// commands) per frame in flight, unless the graphics queue does not support
+/// timestamps (then no GPU times are measured).
+pub unsafe fn create_timestamp_queries(instance: &Instance, device: &Rc<Device>, data: &mut AppData, count: usize) -> Result<()> {
+    let properties = instance.get_physical_device_properties(data.physical_device);
+    let indices = QueueFamilyIndices::get(instance, data, data.physical_device)?;
+    let families = instance.get_physical_device_queue_family_properties(data.physical_device);
+
+    let bits = families[indices.graphics as usize].timestamp_valid_bits;
+    if bits == 0 || properties.limits.timestamp_period == 0.0 {
+        warn!("The graphics queue does not support timestamps, GPU times are not measured.");
+        data.timestamp_query_pool = Owned::default();
+        return Ok(());
+    }
+
+    let info = vk::QueryPoolCreateInfo::builder()
+        .query_type(vk::QueryType::TIMESTAMP)
+        .query_count(2 * count as u32);
+
+    data.timestamp_query_pool = Owned::new(device, device.create_query_pool(&info, None)?);
+    data.timestamp_period = properties.limits.timestamp_period;
+    data.timestamp_mask = if bits >= 64 { u64::MAX } else { (1 << bits) - 1 };
+
+    Ok(())
+}
+
+/// Resets the timestamp queries of a frame in flight and writes the first one
+/// (outside of a render pass, before the commands that are measured).
+pub unsafe fn cmd_begin_timestamps(device: &Device, command_buffer: vk::CommandBuffer, data: &AppData, frame: usize) {
+    if data.timestamp_query_pool.is_null() {
+        return;
+    }
+
+    let pool = *data.timestamp_query_pool;
+    device.cmd_reset_query_pool(command_buffer, pool, 2 * frame as u32, 2);
+    device.cmd_write_timestamp(command_buffer, vk::PipelineStageFlags::TOP_OF_PIPE, pool, 2 * frame as u32);
+}
+
+/// Writes the second timestamp query of a frame in flight, once the measured
+/// commands completed.
+pub unsafe fn cmd_end_timestamps(device: &Device, command_buffer: vk::CommandBuffer, data: &AppData, frame: usize) {
+    if data.timestamp_query_pool.is_null() {
+        return;
+    }
+
+    let pool = *data.timestamp_query_pool;
+    device.cmd_write_timestamp(command_buffer, vk::PipelineStageFlags::BOTTOM_OF_PIPE, pool, 2 * frame as u32 + 1);
+}
+
+/// The GPU time in milliseconds between the timestamps of a frame in flight
+/// that finished (`None` if timestamps are not supported or not written yet).
+pub unsafe fn get_gpu_time(device: &Device, data: &AppData, frame: usize) -> Result<Option<f32>> {
+    if data.timestamp_query_pool.is_null() {
+        return Ok(None);
+    }
+
+    let mut timestamps = [0u64; 2];
+    let result = device.get_query_pool_results(
+        *data.timestamp_query_pool,
+        2 * frame as u32,
+        2,
+        std::slice::from_raw_parts_mut(timestamps.as_mut_ptr().cast(), 16),
+        8,
+        vk::QueryResultFlags::_64,
+    )?;
+
+    if result != vk::SuccessCode::SUCCESS {
+        return Ok(None);
+    }
+
+    let ticks = timestamps[1].wrapping_sub(timestamps[0]) & data.timestamp_mask;
+    Ok(Some((ticks as f64 * data.timestamp_period as f64 / 1_000_000.0) as f32))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percentiles_use_the_nearest_rank() {
+        let mut values = (1..=100).rev().map(|v| v as f32).collect::<Vec<_>>();
+        assert_eq!(percentile(&mut values, 50.0), 50.0);
+        assert_eq!(percentile(&mut values, 99.0), 99.0);
+        assert_eq!(percentile(&mut values, 100.0), 100.0);
+        assert_eq!(percentile(&mut values, 0.0), 1.0);
+        assert_eq!(percentile(&mut [], 50.0), 0.0);
+    }
+
+    #[test]
+    fn summaries_cover_the_recent_frames() {
+        let mut stats = FrameStats::default();
+        assert_eq!(stats.summary(), None);
+
+        // A slow first frame that leaves the window
+        stats.record(100.0, 50.0);
+        for _ in 0..WINDOW {
+            stats.record(20.0, 4.0);
+        }
+        stats.record_gpu(1, 10.0);
+        stats.record_gpu(2, 12.0);
+
+        let summary = stats.summary().unwrap();
+        assert_eq!(summary.fps, 50.0);
+        assert_eq!((summary.frame_average, summary.frame_p99, summary.cpu_average), (20.0, 20.0, 4.0));
+        assert_eq!(summary.gpu_average, Some(11.0));
+        assert_eq!(summary.to_string(), "50 fps | frame 20.00 ms (p50 20.00, p99 20.00) | cpu 4.00 ms | gpu 11.00 ms");
+    }
+
+    #[test]
+    fn csv_has_a_row_per_frame() -> Result<()> {
+        let mut stats = FrameStats::default();
+        stats.record(16.5, 2.0);
+        stats.record(17.0, 2.5);
+        stats.record_gpu(0, 1.25);
+
+        let path = std::env::temp_dir().join(format!("frame_stats_{}.csv", std::process::id()));
+        stats.write_csv(&path)?;
+        let csv = fs::read_to_string(&path)?;
+        fs::remove_file(&path)?;
+
+        assert_eq!(csv, "frame,frame_ms,cpu_ms,gpu_ms\n0,16.5,2,1.25\n1,17,2.5,\n");
+        Ok(())
+    }
+}
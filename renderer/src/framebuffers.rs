@@ -0,0 +1,55 @@
+//================================================
+// Framebuffers
+//================================================
+use crate::app_data::AppData;
+
+use crate::owned::Owned;
+
+use std::rc::Rc;
+
+use anyhow::Result;
+use vulkanalia::prelude::v1_0::*;
+
+pub unsafe fn create_framebuffers(device: &Rc<Device>, data: &mut AppData) -> Result<()> {
+    data.framebuffers = build_framebuffers(
+        device,
+        data,
+        *data.render_pass,
+        *data.color_image_view,
+        *data.depth_image_view,
+    )?;
+
+    Ok(())
+}
+
+/// Builds a framebuffer for each swapchain image view and `render_pass`
+/// (`color_image_view` is null without multisampling).
+pub unsafe fn build_framebuffers(
+    device: &Rc<Device>,
+    data: &AppData,
+    render_pass: vk::RenderPass,
+    color_image_view: vk::ImageView,
+    depth_image_view: vk::ImageView,
+) -> Result<Vec<Owned<vk::Framebuffer>>> {
+    data
+        .swapchain_image_views
+        .iter()
+        .map(|i| {
+            // Multisampled images are resolved into the swapchain image
+            let attachments = if color_image_view.is_null() {
+                vec![**i, depth_image_view]
+            } else {
+                vec![color_image_view, depth_image_view, **i]
+            };
+
+            let create_info = vk::FramebufferCreateInfo::builder()
+                .render_pass(render_pass)
+                .attachments(&attachments)
+                .width(data.swapchain_extent.width)
+                .height(data.swapchain_extent.height)
+                .layers(1);
+
+            Ok(Owned::new(device, device.create_framebuffer(&create_info, None)?))
+        })
+        .collect()
+}
\ No newline at end of file
@@ -0,0 +1,551 @@
+//================================================
+// Images
+//================================================
+#![allow(unused_variables)]
+
+use crate::app_data::AppData;
+use crate::buffers;
+use crate::commands::{self, Transfer};
+use crate::memory::Allocator;
+use crate::owned::{Image, Owned};
+
+use std::ptr::copy_nonoverlapping as memcpy;
+use std::rc::Rc;
+
+use anyhow::{anyhow, Result};
+use vulkanalia::prelude::v1_0::*;
+
+/// Describes a 2D image (see [`create_image_array`]).
+#[derive(Copy, Clone, Debug)]
+pub struct ImageInfo {
+    pub extent:     vk::Extent2D,
+    pub mip_levels: u32,
+    /// The number of array layers (e.g. the six faces of a cube map, which
+    /// also needs `CUBE_COMPATIBLE` in `flags`).
+    pub layers:     u32,
+    pub flags:      vk::ImageCreateFlags,
+    pub samples:    vk::SampleCountFlags,
+    pub format:     vk::Format,
+    pub tiling:     vk::ImageTiling,
+    pub usage:      vk::ImageUsageFlags,
+}
+
+#[track_caller]
+pub unsafe fn create_image(
+    instance: &Instance,
+    device: &Rc<Device>,
+    data: &AppData,
+    width: u32,
+    height: u32,
+    mip_levels: u32,
+    samples: vk::SampleCountFlags,
+    format: vk::Format,
+    tiling: vk::ImageTiling,
+    usage: vk::ImageUsageFlags,
+    properties: vk::MemoryPropertyFlags,
+) -> Result<Image> {
+    let info = ImageInfo {
+        extent: vk::Extent2D { width, height },
+        mip_levels,
+        layers: 1,
+        flags: vk::ImageCreateFlags::empty(),
+        samples,
+        format,
+        tiling,
+        usage,
+    };
+
+    create_image_array(instance, device, data, &info, properties)
+}
+
+/// Creates an image with several array layers (or any other [`ImageInfo`]).
+#[track_caller]
+pub unsafe fn create_image_array(
+    instance: &Instance,
+    device: &Rc<Device>,
+    data: &AppData,
+    image: &ImageInfo,
+    properties: vk::MemoryPropertyFlags,
+) -> Result<Image> {
+    allocate_image(device, &data.allocator, image, properties)
+}
+
+/// Creates an image in memory from `allocator`.
+#[track_caller]
+pub unsafe fn allocate_image(
+    device: &Rc<Device>,
+    allocator: &Rc<Allocator>,
+    image: &ImageInfo,
+    properties: vk::MemoryPropertyFlags,
+) -> Result<Image> {
+    // Image
+    let info = vk::ImageCreateInfo::builder()
+        .flags(image.flags)
+        .image_type(vk::ImageType::_2D)
+        .extent(vk::Extent3D {
+            width: image.extent.width,
+            height: image.extent.height,
+            depth: 1,
+        })
+        .mip_levels(image.mip_levels)
+        .array_layers(image.layers)
+        .format(image.format)
+        .tiling(image.tiling)
+        .initial_layout(vk::ImageLayout::UNDEFINED)
+        .usage(image.usage)
+        .sharing_mode(vk::SharingMode::EXCLUSIVE)
+        .samples(image.samples);
+
+    let handle = Owned::new(device, device.create_image(&info, None)?);
+
+    // Memory
+    let requirements = device.get_image_memory_requirements(*handle);
+
+    let linear = image.tiling == vk::ImageTiling::LINEAR;
+    let image_memory = allocator.allocate(device, requirements, properties, linear)?;
+    let image = Image::new(handle, allocator, image_memory);
+
+    device.bind_image_memory(*image, image_memory.memory, image_memory.offset)?;
+
+    Ok(image)
+}
+
+#[track_caller]
+pub unsafe fn create_image_view(
+    device:  &Rc<Device>,
+    image:   vk::Image,
+    format:  vk::Format,
+    aspects: vk::ImageAspectFlags,
+    mip_levels: u32,
+) -> Result<Owned<vk::ImageView>> {
+    let subresource_range = vk::ImageSubresourceRange::builder()
+        .aspect_mask(aspects)
+        .base_mip_level(0)
+        .level_count(mip_levels)
+        .base_array_layer(0)
+        .layer_count(1);
+
+    let info = vk::ImageViewCreateInfo::builder()
+        .image(image)
+        .view_type(vk::ImageViewType::_2D)
+        .format(format)
+        .subresource_range(subresource_range);
+
+    Ok(Owned::new(device, device.create_image_view(&info, None)?))
+}
+
+pub unsafe fn transition_image_layout(
+    device: &Device,
+    data: &AppData,
+    image: vk::Image,
+    format: vk::Format,
+    old_layout: vk::ImageLayout,
+    new_layout: vk::ImageLayout,
+    mip_levels: u32,
+) -> Result<()> {
+    transition_image_layers(device, data, image, old_layout, new_layout, mip_levels, 1)
+}
+
+/// Transitions every mip level of the first `layers` array layers of an image.
+pub unsafe fn transition_image_layers(
+    device: &Device,
+    data: &AppData,
+    image: vk::Image,
+    old_layout: vk::ImageLayout,
+    new_layout: vk::ImageLayout,
+    mip_levels: u32,
+    layers: u32,
+) -> Result<()> {
+    let (src_access_mask, dst_access_mask, src_stage_mask, dst_stage_mask) = match (old_layout, new_layout) {
+        (vk::ImageLayout::UNDEFINED, vk::ImageLayout::TRANSFER_DST_OPTIMAL) => (
+            vk::AccessFlags::empty(),
+            vk::AccessFlags::TRANSFER_WRITE,
+            vk::PipelineStageFlags::TOP_OF_PIPE,
+            vk::PipelineStageFlags::TRANSFER,
+        ),
+        (vk::ImageLayout::TRANSFER_DST_OPTIMAL, vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL) => (
+            vk::AccessFlags::TRANSFER_WRITE,
+            vk::AccessFlags::SHADER_READ,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::PipelineStageFlags::FRAGMENT_SHADER,
+        ),
+        _ => return Err(anyhow!("Unsupported image layout transition!")),
+    };
+
+    let command_buffer = commands::begin_single_time_commands(device, data)?;
+
+    let subresource = vk::ImageSubresourceRange::builder()
+        .aspect_mask(vk::ImageAspectFlags::COLOR)
+        .base_mip_level(0)
+        .level_count(mip_levels)
+        .base_array_layer(0)
+        .layer_count(layers);
+
+    let barrier = vk::ImageMemoryBarrier::builder()
+        .old_layout(old_layout)
+        .new_layout(new_layout)
+        .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .image(image)
+        .subresource_range(subresource)
+        .src_access_mask(src_access_mask)
+        .dst_access_mask(dst_access_mask);
+
+    device.cmd_pipeline_barrier(
+        command_buffer,
+        src_stage_mask,
+        dst_stage_mask,
+        vk::DependencyFlags::empty(),
+        &[] as &[vk::MemoryBarrier],
+        &[] as &[vk::BufferMemoryBarrier],
+        &[barrier],
+    );
+
+    commands::end_single_time_commands(device, data, command_buffer)?;
+
+    Ok(())
+}
+
+pub unsafe fn copy_buffer_to_image(
+    device: &Device,
+    data: &AppData,
+    buffer: vk::Buffer,
+    image: vk::Image,
+    width: u32,
+    height: u32,
+) -> Result<()> {
+    let subresource = vk::ImageSubresourceLayers::builder()
+        .aspect_mask(vk::ImageAspectFlags::COLOR)
+        .mip_level(0)
+        .base_array_layer(0)
+        .layer_count(1);
+
+    let region = vk::BufferImageCopy::builder()
+        .buffer_offset(0)
+        .buffer_row_length(0)
+        .buffer_image_height(0)
+        .image_subresource(subresource)
+        .image_offset(vk::Offset3D { x: 0, y: 0, z: 0 })
+        .image_extent(vk::Extent3D {
+            width,
+            height,
+            depth: 1,
+        });
+
+    copy_buffer_to_image_regions(device, data, buffer, image, &[region.build()])
+}
+
+/// Copies regions of a buffer to an image (e.g. one per stored mip level).
+pub unsafe fn copy_buffer_to_image_regions(
+    device: &Device,
+    data: &AppData,
+    buffer: vk::Buffer,
+    image: vk::Image,
+    regions: &[vk::BufferImageCopy],
+) -> Result<()> {
+    let command_buffer = commands::begin_single_time_commands(device, data)?;
+
+    device.cmd_copy_buffer_to_image(
+        command_buffer,
+        buffer,
+        image,
+        vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+        regions,
+    );
+
+    commands::end_single_time_commands(device, data, command_buffer)?;
+
+    Ok(())
+}
+
+/// Reads a color image that was just rendered to back to CPU memory, leaving
+/// it in `layout` (the layout the render pass left it in) afterwards.
+pub unsafe fn read_image(
+    instance: &Instance,
+    device: &Rc<Device>,
+    data: &AppData,
+    image: vk::Image,
+    layout: vk::ImageLayout,
+    width: u32,
+    height: u32,
+) -> Result<Vec<u8>> {
+    read_image_with(device, &Transfer::from(data), image, layout, width, height)
+}
+
+/// Reads a color image back to CPU memory like [`read_image`], copying it with
+/// the command pool, queue and allocator of `transfer`.
+pub unsafe fn read_image_with(
+    device: &Rc<Device>,
+    transfer: &Transfer,
+    image: vk::Image,
+    layout: vk::ImageLayout,
+    width: u32,
+    height: u32,
+) -> Result<Vec<u8>> {
+    // 1. Create a staging buffer visible to the cpu
+    let size = (width * height * 4) as u64;
+    let staging_buffer = buffers::allocate_buffer(
+        device,
+        transfer.allocator,
+        size,
+        vk::BufferUsageFlags::TRANSFER_DST,
+        vk::MemoryPropertyFlags::HOST_COHERENT | vk::MemoryPropertyFlags::HOST_VISIBLE,
+    )?;
+
+    // 2. Copy image to staging buffer (after the render pass has written it)
+    let command_buffer = commands::begin_transfer_commands(device, transfer)?;
+    cmd_read_image(device, command_buffer, image, layout, *staging_buffer, width, height);
+    commands::end_transfer_commands(device, transfer, command_buffer)?;
+
+    // 3. Copy data from staging buffer
+    let mut pixels = vec![0u8; size as usize];
+
+    memcpy(staging_buffer.memory().mapped.cast(), pixels.as_mut_ptr(), pixels.len());
+
+    Ok(pixels)
+}
+
+/// Records copying a color image that was just rendered to into a buffer (as
+/// tightly packed texels), leaving it in `layout` (the layout the render pass
+/// left it in) afterwards.
+pub unsafe fn cmd_read_image(
+    device: &Device,
+    command_buffer: vk::CommandBuffer,
+    image: vk::Image,
+    layout: vk::ImageLayout,
+    buffer: vk::Buffer,
+    width: u32,
+    height: u32,
+) {
+    let subresource_range = vk::ImageSubresourceRange::builder()
+        .aspect_mask(vk::ImageAspectFlags::COLOR)
+        .base_mip_level(0)
+        .level_count(1)
+        .base_array_layer(0)
+        .layer_count(1);
+
+    let mut barrier = vk::ImageMemoryBarrier::builder()
+        .old_layout(layout)
+        .new_layout(vk::ImageLayout::TRANSFER_SRC_OPTIMAL)
+        .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .image(image)
+        .subresource_range(subresource_range)
+        .src_access_mask(vk::AccessFlags::COLOR_ATTACHMENT_WRITE)
+        .dst_access_mask(vk::AccessFlags::TRANSFER_READ);
+
+    device.cmd_pipeline_barrier(
+        command_buffer,
+        vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT,
+        vk::PipelineStageFlags::TRANSFER,
+        vk::DependencyFlags::empty(),
+        &[] as &[vk::MemoryBarrier],
+        &[] as &[vk::BufferMemoryBarrier],
+        &[barrier],
+    );
+
+    let subresource = vk::ImageSubresourceLayers::builder()
+        .aspect_mask(vk::ImageAspectFlags::COLOR)
+        .mip_level(0)
+        .base_array_layer(0)
+        .layer_count(1);
+
+    let region = vk::BufferImageCopy::builder()
+        .buffer_offset(0)
+        .buffer_row_length(0)
+        .buffer_image_height(0)
+        .image_subresource(subresource)
+        .image_offset(vk::Offset3D { x: 0, y: 0, z: 0 })
+        .image_extent(vk::Extent3D { width, height, depth: 1 });
+
+    device.cmd_copy_image_to_buffer(
+        command_buffer,
+        image,
+        vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+        buffer,
+        &[region],
+    );
+
+    // Hand the image back in its original layout
+    barrier.old_layout = vk::ImageLayout::TRANSFER_SRC_OPTIMAL;
+    barrier.new_layout = layout;
+    barrier.src_access_mask = vk::AccessFlags::TRANSFER_READ;
+    barrier.dst_access_mask = vk::AccessFlags::empty();
+
+    device.cmd_pipeline_barrier(
+        command_buffer,
+        vk::PipelineStageFlags::TRANSFER,
+        vk::PipelineStageFlags::BOTTOM_OF_PIPE,
+        vk::DependencyFlags::empty(),
+        &[] as &[vk::MemoryBarrier],
+        &[] as &[vk::BufferMemoryBarrier],
+        &[barrier],
+    );
+}
+
+/// Whether [`generate_mipmaps`] can blit the levels of images of a format
+/// (otherwise see [`crate::mipmaps`]).
+pub unsafe fn supports_linear_blit(instance: &Instance, data: &AppData, format: vk::Format) -> bool {
+    instance
+        .get_physical_device_format_properties(data.physical_device, format)
+        .optimal_tiling_features
+        .contains(vk::FormatFeatureFlags::SAMPLED_IMAGE_FILTER_LINEAR)
+}
+
+pub unsafe fn generate_mipmaps(
+    instance: &Instance,
+    device: &Device,
+    data: &AppData,
+    image: vk::Image,
+    format: vk::Format,
+    width: u32,
+    height: u32,
+    mip_levels: u32,
+) -> Result<()> {
+    // Support
+    if !supports_linear_blit(instance, data, format) {
+        return Err(anyhow!("Texture image format does not support linear blitting!"));
+    }
+
+    // Mipmaps
+    let command_buffer = commands::begin_single_time_commands(device, data)?;
+
+    let subresource = vk::ImageSubresourceRange::builder()
+        .aspect_mask(vk::ImageAspectFlags::COLOR)
+        .base_array_layer(0)
+        .layer_count(1)
+        .level_count(1);
+
+    let mut barrier = vk::ImageMemoryBarrier::builder()
+        .image(image)
+        .src_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .dst_queue_family_index(vk::QUEUE_FAMILY_IGNORED)
+        .subresource_range(subresource);
+
+    let mut mip_width = width;
+    let mut mip_height = height;
+
+    for i in 1..mip_levels {
+        barrier.subresource_range.base_mip_level = i - 1;
+        barrier.old_layout = vk::ImageLayout::TRANSFER_DST_OPTIMAL;
+        barrier.new_layout = vk::ImageLayout::TRANSFER_SRC_OPTIMAL;
+        barrier.src_access_mask = vk::AccessFlags::TRANSFER_WRITE;
+        barrier.dst_access_mask = vk::AccessFlags::TRANSFER_READ;
+
+        device.cmd_pipeline_barrier(
+            command_buffer,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::DependencyFlags::empty(),
+            &[] as &[vk::MemoryBarrier],
+            &[] as &[vk::BufferMemoryBarrier],
+            &[barrier],
+        );
+
+        let src_subresource = vk::ImageSubresourceLayers::builder()
+            .aspect_mask(vk::ImageAspectFlags::COLOR)
+            .mip_level(i - 1)
+            .base_array_layer(0)
+            .layer_count(1);
+
+        let dst_subresource = vk::ImageSubresourceLayers::builder()
+            .aspect_mask(vk::ImageAspectFlags::COLOR)
+            .mip_level(i)
+            .base_array_layer(0)
+            .layer_count(1);
+
+        let blit = vk::ImageBlit::builder()
+            .src_offsets([
+                vk::Offset3D { x: 0, y: 0, z: 0 },
+                vk::Offset3D {
+                    x: mip_width as i32,
+                    y: mip_height as i32,
+                    z: 1,
+                },
+            ])
+            .src_subresource(src_subresource)
+            .dst_offsets([
+                vk::Offset3D { x: 0, y: 0, z: 0 },
+                vk::Offset3D {
+                    x: (if mip_width > 1 { mip_width / 2 } else { 1 }) as i32,
+                    y: (if mip_height > 1 { mip_height / 2 } else { 1 }) as i32,
+                    z: 1,
+                },
+            ])
+            .dst_subresource(dst_subresource);
+
+        device.cmd_blit_image(
+            command_buffer,
+            image,
+            vk::ImageLayout::TRANSFER_SRC_OPTIMAL,
+            image,
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            &[blit],
+            vk::Filter::LINEAR,
+        );
+
+        barrier.old_layout = vk::ImageLayout::TRANSFER_SRC_OPTIMAL;
+        barrier.new_layout = vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL;
+        barrier.src_access_mask = vk::AccessFlags::TRANSFER_READ;
+        barrier.dst_access_mask = vk::AccessFlags::SHADER_READ;
+
+        device.cmd_pipeline_barrier(
+            command_buffer,
+            vk::PipelineStageFlags::TRANSFER,
+            vk::PipelineStageFlags::FRAGMENT_SHADER,
+            vk::DependencyFlags::empty(),
+            &[] as &[vk::MemoryBarrier],
+            &[] as &[vk::BufferMemoryBarrier],
+            &[barrier],
+        );
+
+        if mip_width > 1 {
+            mip_width /= 2;
+        }
+
+        if mip_height > 1 {
+            mip_height /= 2;
+        }
+    }
+
+    barrier.subresource_range.base_mip_level = mip_levels - 1;
+    barrier.old_layout = vk::ImageLayout::TRANSFER_DST_OPTIMAL;
+    barrier.new_layout = vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL;
+    barrier.src_access_mask = vk::AccessFlags::TRANSFER_WRITE;
+    barrier.dst_access_mask = vk::AccessFlags::SHADER_READ;
+
+    device.cmd_pipeline_barrier(
+        command_buffer,
+        vk::PipelineStageFlags::TRANSFER,
+        vk::PipelineStageFlags::FRAGMENT_SHADER,
+        vk::DependencyFlags::empty(),
+        &[] as &[vk::MemoryBarrier],
+        &[] as &[vk::BufferMemoryBarrier],
+        &[barrier],
+    );
+
+    commands::end_single_time_commands(device, data, command_buffer)?;
+
+    Ok(())
+}
+
+pub unsafe fn get_supported_format(
+    instance: &Instance,
+    data: &AppData,
+    candidates: &[vk::Format],
+    tiling: vk::ImageTiling,
+    features: vk::FormatFeatureFlags,
+) -> Result<vk::Format> {
+    candidates
+        .iter()
+        .cloned()
+        .find(|f| {
+            let properties = instance.get_physical_device_format_properties(data.physical_device, *f);
+            match tiling {
+                vk::ImageTiling::LINEAR => properties.linear_tiling_features.contains(features),
+                vk::ImageTiling::OPTIMAL => properties.optimal_tiling_features.contains(features),
+                _ => false,
+            }
+        })
+        .ok_or_else(|| anyhow!("Failed to find supported format!"))
+}
\ No newline at end of file
@@ -0,0 +1,529 @@
+//================================================
+// Input
+//================================================
+//! Named actions bound to keys and mouse buttons (with modifiers), and the
+//! state of those actions for the frame being rendered.
+//!
+//! The event loop only passes the window's events to [`Input`], and the app
+//! asks it which actions were pressed, released or are held when it renders a
+//! frame. The bindings can be changed with a file of `action = binding, ...`
+//! lines (see [`Bindings::apply_config`]).
+
+use crate::assets;
+use crate::settings::config_entries;
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+
+use anyhow::{anyhow, Context, Result};
+use winit::event::{ElementState, KeyboardInput, ModifiersState, MouseButton, MouseScrollDelta, VirtualKeyCode, WindowEvent};
+use winit::window::{CursorGrabMode, Window};
+
+/// The bindings file that is read when `--bindings` is not given (if it is in
+/// one of the asset roots, see [`assets::find_default`]).
+pub const DEFAULT_BINDINGS: &str = "input.conf";
+
+/// Something the user can do, independent of the key or button it is bound to.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Action {
+    AddModel,
+    RemoveModel,
+    CycleMsaa,
+    Screenshot,
+    GrabCursor,
+    ReleaseCursor,
+    MoveForward,
+    MoveBack,
+    MoveLeft,
+    MoveRight,
+    MoveUp,
+    MoveDown,
+    ToggleCameraMode,
+}
+
+impl Action {
+    pub const ALL: &'static [Action] = &[
+        Action::AddModel,
+        Action::RemoveModel,
+        Action::CycleMsaa,
+        Action::Screenshot,
+        Action::GrabCursor,
+        Action::ReleaseCursor,
+        Action::MoveForward,
+        Action::MoveBack,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::ToggleCameraMode,
+    ];
+
+    /// The name of the action in bindings files.
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::AddModel => "add_model",
+            Action::RemoveModel => "remove_model",
+            Action::CycleMsaa => "cycle_msaa",
+            Action::Screenshot => "screenshot",
+            Action::GrabCursor => "grab_cursor",
+            Action::ReleaseCursor => "release_cursor",
+            Action::MoveForward => "move_forward",
+            Action::MoveBack => "move_back",
+            Action::MoveLeft => "move_left",
+            Action::MoveRight => "move_right",
+            Action::MoveUp => "move_up",
+            Action::MoveDown => "move_down",
+            Action::ToggleCameraMode => "toggle_camera_mode",
+        }
+    }
+}
+
+impl FromStr for Action {
+    type Err = anyhow::Error;
+
+    fn from_str(name: &str) -> Result<Self> {
+        let name = name.to_lowercase().replace('-', "_");
+        Action::ALL
+            .iter()
+            .copied()
+            .find(|a| a.name() == name)
+            .ok_or_else(|| anyhow!("Unknown action `{}`.", name))
+    }
+}
+
+//================================================
+// Bindings
+//================================================
+
+/// A physical key or mouse button.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
+pub enum Trigger {
+    Key(VirtualKeyCode),
+    Mouse(MouseButton),
+}
+
+/// A trigger and the modifiers that have to be held with it (e.g. `Ctrl+S`).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Binding {
+    pub trigger: Trigger,
+    pub modifiers: ModifiersState,
+}
+
+impl Binding {
+    pub fn key(key: VirtualKeyCode) -> Self {
+        Self { trigger: Trigger::Key(key), modifiers: ModifiersState::empty() }
+    }
+
+    pub fn mouse(button: MouseButton) -> Self {
+        Self { trigger: Trigger::Mouse(button), modifiers: ModifiersState::empty() }
+    }
+}
+
+impl FromStr for Binding {
+    type Err = anyhow::Error;
+
+    /// Parses bindings like `F12`, `Ctrl+Shift+S` or `MouseLeft`.
+    fn from_str(text: &str) -> Result<Self> {
+        let invalid = || anyhow!("Invalid binding `{}`.", text);
+
+        let mut modifiers = ModifiersState::empty();
+        let mut parts = text.split('+').map(|p| p.trim().to_lowercase()).collect::<Vec<_>>();
+        let trigger = parts.pop().filter(|t| !t.is_empty()).ok_or_else(invalid)?;
+        for part in parts {
+            modifiers |= match part.as_str() {
+                "shift" => ModifiersState::SHIFT,
+                "ctrl" | "control" => ModifiersState::CTRL,
+                "alt" => ModifiersState::ALT,
+                "logo" | "super" | "cmd" => ModifiersState::LOGO,
+                _ => return Err(invalid()),
+            };
+        }
+
+        let trigger = match trigger.as_str() {
+            "mouseleft" => Trigger::Mouse(MouseButton::Left),
+            "mouseright" => Trigger::Mouse(MouseButton::Right),
+            "mousemiddle" => Trigger::Mouse(MouseButton::Middle),
+            key => Trigger::Key(KEYS.iter().find(|(n, _)| n.to_lowercase() == key).ok_or_else(invalid)?.1),
+        };
+
+        Ok(Self { trigger, modifiers })
+    }
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (modifier, name) in [
+            (ModifiersState::CTRL, "Ctrl"),
+            (ModifiersState::ALT, "Alt"),
+            (ModifiersState::SHIFT, "Shift"),
+            (ModifiersState::LOGO, "Logo"),
+        ] {
+            if self.modifiers.contains(modifier) {
+                write!(f, "{}+", name)?;
+            }
+        }
+
+        match self.trigger {
+            Trigger::Key(key) => match KEYS.iter().find(|(_, k)| *k == key) {
+                Some((name, _)) => write!(f, "{}", name),
+                None => write!(f, "{:?}", key),
+            },
+            Trigger::Mouse(button) => write!(f, "Mouse{:?}", button),
+        }
+    }
+}
+
+/// The bindings of all actions.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Bindings(HashMap<Action, Vec<Binding>>);
+
+impl Default for Bindings {
+    fn default() -> Self {
+        use VirtualKeyCode::*;
+        let bindings = [
+            (Action::AddModel, vec![Binding::key(Right)]),
+            (Action::RemoveModel, vec![Binding::key(Left)]),
+            (Action::CycleMsaa, vec![Binding::key(M)]),
+            (Action::Screenshot, vec![Binding::key(F12)]),
+            (Action::GrabCursor, vec![Binding::mouse(MouseButton::Left)]),
+            (Action::ReleaseCursor, vec![Binding::key(Escape)]),
+            (Action::MoveForward, vec![Binding::key(W)]),
+            (Action::MoveBack, vec![Binding::key(S)]),
+            (Action::MoveLeft, vec![Binding::key(A)]),
+            (Action::MoveRight, vec![Binding::key(D)]),
+            (Action::MoveUp, vec![Binding::key(E)]),
+            (Action::MoveDown, vec![Binding::key(Q)]),
+            (Action::ToggleCameraMode, vec![Binding::key(Tab)]),
+        ];
+
+        Self(bindings.into_iter().collect())
+    }
+}
+
+impl Bindings {
+    /// Reads the default bindings, changed by the bindings file at `path` or
+    /// [`DEFAULT_BINDINGS`] (if it is in one of the asset roots).
+    pub fn load(path: Option<&Path>) -> Result<Self> {
+        let mut bindings = Self::default();
+        if let Some(path) = path.map(PathBuf::from).or_else(|| assets::find_default(DEFAULT_BINDINGS)) {
+            bindings.read(&path)?;
+        }
+
+        Ok(bindings)
+    }
+
+    /// Applies the bindings of a file.
+    pub fn read(&mut self, path: &Path) -> Result<()> {
+        let text = fs::read_to_string(path).with_context(|| format!("Failed to read `{}`.", path.display()))?;
+        self.apply_config(&text).with_context(|| format!("Invalid bindings file `{}`.", path.display()))
+    }
+
+    /// Applies `action = binding, ...` lines, each replacing the bindings of
+    /// an action (an empty list unbinds it).
+    pub fn apply_config(&mut self, text: &str) -> Result<()> {
+        for (number, action, bindings) in config_entries(text)? {
+            let action = action.parse::<Action>().with_context(|| format!("Line {}", number))?;
+            let bindings = bindings
+                .split(',')
+                .map(str::trim)
+                .filter(|b| !b.is_empty())
+                .map(Binding::from_str)
+                .collect::<Result<Vec<_>>>()
+                .with_context(|| format!("Line {}", number))?;
+            self.set(action, bindings);
+        }
+
+        Ok(())
+    }
+
+    pub fn get(&self, action: Action) -> &[Binding] {
+        self.0.get(&action).map(|b| &b[..]).unwrap_or_default()
+    }
+
+    pub fn set(&mut self, action: Action, bindings: Vec<Binding>) {
+        self.0.insert(action, bindings);
+    }
+
+    /// The actions a trigger activates while `modifiers` are held: those bound
+    /// with the most modifiers that are all held (so `Ctrl+S` does not also
+    /// activate `S`).
+    pub fn actions(&self, trigger: Trigger, modifiers: ModifiersState) -> Vec<Action> {
+        let matches = self
+            .0
+            .iter()
+            .flat_map(|(a, bs)| bs.iter().map(move |b| (*a, b)))
+            .filter(|(_, b)| b.trigger == trigger && modifiers.contains(b.modifiers))
+            .collect::<Vec<_>>();
+
+        let most = matches.iter().map(|(_, b)| b.modifiers.bits().count_ones()).max();
+        matches
+            .into_iter()
+            .filter(|(_, b)| Some(b.modifiers.bits().count_ones()) == most)
+            .map(|(a, _)| a)
+            .collect()
+    }
+}
+
+//================================================
+// State
+//================================================
+
+/// The input of a window since the previous frame: the actions that were
+/// pressed and released, those that are held, and the mouse movement.
+#[derive(Clone, Debug)]
+pub struct Input {
+    pub bindings: Bindings,
+    modifiers: ModifiersState,
+    /// The actions activated by each trigger that is down.
+    down: HashMap<Trigger, Vec<Action>>,
+    pressed: HashSet<Action>,
+    released: HashSet<Action>,
+    mouse_delta: (f32, f32),
+    scroll: f32,
+    focused: bool,
+    grabbed: bool,
+}
+
+impl Input {
+    pub fn new(bindings: Bindings) -> Self {
+        Self {
+            bindings,
+            modifiers: ModifiersState::empty(),
+            down: HashMap::new(),
+            pressed: HashSet::new(),
+            released: HashSet::new(),
+            mouse_delta: (0.0, 0.0),
+            scroll: 0.0,
+            focused: true,
+            grabbed: false,
+        }
+    }
+
+    /// Records the keyboard, mouse button and scroll wheel input of a window event.
+    pub fn handle_window_event(&mut self, event: &WindowEvent) {
+        match event {
+            WindowEvent::ModifiersChanged(modifiers) => self.modifiers = *modifiers,
+            WindowEvent::KeyboardInput {
+                input: KeyboardInput { virtual_keycode: Some(key), state, .. },
+                ..
+            } => self.trigger(Trigger::Key(*key), *state == ElementState::Pressed),
+            WindowEvent::MouseInput { button, state, .. } => {
+                self.trigger(Trigger::Mouse(*button), *state == ElementState::Pressed)
+            }
+            WindowEvent::MouseWheel { delta, .. } => {
+                self.scroll += match delta {
+                    MouseScrollDelta::LineDelta(_, y) => *y,
+                    MouseScrollDelta::PixelDelta(position) => position.y as f32 / 50.0,
+                };
+            }
+            WindowEvent::Focused(focused) => {
+                self.focused = *focused;
+                if !focused {
+                    // The window will not see the releases
+                    let triggers = self.down.keys().copied().collect::<Vec<_>>();
+                    for trigger in triggers {
+                        self.trigger(trigger, false);
+                    }
+                    self.modifiers = ModifiersState::empty();
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Records a key or mouse button being pressed or released (repeated
+    /// presses of a held key are ignored).
+    pub fn trigger(&mut self, trigger: Trigger, pressed: bool) {
+        if pressed {
+            if self.down.contains_key(&trigger) {
+                return;
+            }
+
+            let actions = self.bindings.actions(trigger, self.modifiers);
+            self.pressed.extend(actions.iter().copied());
+            self.down.insert(trigger, actions);
+        } else if let Some(actions) = self.down.remove(&trigger) {
+            for action in actions {
+                if !self.is_held(action) {
+                    self.released.insert(action);
+                }
+            }
+        }
+    }
+
+    /// Records raw mouse movement (ignored unless the cursor is grabbed).
+    pub fn mouse_motion(&mut self, delta: (f64, f64)) {
+        if self.grabbed {
+            self.mouse_delta.0 += delta.0 as f32;
+            self.mouse_delta.1 += delta.1 as f32;
+        }
+    }
+
+    /// Whether an action was pressed since the previous frame.
+    pub fn was_pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    /// Whether an action was released since the previous frame.
+    pub fn was_released(&self, action: Action) -> bool {
+        self.released.contains(&action)
+    }
+
+    /// Whether an action is held down.
+    pub fn is_held(&self, action: Action) -> bool {
+        self.down.values().any(|actions| actions.contains(&action))
+    }
+
+    /// How much the (grabbed) mouse moved since the previous frame in pixels.
+    pub fn mouse_delta(&self) -> (f32, f32) {
+        self.mouse_delta
+    }
+
+    /// How many scroll wheel steps were scrolled since the previous frame.
+    pub fn scroll(&self) -> f32 {
+        self.scroll
+    }
+
+    pub fn focused(&self) -> bool {
+        self.focused
+    }
+
+    pub fn grabbed(&self) -> bool {
+        self.grabbed
+    }
+
+    /// Grabs (and hides) or releases the cursor of a window, so the mouse can
+    /// rotate the camera without leaving the window.
+    pub fn set_grabbed(&mut self, window: &Window, grabbed: bool) -> Result<()> {
+        if grabbed {
+            // Not every platform supports both modes
+            window
+                .set_cursor_grab(CursorGrabMode::Locked)
+                .or_else(|_| window.set_cursor_grab(CursorGrabMode::Confined))?;
+        } else {
+            window.set_cursor_grab(CursorGrabMode::None)?;
+        }
+
+        window.set_cursor_visible(!grabbed);
+        self.grabbed = grabbed;
+        Ok(())
+    }
+
+    /// Forgets the presses, releases and mouse movement of the frame that was
+    /// just rendered (held actions stay held).
+    pub fn end_frame(&mut self) {
+        self.pressed.clear();
+        self.released.clear();
+        self.mouse_delta = (0.0, 0.0);
+        self.scroll = 0.0;
+    }
+}
+
+impl Default for Input {
+    fn default() -> Self {
+        Self::new(Bindings::default())
+    }
+}
+
+/// The names of the keys in bindings files.
+#[rustfmt::skip]
+const KEYS: &[(&str, VirtualKeyCode)] = {
+    use VirtualKeyCode::*;
+    &[
+        ("A", A), ("B", B), ("C", C), ("D", D), ("E", E), ("F", F), ("G", G), ("H", H), ("I", I),
+        ("J", J), ("K", K), ("L", L), ("M", M), ("N", N), ("O", O), ("P", P), ("Q", Q), ("R", R),
+        ("S", S), ("T", T), ("U", U), ("V", V), ("W", W), ("X", X), ("Y", Y), ("Z", Z),
+        ("0", Key0), ("1", Key1), ("2", Key2), ("3", Key3), ("4", Key4),
+        ("5", Key5), ("6", Key6), ("7", Key7), ("8", Key8), ("9", Key9),
+        ("F1", F1), ("F2", F2), ("F3", F3), ("F4", F4), ("F5", F5), ("F6", F6),
+        ("F7", F7), ("F8", F8), ("F9", F9), ("F10", F10), ("F11", F11), ("F12", F12),
+        ("Escape", Escape), ("Tab", Tab), ("Space", Space), ("Enter", Return), ("Backspace", Back),
+        ("Insert", Insert), ("Delete", Delete), ("Home", Home), ("End", End),
+        ("PageUp", PageUp), ("PageDown", PageDown),
+        ("Left", Left), ("Right", Right), ("Up", Up), ("Down", Down),
+        ("Minus", Minus), ("Equals", Equals), ("Plus", Plus), ("Comma", Comma), ("Period", Period),
+        ("Slash", Slash), ("Backslash", Backslash), ("Semicolon", Semicolon), ("Apostrophe", Apostrophe),
+        ("LBracket", LBracket), ("RBracket", RBracket), ("Grave", Grave),
+        ("LShift", LShift), ("RShift", RShift), ("LControl", LControl), ("RControl", RControl),
+        ("LAlt", LAlt), ("RAlt", RAlt),
+    ]
+};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bindings_are_parsed_and_displayed() -> Result<()> {
+        let binding = "ctrl + Shift+s".parse::<Binding>()?;
+        assert_eq!(binding.trigger, Trigger::Key(VirtualKeyCode::S));
+        assert_eq!(binding.modifiers, ModifiersState::CTRL | ModifiersState::SHIFT);
+        assert_eq!(binding.to_string(), "Ctrl+Shift+S");
+        assert_eq!("MouseRight".parse::<Binding>()?, Binding::mouse(MouseButton::Right));
+
+        assert!("Hyper+S".parse::<Binding>().is_err());
+        assert!("Ctrl+".parse::<Binding>().is_err());
+        assert!("Sideways".parse::<Binding>().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn bindings_files_replace_the_bindings_of_an_action() -> Result<()> {
+        let mut bindings = Bindings::default();
+        bindings.apply_config("# Keys\nscreenshot = P, Ctrl+F12\ncycle-msaa =\n")?;
+
+        assert_eq!(bindings.get(Action::Screenshot), &["P".parse()?, "Ctrl+F12".parse()?]);
+        assert!(bindings.get(Action::CycleMsaa).is_empty());
+        assert_eq!(bindings.get(Action::AddModel), &[Binding::key(VirtualKeyCode::Right)]);
+
+        let error = bindings.apply_config("screenshot = P\nfly = F\n").unwrap_err();
+        assert!(error.to_string().contains("Line 2"), "{}", error);
+
+        Ok(())
+    }
+
+    #[test]
+    fn press_release_and_hold() {
+        let mut input = Input::default();
+        let w = Trigger::Key(VirtualKeyCode::W);
+
+        input.trigger(w, true);
+        input.trigger(w, true);
+        assert!(input.was_pressed(Action::MoveForward) && input.is_held(Action::MoveForward));
+
+        input.end_frame();
+        assert!(!input.was_pressed(Action::MoveForward) && input.is_held(Action::MoveForward));
+
+        input.trigger(w, false);
+        assert!(input.was_released(Action::MoveForward) && !input.is_held(Action::MoveForward));
+
+        input.end_frame();
+        assert!(!input.was_released(Action::MoveForward));
+    }
+
+    #[test]
+    fn modifiers_select_the_most_specific_binding() -> Result<()> {
+        let mut input = Input::default();
+        input.bindings.apply_config("screenshot = Ctrl+S")?;
+        let s = Trigger::Key(VirtualKeyCode::S);
+
+        input.trigger(s, true);
+        assert!(input.was_pressed(Action::MoveBack) && !input.was_pressed(Action::Screenshot));
+        input.trigger(s, false);
+        input.end_frame();
+
+        input.handle_window_event(&WindowEvent::ModifiersChanged(ModifiersState::CTRL));
+        input.trigger(s, true);
+        assert!(input.was_pressed(Action::Screenshot) && !input.was_pressed(Action::MoveBack));
+
+        // Losing focus releases what is held
+        input.handle_window_event(&WindowEvent::Focused(false));
+        assert!(input.was_released(Action::Screenshot) && !input.is_held(Action::Screenshot));
+
+        Ok(())
+    }
+}
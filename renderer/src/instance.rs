@@ -7,7 +7,7 @@ use std::collections::HashSet;
 use std::ffi::CStr;
 use std::os::raw::c_void;
 
-use anyhow::{anyhow, Result};
+use anyhow::Result;
 use winit::window::Window;
 use vulkanalia::prelude::v1_0::*;
 use vulkanalia::window as vk_window;
@@ -15,7 +15,8 @@ use vulkanalia::Version;
 use vulkanalia::vk::ExtDebugUtilsExtension;
 use log::*;
 
-/// Whether the validation layers should be enabled.
+/// Whether the validation layers are enabled by default (see
+/// [`Settings::validation`](crate::settings::Settings::validation)).
 pub const VALIDATION_ENABLED: bool = cfg!(debug_assertions);
 /// The name of the validation layers.
 pub const VALIDATION_LAYER: vk::ExtensionName = vk::ExtensionName::from_bytes(b"VK_LAYER_KHRONOS_validation");
@@ -23,7 +24,7 @@ pub const VALIDATION_LAYER: vk::ExtensionName = vk::ExtensionName::from_bytes(b"
 /// The Vulkan SDK version that started requiring the portability subset extension for macOS.
 pub const PORTABILITY_MACOS_VERSION: Version = Version::new(1, 3, 216);
 
-pub unsafe fn create_instance(window: &Window, entry: &Entry, data: &mut AppData) -> Result<Instance> {
+pub unsafe fn create_instance(window: Option<&Window>, entry: &Entry, data: &mut AppData) -> Result<Instance> {
     // // Application Info
     let application_info = vk::ApplicationInfo::builder()
         .application_name(b"Vulkan Tutorial (Rust)\0")
@@ -39,18 +40,21 @@ pub unsafe fn create_instance(window: &Window, entry: &Entry, data: &mut AppData
         .map(|l| l.layer_name)
         .collect::<HashSet<_>>();
     
-    if VALIDATION_ENABLED && !available_layers.contains(&VALIDATION_LAYER) {
-        return Err(anyhow!("Validation layer requested but not supported."));
+    if data.settings.validation && !available_layers.contains(&VALIDATION_LAYER) {
+        warn!("Validation layer requested but not supported, continuing without validation.");
+        data.settings.validation = false;
     }
 
-    let layers = if VALIDATION_ENABLED {
+    let layers = if data.settings.validation {
         vec![VALIDATION_LAYER.as_ptr()]
     } else {
         Vec::new()
     };
 
-    // Extensions
-    let mut extensions = vk_window::get_required_instance_extensions(window)
+    // Extensions (headless instances do not need the surface extensions)
+    let mut extensions = window
+        .map(|w| vk_window::get_required_instance_extensions(w))
+        .unwrap_or(&[])
         .iter()
         .map(|e| e.as_ptr())
         .collect::<Vec<_>>();
@@ -68,7 +72,7 @@ pub unsafe fn create_instance(window: &Window, entry: &Entry, data: &mut AppData
         vk::InstanceCreateFlags::empty()
     };
 
-    if VALIDATION_ENABLED {
+    if data.settings.validation {
         extensions.push(vk::EXT_DEBUG_UTILS_EXTENSION.name.as_ptr());
     }
 
@@ -84,14 +88,14 @@ pub unsafe fn create_instance(window: &Window, entry: &Entry, data: &mut AppData
         .message_type(vk::DebugUtilsMessageTypeFlagsEXT::all())
         .user_callback(Some(debug_callback));
 
-    if VALIDATION_ENABLED {
+    if data.settings.validation {
         info = info.push_next(&mut debug_info);
     }
 
     let instance = entry.create_instance(&info, None)?;
 
     // Messenger
-    if VALIDATION_ENABLED {
+    if data.settings.validation {
         data.messenger = instance.create_debug_utils_messenger_ext(&debug_info, None)?;
     }
 
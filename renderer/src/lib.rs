@@ -0,0 +1,44 @@
+//! The Vulkan building blocks shared by the later chapters (demo_20 onwards).
+//!
+//! Everything lives in [`app_data::AppData`], which the functions of each
+//! module fill in or read from, so a chapter only has to provide its own app
+//! (the order things are created and destroyed in, its shaders, uniforms and
+//! command recording).
+
+pub mod app_data;
+pub mod settings;
+pub mod assets;
+pub mod input;
+pub mod camera;
+pub mod error;
+pub mod structs;
+pub mod instance;
+pub mod physical_device;
+pub mod logical_device;
+pub mod swapchain;
+pub mod memory;
+pub mod owned;
+pub mod tracker;
+pub mod commands;
+pub mod command_pool;
+pub mod buffers;
+pub mod images;
+pub mod mipmaps;
+pub mod decode;
+pub mod compressed;
+pub mod texture;
+pub mod model;
+pub mod scene;
+pub mod pipeline;
+pub mod pipeline_cache;
+pub mod shaders;
+pub mod reflect;
+pub mod descriptor;
+pub mod depth_objects;
+pub mod color_objects;
+pub mod framebuffers;
+pub mod sync_objects;
+pub mod frame_stats;
+pub mod queries;
+pub mod offscreen;
+pub mod screenshot;
\ No newline at end of file
@@ -2,16 +2,17 @@
 // Logical Device
 //================================================
 use crate::app_data::AppData;
-use crate::instance::{VALIDATION_ENABLED, VALIDATION_LAYER, PORTABILITY_MACOS_VERSION};
+use crate::instance::{VALIDATION_LAYER, PORTABILITY_MACOS_VERSION};
 use crate::structs::QueueFamilyIndices;
 use crate::physical_device::DEVICE_EXTENSIONS;
 
 use std::collections::HashSet;
+use std::rc::Rc;
 
 use anyhow::Result;
 use vulkanalia::prelude::v1_0::*;
 
-pub unsafe fn create_logical_device(entry: &Entry, instance: &Instance, data: &mut AppData) -> Result<Device> {
+pub unsafe fn create_logical_device(entry: &Entry, instance: &Instance, data: &mut AppData) -> Result<Rc<Device>> {
     // Queue Create Infos
     let indices = QueueFamilyIndices::get(instance, data, data.physical_device)?;
 
@@ -30,14 +31,14 @@ pub unsafe fn create_logical_device(entry: &Entry, instance: &Instance, data: &m
         .collect::<Vec<_>>();
 
     // Layers
-    let layers = if VALIDATION_ENABLED {
+    let layers = if data.settings.validation {
         vec![VALIDATION_LAYER.as_ptr()]
     } else {
         vec![]
     };
 
-    // Extensions
-    let mut extensions = DEVICE_EXTENSIONS
+    // Extensions (headless apps do not use a swapchain)
+    let mut extensions = if data.headless { &[] } else { DEVICE_EXTENSIONS }
         .iter()
         .map(|n| n.as_ptr())
         .collect::<Vec<_>>();
@@ -47,10 +48,17 @@ pub unsafe fn create_logical_device(entry: &Entry, instance: &Instance, data: &m
         extensions.push(vk::KHR_PORTABILITY_SUBSET_EXTENSION.name.as_ptr());
     }
 
-    // Features
+    // Features (pipeline statistics queries and texture compression are optional)
+    let supported = instance.get_physical_device_features(data.physical_device);
+    data.pipeline_statistics_query = supported.pipeline_statistics_query == vk::TRUE;
+
     let features = vk::PhysicalDeviceFeatures::builder()
         .sampler_anisotropy(true)
-        .sample_rate_shading(true);
+        .sample_rate_shading(true)
+        .pipeline_statistics_query(data.pipeline_statistics_query)
+        .texture_compression_bc(supported.texture_compression_bc == vk::TRUE)
+        .texture_compression_etc2(supported.texture_compression_etc2 == vk::TRUE)
+        .texture_compression_astc_ldr(supported.texture_compression_astc_ldr == vk::TRUE);
 
     // Create
     let info = vk::DeviceCreateInfo::builder()
@@ -66,5 +74,5 @@ pub unsafe fn create_logical_device(entry: &Entry, instance: &Instance, data: &m
     data.graphics_queue = device.get_device_queue(indices.graphics, 0);
     data.present_queue = device.get_device_queue(indices.present, 0);
 
-    Ok(device)
+    Ok(Rc::new(device))
 }
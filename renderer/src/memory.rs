@@ -0,0 +1,403 @@
+//================================================
+// Memory
+//================================================
+//! A block-based sub-allocator for device memory.
+//!
+//! Every buffer and image used to get its own `vkAllocateMemory`, which runs
+//! into `maxMemoryAllocationCount` (as low as 4096) quickly. Instead, memory is
+//! allocated in large blocks per memory type and resources are placed inside
+//! them with a first-fit free list, honoring the alignment of each resource and
+//! the `bufferImageGranularity` between linear and optimal resources.
+//!
+//! Host visible blocks are mapped once when they are allocated (a memory object
+//! can only be mapped once at a time), so resources in them are written through
+//! [`Allocation::mapped`] instead of `map_memory`/`unmap_memory`.
+
+use std::cell::{Cell, RefCell};
+use std::fmt;
+use std::os::raw::c_void;
+use std::ptr;
+
+use anyhow::{anyhow, Result};
+use vulkanalia::prelude::v1_0::*;
+use log::*;
+
+/// The size of the blocks allocated from heaps larger than 512 MiB.
+pub const BLOCK_SIZE: vk::DeviceSize = 64 * 1024 * 1024;
+
+/// A range of device memory sub-allocated for a single buffer or image.
+#[derive(Copy, Clone, Debug)]
+pub struct Allocation {
+    pub memory: vk::DeviceMemory,
+    pub offset: vk::DeviceSize,
+    pub size:   vk::DeviceSize,
+    /// The host address of the range (null unless the memory is host visible).
+    pub mapped: *mut c_void,
+    block: u64,
+}
+
+impl Default for Allocation {
+    fn default() -> Self {
+        Self {
+            memory: vk::DeviceMemory::null(),
+            offset: 0,
+            size: 0,
+            mapped: ptr::null_mut(),
+            block: 0,
+        }
+    }
+}
+
+/// The memory usage of a memory heap.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct HeapStats {
+    pub heap_index: u32,
+    pub heap_size:  vk::DeviceSize,
+    /// The number of blocks (`vkAllocateMemory` calls) in the heap.
+    pub blocks: usize,
+    pub block_bytes: vk::DeviceSize,
+    /// The number of buffers and images placed in those blocks.
+    pub allocations: usize,
+    pub allocation_bytes: vk::DeviceSize,
+}
+
+impl fmt::Display for HeapStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        const MIB: f64 = 1024.0 * 1024.0;
+        write!(
+            f,
+            "heap {}: {} allocations using {:.1} MiB of {} blocks ({:.1} MiB, heap size {:.1} MiB)",
+            self.heap_index,
+            self.allocations,
+            self.allocation_bytes as f64 / MIB,
+            self.blocks,
+            self.block_bytes as f64 / MIB,
+            self.heap_size as f64 / MIB,
+        )
+    }
+}
+
+/// Sub-allocates buffers and images from large blocks of device memory.
+///
+/// The allocator lives in `AppData` behind an `Rc` and is only used from the
+/// render thread, so it tracks its blocks with interior mutability.
+#[derive(Debug, Default)]
+pub struct Allocator {
+    properties:  vk::PhysicalDeviceMemoryProperties,
+    granularity: vk::DeviceSize,
+    blocks:      RefCell<Vec<Block>>,
+    next_block:  Cell<u64>,
+}
+
+impl Allocator {
+    pub unsafe fn new(instance: &Instance, physical_device: vk::PhysicalDevice) -> Self {
+        let limits = instance.get_physical_device_properties(physical_device).limits;
+        Self {
+            properties: instance.get_physical_device_memory_properties(physical_device),
+            granularity: limits.buffer_image_granularity.max(1),
+            blocks: RefCell::new(Vec::new()),
+            next_block: Cell::new(1),
+        }
+    }
+
+    /// Allocates memory with the given properties for a resource, where
+    /// `linear` is set for buffers and linearly tiled images.
+    pub unsafe fn allocate(
+        &self,
+        device: &Device,
+        requirements: vk::MemoryRequirements,
+        properties: vk::MemoryPropertyFlags,
+        linear: bool,
+    ) -> Result<Allocation> {
+        let memory_type_index = self.get_memory_type_index(properties, requirements)?;
+        let alignment = requirements.alignment.max(1);
+
+        let mut blocks = self.blocks.borrow_mut();
+
+        // Existing blocks
+        for block in blocks.iter_mut().filter(|b| b.memory_type_index == memory_type_index) {
+            if let Some(offset) = block.find(requirements.size, alignment, linear, self.granularity) {
+                return Ok(block.insert(offset, requirements.size, linear));
+            }
+        }
+
+        // New block (resources larger than a block get one of their own)
+        let size = self.block_size(memory_type_index).max(requirements.size);
+
+        let info = vk::MemoryAllocateInfo::builder()
+            .allocation_size(size)
+            .memory_type_index(memory_type_index);
+
+        let memory = device.allocate_memory(&info, None)?;
+
+        let host_visible = self.properties.memory_types[memory_type_index as usize]
+            .property_flags
+            .contains(vk::MemoryPropertyFlags::HOST_VISIBLE);
+        let mapped = if host_visible {
+            match device.map_memory(memory, 0, vk::WHOLE_SIZE as u64, vk::MemoryMapFlags::empty()) {
+                Ok(mapped) => mapped,
+                Err(e) => {
+                    device.free_memory(memory, None);
+                    return Err(anyhow!(e));
+                }
+            }
+        } else {
+            ptr::null_mut()
+        };
+
+        let id = self.next_block.get();
+        self.next_block.set(id + 1);
+        debug!("Allocated memory block {} ({} bytes, memory type {}).", id, size, memory_type_index);
+
+        let mut block = Block::new(id, memory, memory_type_index, size, mapped);
+        let allocation = block.insert(0, requirements.size, linear);
+        blocks.push(block);
+
+        Ok(allocation)
+    }
+
+    /// Returns an allocation to its block, releasing the block's memory once
+    /// it is empty (except for the last block of a memory type).
+    pub unsafe fn free(&self, device: &Device, allocation: Allocation) {
+        if allocation.memory.is_null() {
+            return;
+        }
+
+        let mut blocks = self.blocks.borrow_mut();
+        let index = match blocks.iter().position(|b| b.id == allocation.block) {
+            Some(index) => index,
+            None => {
+                warn!("Freeing memory that was not allocated by this allocator ({:?}).", allocation);
+                return;
+            }
+        };
+
+        blocks[index].remove(allocation.offset);
+
+        let block = &blocks[index];
+        let oversized = block.size > self.block_size(block.memory_type_index);
+        let siblings = blocks
+            .iter()
+            .filter(|b| b.memory_type_index == block.memory_type_index)
+            .count();
+        if block.regions.is_empty() && (oversized || siblings > 1) {
+            debug!("Freed memory block {} ({} bytes).", block.id, block.size);
+            device.free_memory(block.memory, None);
+            blocks.remove(index);
+        }
+    }
+
+    /// Frees all blocks (which must no longer be used by any resource).
+    pub unsafe fn destroy(&self, device: &Device) {
+        for block in self.blocks.borrow_mut().drain(..) {
+            if !block.regions.is_empty() {
+                warn!("Memory block {} still holds {} allocations.", block.id, block.regions.len());
+            }
+
+            device.free_memory(block.memory, None);
+        }
+    }
+
+    /// Returns the usage statistics of each memory heap.
+    pub fn stats(&self) -> Vec<HeapStats> {
+        let mut stats = (0..self.properties.memory_heap_count)
+            .map(|i| HeapStats {
+                heap_index: i,
+                heap_size: self.properties.memory_heaps[i as usize].size,
+                ..Default::default()
+            })
+            .collect::<Vec<_>>();
+
+        for block in self.blocks.borrow().iter() {
+            let heap_index = self.properties.memory_types[block.memory_type_index as usize].heap_index;
+            let heap = &mut stats[heap_index as usize];
+            heap.blocks += 1;
+            heap.block_bytes += block.size;
+            heap.allocations += block.regions.len();
+            heap.allocation_bytes += block.regions.iter().map(|r| r.size).sum::<vk::DeviceSize>();
+        }
+
+        stats
+    }
+
+    fn get_memory_type_index(
+        &self,
+        properties: vk::MemoryPropertyFlags,
+        requirements: vk::MemoryRequirements,
+    ) -> Result<u32> {
+        (0..self.properties.memory_type_count)
+            .find(|i| {
+                let suitable = (requirements.memory_type_bits & (1 << i)) != 0;
+                let memory_type = self.properties.memory_types[*i as usize];
+
+                suitable && memory_type.property_flags.contains(properties)
+            })
+            .ok_or_else(|| anyhow!("Failed to find suitable memory type."))
+    }
+
+    /// Small heaps (e.g. the 256 MiB host visible device memory of many GPUs)
+    /// get smaller blocks so a few of them do not exhaust the heap.
+    fn block_size(&self, memory_type_index: u32) -> vk::DeviceSize {
+        let heap_index = self.properties.memory_types[memory_type_index as usize].heap_index;
+        let heap_size = self.properties.memory_heaps[heap_index as usize].size;
+        if heap_size <= 512 * 1024 * 1024 {
+            heap_size / 8
+        } else {
+            BLOCK_SIZE
+        }
+    }
+}
+
+/// A range of a block in use by a resource.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+struct Region {
+    offset: vk::DeviceSize,
+    size:   vk::DeviceSize,
+    linear: bool,
+}
+
+/// A device memory allocation resources are placed in.
+#[derive(Debug)]
+struct Block {
+    id:     u64,
+    memory: vk::DeviceMemory,
+    memory_type_index: u32,
+    size:   vk::DeviceSize,
+    mapped: *mut c_void,
+    /// The regions in use, sorted by offset.
+    regions: Vec<Region>,
+}
+
+impl Block {
+    fn new(id: u64, memory: vk::DeviceMemory, memory_type_index: u32, size: vk::DeviceSize, mapped: *mut c_void) -> Self {
+        Self { id, memory, memory_type_index, size, mapped, regions: Vec::new() }
+    }
+
+    /// Finds the first offset a resource fits at, keeping linear and optimal
+    /// resources on separate `granularity` sized pages.
+    fn find(
+        &self,
+        size: vk::DeviceSize,
+        alignment: vk::DeviceSize,
+        linear: bool,
+        granularity: vk::DeviceSize,
+    ) -> Option<vk::DeviceSize> {
+        let same_page = |a: vk::DeviceSize, b: vk::DeviceSize| a / granularity == b / granularity;
+
+        let mut previous: Option<&Region> = None;
+        for index in 0..=self.regions.len() {
+            let next = self.regions.get(index);
+            let start = previous.map_or(0, |p| p.offset + p.size);
+            let end = next.map_or(self.size, |n| n.offset);
+
+            let mut offset = align_up(start, alignment);
+            if let Some(p) = previous {
+                if p.linear != linear && same_page(p.offset + p.size - 1, offset) {
+                    offset = align_up(offset, granularity);
+                }
+            }
+
+            let conflict = next.is_some_and(|n| n.linear != linear && same_page(offset + size - 1, n.offset));
+            if offset + size <= end && !conflict {
+                return Some(offset);
+            }
+
+            previous = next;
+        }
+
+        None
+    }
+
+    fn insert(&mut self, offset: vk::DeviceSize, size: vk::DeviceSize, linear: bool) -> Allocation {
+        let index = self.regions.partition_point(|r| r.offset < offset);
+        self.regions.insert(index, Region { offset, size, linear });
+
+        let mapped = if self.mapped.is_null() {
+            ptr::null_mut()
+        } else {
+            unsafe { self.mapped.cast::<u8>().add(offset as usize).cast() }
+        };
+
+        Allocation { memory: self.memory, offset, size, mapped, block: self.id }
+    }
+
+    fn remove(&mut self, offset: vk::DeviceSize) {
+        if let Some(index) = self.regions.iter().position(|r| r.offset == offset) {
+            self.regions.remove(index);
+        } else {
+            warn!("Freeing memory at offset {} of block {} twice.", offset, self.id);
+        }
+    }
+}
+
+fn align_up(value: vk::DeviceSize, alignment: vk::DeviceSize) -> vk::DeviceSize {
+    value.div_ceil(alignment) * alignment
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn block(size: vk::DeviceSize) -> Block {
+        Block::new(1, vk::DeviceMemory::null(), 0, size, ptr::null_mut())
+    }
+
+    #[test]
+    fn allocations_are_aligned_and_packed() {
+        let mut block = block(1024);
+        let a = block.find(100, 16, true, 1).unwrap();
+        block.insert(a, 100, true);
+        let b = block.find(10, 64, true, 1).unwrap();
+        block.insert(b, 10, true);
+
+        assert_eq!(a, 0);
+        assert_eq!(b, 128);
+        assert_eq!(block.find(2000, 1, true, 1), None);
+    }
+
+    #[test]
+    fn freed_ranges_are_reused() {
+        let mut block = block(256);
+        for offset in [0, 64, 128, 192] {
+            assert_eq!(block.find(64, 64, true, 1), Some(offset));
+            block.insert(offset, 64, true);
+        }
+        assert_eq!(block.find(64, 64, true, 1), None);
+
+        block.remove(64);
+        assert_eq!(block.find(64, 64, true, 1), Some(64));
+        assert_eq!(block.find(65, 1, true, 1), None);
+    }
+
+    #[test]
+    fn linear_and_optimal_resources_do_not_share_pages() {
+        let mut block = block(4096);
+        block.insert(0, 100, true);
+
+        // An image after a buffer starts on the next page ...
+        let image = block.find(100, 16, false, 1024).unwrap();
+        assert_eq!(image, 1024);
+        block.insert(image, 100, false);
+
+        // ... but buffers still fill the rest of the first page
+        assert_eq!(block.find(100, 16, true, 1024), Some(112));
+    }
+
+    #[test]
+    fn gaps_next_to_conflicting_pages_are_skipped() {
+        let mut block = block(4096);
+        block.insert(1000, 100, false);
+
+        // A buffer before the image would end on its page, so it goes after it
+        assert_eq!(block.find(500, 1, true, 1024), Some(2048));
+        assert_eq!(block.find(500, 1, false, 1024), Some(0));
+    }
+
+    #[test]
+    fn mapped_pointers_are_offset() {
+        let mut memory = [0u8; 256];
+        let mut block = Block::new(1, vk::DeviceMemory::null(), 0, 256, memory.as_mut_ptr().cast());
+        let allocation = block.insert(64, 16, true);
+        assert_eq!(allocation.mapped, unsafe { memory.as_mut_ptr().add(64).cast() });
+    }
+}
\ No newline at end of file
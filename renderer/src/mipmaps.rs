@@ -0,0 +1,255 @@
+//================================================
+// Mipmaps
+//================================================
+//! Builds the mip chain of an RGBA8 image on the CPU, for formats the device
+//! cannot blit with linear filtering (see [`crate::images::generate_mipmaps`])
+//! or when `--cpu-mipmaps` asks for the higher quality of a wider filter.
+//!
+//! Each level is resampled from the one above it with a separable filter.
+//! sRGB colors are filtered in linear space (alpha always is linear), so a
+//! black and white checkerboard becomes the gray of half the light rather
+//! than a darker one.
+
+use crate::decode::Pixels;
+
+/// The filter levels are resampled with.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum MipFilter {
+    /// The average of the texels a texel of the next level covers.
+    Box,
+    /// A Kaiser windowed sinc, which keeps levels sharper without aliasing.
+    Kaiser,
+}
+
+/// The half width of the Kaiser window (in texels of the next level).
+const KAISER_WIDTH: f32 = 3.0;
+/// The shape of the Kaiser window (higher is smoother, but blurrier).
+const KAISER_ALPHA: f32 = 4.0;
+
+impl MipFilter {
+    /// How far from its center the filter reaches.
+    fn support(self) -> f32 {
+        match self {
+            Self::Box => 0.5,
+            Self::Kaiser => KAISER_WIDTH,
+        }
+    }
+
+    /// The (unnormalized) weight of a texel at `x` from the center.
+    fn weight(self, x: f32) -> f32 {
+        let x = x.abs();
+        match self {
+            // Texels on the edge of two boxes count half for each
+            Self::Box if x < 0.5 => 1.0,
+            Self::Box if x == 0.5 => 0.5,
+            Self::Box => 0.0,
+            Self::Kaiser if x < KAISER_WIDTH => {
+                let window = bessel_i0(KAISER_ALPHA * (1.0 - (x / KAISER_WIDTH).powi(2)).sqrt()) / bessel_i0(KAISER_ALPHA);
+                sinc(x) * window
+            }
+            Self::Kaiser => 0.0,
+        }
+    }
+}
+
+fn sinc(x: f32) -> f32 {
+    if x.abs() < 1e-6 {
+        1.0
+    } else {
+        let x = std::f32::consts::PI * x;
+        x.sin() / x
+    }
+}
+
+/// The modified Bessel function of the first kind of order zero (its series).
+fn bessel_i0(x: f32) -> f32 {
+    let (mut sum, mut term, mut k) = (1.0, 1.0, 1.0);
+    while term > sum * 1e-8 {
+        term *= (x / (2.0 * k)).powi(2);
+        sum += term;
+        k += 1.0;
+    }
+    sum
+}
+
+/// The number of levels of a full mip chain (down to 1x1).
+pub fn mip_levels(width: u32, height: u32) -> u32 {
+    width.max(height).max(1).ilog2() + 1
+}
+
+/// The levels below an image in a full mip chain, each half the size of the
+/// one above (rounded down, at least 1).
+pub fn generate_mip_chain(pixels: &Pixels, srgb: bool, filter: MipFilter) -> Vec<Pixels> {
+    let to_linear = |c: u8| if srgb { srgb_to_linear(c) } else { c as f32 / 255.0 };
+    let mut texels = pixels
+        .data
+        .chunks_exact(4)
+        .map(|t| [to_linear(t[0]), to_linear(t[1]), to_linear(t[2]), t[3] as f32 / 255.0])
+        .collect::<Vec<_>>();
+
+    let (mut width, mut height) = (pixels.width, pixels.height);
+    let mut levels = vec![];
+    for _ in 1..mip_levels(width, height) {
+        let (next_width, next_height) = ((width / 2).max(1), (height / 2).max(1));
+        texels = resample(&texels, width, height, next_width, next_height, filter);
+        (width, height) = (next_width, next_height);
+
+        let data = texels
+            .iter()
+            .flat_map(|[r, g, b, a]| {
+                let encode = |c: f32| if srgb { linear_to_srgb(c) } else { unorm(c) };
+                [encode(*r), encode(*g), encode(*b), unorm(*a)]
+            })
+            .collect();
+
+        levels.push(Pixels { width, height, data });
+    }
+
+    levels
+}
+
+/// Resamples texels to another size, first horizontally, then vertically.
+fn resample(texels: &[[f32; 4]], width: u32, height: u32, new_width: u32, new_height: u32, filter: MipFilter) -> Vec<[f32; 4]> {
+    let (width, height, new_width) = (width as usize, height as usize, new_width as usize);
+
+    let columns = taps(width, new_width, filter);
+    let mut horizontal = Vec::with_capacity(new_width * height);
+    for row in texels.chunks_exact(width) {
+        horizontal.extend(columns.iter().map(|taps| apply(taps, |i| row[i])));
+    }
+
+    let rows = taps(height, new_height as usize, filter);
+    let mut vertical = Vec::with_capacity(new_width * new_height as usize);
+    for taps in &rows {
+        vertical.extend((0..new_width).map(|x| apply(taps, |y| horizontal[y * new_width + x])));
+    }
+
+    vertical
+}
+
+/// The source texels (and their normalized weights) of each texel of a row or
+/// column resampled from `length` to `new_length` texels. Texels beyond the
+/// edges are clamped to them.
+fn taps(length: usize, new_length: usize, filter: MipFilter) -> Vec<Vec<(usize, f32)>> {
+    let scale = length as f32 / new_length as f32;
+    let support = filter.support() * scale;
+
+    (0..new_length)
+        .map(|i| {
+            let center = (i as f32 + 0.5) * scale;
+            let first = (center - support - 0.5).floor() as isize;
+            let last = (center + support - 0.5).ceil() as isize;
+
+            let mut taps = Vec::<(usize, f32)>::new();
+            for source in first..=last {
+                let weight = filter.weight((source as f32 + 0.5 - center) / scale);
+                if weight == 0.0 {
+                    continue;
+                }
+
+                let source = source.clamp(0, length as isize - 1) as usize;
+                match taps.iter_mut().find(|(s, _)| *s == source) {
+                    Some((_, w)) => *w += weight,
+                    None => taps.push((source, weight)),
+                }
+            }
+
+            let total = taps.iter().map(|(_, w)| w).sum::<f32>();
+            taps.iter().map(|(s, w)| (*s, w / total)).collect()
+        })
+        .collect()
+}
+
+fn apply(taps: &[(usize, f32)], texel: impl Fn(usize) -> [f32; 4]) -> [f32; 4] {
+    let mut sum = [0.0; 4];
+    for (source, weight) in taps {
+        for (s, t) in sum.iter_mut().zip(texel(*source)) {
+            *s += weight * t;
+        }
+    }
+    sum
+}
+
+fn srgb_to_linear(c: u8) -> f32 {
+    let c = c as f32 / 255.0;
+    if c <= 0.04045 { c / 12.92 } else { ((c + 0.055) / 1.055).powf(2.4) }
+}
+
+fn linear_to_srgb(c: f32) -> u8 {
+    let c = c.clamp(0.0, 1.0);
+    unorm(if c <= 0.0031308 { c * 12.92 } else { 1.055 * c.powf(1.0 / 2.4) - 0.055 })
+}
+
+fn unorm(c: f32) -> u8 {
+    (c.clamp(0.0, 1.0) * 255.0).round() as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sizes(levels: &[Pixels]) -> Vec<(u32, u32)> {
+        levels.iter().map(|l| (l.width, l.height)).collect()
+    }
+
+    #[test]
+    fn box_filtered_levels_average_their_texels() {
+        let pixels = Pixels { width: 2, height: 2, data: [[0, 100, 200, 0], [100, 100, 100, 100], [0, 0, 0, 255], [100, 0, 0, 45]].concat() };
+        let levels = generate_mip_chain(&pixels, false, MipFilter::Box);
+        assert_eq!(sizes(&levels), [(1, 1)]);
+        assert_eq!(levels[0].data, [50, 50, 75, 100]);
+
+        // Odd sizes round down, and every texel contributes
+        let pixels = Pixels { width: 5, height: 3, data: [[255; 4]; 15].concat() };
+        let levels = generate_mip_chain(&pixels, false, MipFilter::Box);
+        assert_eq!(sizes(&levels), [(2, 1), (1, 1)]);
+        assert_eq!(mip_levels(5, 3), 3);
+        assert_eq!(mip_levels(1024, 1), 11);
+        assert!(levels.iter().all(|l| l.data.iter().all(|c| *c == 255)));
+
+        let taps = taps(5, 2, MipFilter::Box);
+        assert_eq!(taps[0].iter().map(|(s, _)| *s).collect::<Vec<_>>(), [0, 1, 2]);
+        assert_eq!(taps[1].iter().map(|(s, _)| *s).collect::<Vec<_>>(), [2, 3, 4]);
+    }
+
+    #[test]
+    fn srgb_colors_are_filtered_in_linear_space() {
+        // A black and white checkerboard with half transparent texels
+        let (black, white) = ([0, 0, 0, 255], [255, 255, 255, 0]);
+        let pixels = Pixels { width: 2, height: 2, data: [black, white, white, black].concat() };
+
+        // Half the light is lighter than half the sRGB value
+        let srgb = generate_mip_chain(&pixels, true, MipFilter::Box);
+        assert_eq!(srgb[0].data, [188, 188, 188, 128]);
+        let unorm = generate_mip_chain(&pixels, false, MipFilter::Box);
+        assert_eq!(unorm[0].data, [128, 128, 128, 128]);
+
+        // The conversions are inverses of each other
+        assert!((0..=255).all(|c| linear_to_srgb(srgb_to_linear(c)) == c));
+    }
+
+    #[test]
+    fn kaiser_weights_are_normalized_and_keep_flat_images_flat() {
+        assert_eq!(MipFilter::Kaiser.weight(0.0), 1.0);
+        assert!(MipFilter::Kaiser.weight(1.0).abs() < 1e-6);
+        assert_eq!(MipFilter::Kaiser.weight(KAISER_WIDTH), 0.0);
+
+        for taps in taps(16, 8, MipFilter::Kaiser) {
+            assert!((taps.iter().map(|(_, w)| w).sum::<f32>() - 1.0).abs() < 1e-5);
+            // 6 texels of the next level on each side of the center
+            assert!(taps.len() <= 12);
+        }
+
+        let flat = Pixels { width: 16, height: 8, data: [[10, 20, 30, 40]; 16 * 8].concat() };
+        let levels = generate_mip_chain(&flat, true, MipFilter::Kaiser);
+        assert_eq!(sizes(&levels), [(8, 4), (4, 2), (2, 1), (1, 1)]);
+        assert!(levels.iter().all(|l| l.data.chunks_exact(4).all(|t| t == [10, 20, 30, 40])));
+
+        // Ringing at hard edges is clamped, and the edge stays symmetric
+        let edge = Pixels { width: 8, height: 1, data: [[[0; 4]; 4].concat(), [[255; 4]; 4].concat()].concat() };
+        let level = &generate_mip_chain(&edge, false, MipFilter::Kaiser)[0];
+        let red = level.data.chunks_exact(4).map(|t| t[0]).collect::<Vec<_>>();
+        assert_eq!((red[0], red[3]), (0, 255));
+        assert_eq!(red[1] as u32 + red[2] as u32, 255);
+    }
+}
@@ -0,0 +1,337 @@
+//================================================
+// Model
+//================================================
+//! Models made of several meshes, each drawn as its own index range with its
+//! own material and the transform of the nodes that use it.
+//!
+//! OBJ models (with the material each object's `usemtl` names in the `.mtl`
+//! file next to it) are loaded here, glTF scenes by [`crate::scene`]. The
+//! vertices of every mesh share one vertex and index buffer, so a model is
+//! drawn by binding them once and issuing a draw per [`Mesh`] of each [`Node`].
+
+use crate::app_data::AppData;
+use crate::scene;
+use crate::structs::{Mat4, Vec3, Vertex};
+use crate::texture::TextureSource;
+
+use std::collections::HashMap;
+use std::path::Path;
+
+use anyhow::{Context, Result};
+use cgmath::{vec2, vec3, InnerSpace, SquareMatrix, Zero};
+use log::*;
+
+/// An object of a model, drawn with the indices
+/// `first_index..first_index + index_count`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mesh {
+    pub name: String,
+    pub first_index: u32,
+    pub index_count: u32,
+    /// The index of the mesh's material in [`Model::materials`].
+    pub material: usize,
+}
+
+/// A metallic-roughness material (only the diffuse color and texture are
+/// drawn by the current shaders).
+#[derive(Clone, Debug, PartialEq)]
+pub struct Material {
+    pub name: String,
+    /// The diffuse or base color (`Kd`), which is also the color of the vertices.
+    pub diffuse: [f32; 3],
+    /// The diffuse or base color texture (`map_Kd`).
+    pub diffuse_texture: Option<TextureSource>,
+    pub metallic: f32,
+    pub roughness: f32,
+    /// The metalness (blue) and roughness (green) texture.
+    pub metallic_roughness_texture: Option<TextureSource>,
+    /// The tangent space normal texture (`norm` or `map_Bump`).
+    pub normal_texture: Option<TextureSource>,
+    pub emissive: [f32; 3],
+    pub emissive_texture: Option<TextureSource>,
+}
+
+impl Default for Material {
+    fn default() -> Self {
+        Self {
+            name: "default".into(),
+            diffuse: [1.0; 3],
+            diffuse_texture: None,
+            metallic: 0.0,
+            roughness: 1.0,
+            metallic_roughness_texture: None,
+            normal_texture: None,
+            emissive: [0.0; 3],
+            emissive_texture: None,
+        }
+    }
+}
+
+/// A node of a model's hierarchy and the meshes drawn with its transform.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Node {
+    pub name: String,
+    /// The index of the parent node (which comes before its children).
+    pub parent: Option<usize>,
+    /// The transform relative to the parent node.
+    pub transform: Mat4,
+    /// The transform relative to the model (which the meshes are drawn with).
+    pub world: Mat4,
+    /// The indices of the meshes in [`Model::meshes`].
+    pub meshes: Vec<usize>,
+}
+
+/// The (deduplicated) vertices and indices of a model and the meshes,
+/// materials and nodes they are drawn with.
+#[derive(Clone, Debug, Default)]
+pub struct Model {
+    pub vertices: Vec<Vertex>,
+    pub indices: Vec<u32>,
+    pub meshes: Vec<Mesh>,
+    pub materials: Vec<Material>,
+    pub nodes: Vec<Node>,
+}
+
+/// Loads an OBJ model and its materials.
+///
+/// A missing or invalid `.mtl` file is only a warning (every object then uses
+/// a white default material), as are objects without a material. Objects
+/// without normals get smooth normals computed from their triangles.
+pub fn load_obj(path: impl AsRef<Path>) -> Result<Model> {
+    let path = path.as_ref();
+    let options = tobj::LoadOptions { single_index: true, triangulate: true, ..Default::default() };
+    let (objects, materials) = tobj::load_obj(path, &options)
+        .with_context(|| format!("Failed to load model `{}`.", path.display()))?;
+
+    // Materials (with textures relative to the model)
+    let directory = path.parent().unwrap_or_else(|| Path::new(""));
+    let mut materials = match materials {
+        Ok(materials) => materials
+            .into_iter()
+            .map(|m| {
+                let texture = |file: &str| (!file.is_empty()).then(|| TextureSource::File(directory.join(file)));
+                Material {
+                    diffuse_texture: texture(&m.diffuse_texture),
+                    normal_texture: texture(&m.normal_texture),
+                    diffuse: m.diffuse,
+                    name: m.name,
+                    ..Default::default()
+                }
+            })
+            .collect(),
+        Err(e) => {
+            warn!("Failed to load the materials of `{}`: {}", path.display(), e);
+            vec![]
+        }
+    };
+
+    let mut default_material = None;
+    let mut model = Model::default();
+    let mut unique_vertices = HashMap::new();
+
+    for object in &objects {
+        let mesh = &object.mesh;
+        let material = match mesh.material_id.filter(|m| *m < materials.len()) {
+            Some(material) => material,
+            None => *default_material.get_or_insert_with(|| {
+                materials.push(Material::default());
+                materials.len() - 1
+            }),
+        };
+
+        let normals = if mesh.normals.is_empty() {
+            compute_normals(&mesh.positions, &mesh.indices)
+        } else {
+            mesh.normals.chunks_exact(3).map(|n| vec3(n[0], n[1], n[2])).collect()
+        };
+
+        let [r, g, b] = materials[material].diffuse;
+        let first_index = model.indices.len() as u32;
+
+        // Vertices / Indices
+        for index in &mesh.indices {
+            let index = *index as usize;
+            let tex_coord = match mesh.texcoords.get(2 * index..2 * index + 2) {
+                Some(t) => vec2(t[0], 1.0 - t[1]),
+                None => vec2(0.0, 0.0),
+            };
+
+            let vertex = Vertex {
+                pos: vec3(mesh.positions[3 * index], mesh.positions[3 * index + 1], mesh.positions[3 * index + 2]),
+                color: vec3(r, g, b),
+                tex_coord,
+                normal: normals[index],
+            };
+
+            let index = *unique_vertices.entry(vertex).or_insert_with(|| {
+                model.vertices.push(vertex);
+                model.vertices.len() - 1
+            });
+            model.indices.push(index as u32);
+        }
+
+        model.meshes.push(Mesh {
+            name: object.name.clone(),
+            first_index,
+            index_count: model.indices.len() as u32 - first_index,
+            material,
+        });
+    }
+
+    // Every object is drawn untransformed
+    model.nodes.push(Node {
+        name: path.file_stem().unwrap_or_default().to_string_lossy().into(),
+        parent: None,
+        transform: Mat4::identity(),
+        world: Mat4::identity(),
+        meshes: (0..model.meshes.len()).collect(),
+    });
+    model.materials = materials;
+
+    Ok(model)
+}
+
+/// The normals of each position, averaged over the triangles using it
+/// (weighted by their area).
+pub(crate) fn compute_normals(positions: &[f32], indices: &[u32]) -> Vec<Vec3> {
+    let position = |i: u32| {
+        let i = 3 * i as usize;
+        vec3(positions[i], positions[i + 1], positions[i + 2])
+    };
+
+    let mut normals = vec![Vec3::zero(); positions.len() / 3];
+    for triangle in indices.chunks_exact(3) {
+        let (a, b, c) = (position(triangle[0]), position(triangle[1]), position(triangle[2]));
+        let normal = (b - a).cross(c - a);
+        for index in triangle {
+            normals[*index as usize] += normal;
+        }
+    }
+
+    normals
+        .into_iter()
+        .map(|n| if n.magnitude2() > 0.0 { n.normalize() } else { n })
+        .collect()
+}
+
+/// Loads an OBJ model or glTF scene (`.gltf` or `.glb`) into the vertices,
+/// indices, meshes, materials and nodes of the app data.
+pub fn load_model(data: &mut AppData, path: &Path) -> Result<()> {
+    let model = match path.extension().and_then(|e| e.to_str()) {
+        Some(e) if e.eq_ignore_ascii_case("gltf") || e.eq_ignore_ascii_case("glb") => scene::load_gltf(path)?,
+        _ => load_obj(path)?,
+    };
+
+    data.vertices = model.vertices;
+    data.indices = model.indices;
+    data.meshes = model.meshes;
+    data.materials = model.materials;
+    data.nodes = model.nodes;
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+    use std::path::PathBuf;
+
+    const OBJ: &str = "\
+mtllib scene.mtl
+o loose
+v 0 0 1
+v 1 0 1
+v 0 1 1
+f 1 3 2
+o floor
+v 0 0 0
+v 1 0 0
+v 1 1 0
+v 0 1 0
+vt 0 0
+vt 1 0
+vt 1 1
+vt 0 1
+vn 0 0 1
+usemtl wood
+f 4/1/1 5/2/1 6/3/1 7/4/1
+o marker
+usemtl red
+f 1 2 3
+";
+
+    const MTL: &str = "\
+newmtl wood
+Kd 1 1 1
+map_Kd textures/wood.png
+newmtl red
+Kd 1 0 0
+";
+
+    /// Writes the files of a model to a new directory in the temporary directory.
+    fn write_model(name: &str, files: &[(&str, &str)]) -> Result<PathBuf> {
+        let directory = std::env::temp_dir().join(format!("model_{}_{}", name, std::process::id()));
+        fs::create_dir_all(&directory)?;
+        for (file, contents) in files {
+            fs::write(directory.join(file), contents)?;
+        }
+        Ok(directory)
+    }
+
+    #[test]
+    fn objects_keep_their_index_ranges_and_materials() -> Result<()> {
+        let directory = write_model("scene", &[("scene.obj", OBJ), ("scene.mtl", MTL)])?;
+        let model = load_obj(directory.join("scene.obj"))?;
+        fs::remove_dir_all(&directory)?;
+
+        let ranges = model.meshes.iter().map(|m| (m.name.as_str(), m.first_index, m.index_count)).collect::<Vec<_>>();
+        assert_eq!(ranges, [("loose", 0, 3), ("floor", 3, 6), ("marker", 9, 3)]);
+        assert_eq!(model.indices.len(), 12);
+        assert_eq!(model.nodes.len(), 1);
+        assert_eq!((model.nodes[0].name.as_str(), &model.nodes[0].meshes[..]), ("scene", &[0, 1, 2][..]));
+
+        let names = model.meshes.iter().map(|m| model.materials[m.material].name.as_str()).collect::<Vec<_>>();
+        assert_eq!(names, ["default", "wood", "red"]);
+        assert_eq!(model.materials[0].diffuse_texture, Some(TextureSource::File(directory.join("textures/wood.png"))));
+        assert_eq!(model.materials[1].diffuse_texture, None);
+
+        // The floor's quad shares two of its four vertices between its triangles
+        let floor = &model.indices[3..9];
+        assert_eq!(floor.iter().max(), Some(&6));
+        let vertex = model.vertices[floor[2] as usize];
+        assert_eq!((vertex.pos, vertex.tex_coord, vertex.normal), (vec3(1.0, 1.0, 0.0), vec2(1.0, 0.0), vec3(0.0, 0.0, 1.0)));
+        assert_eq!(model.vertices[model.indices[9] as usize].color, vec3(1.0, 0.0, 0.0));
+
+        Ok(())
+    }
+
+    #[test]
+    fn missing_normals_are_computed() -> Result<()> {
+        let directory = write_model("normals", &[("scene.obj", OBJ), ("scene.mtl", MTL)])?;
+        let model = load_obj(directory.join("scene.obj"))?;
+        fs::remove_dir_all(&directory)?;
+
+        // The marker faces up and the loose triangle (wound the other way) down
+        let marker = model.vertices[model.indices[9] as usize];
+        let loose = model.vertices[model.indices[0] as usize];
+        assert_eq!(marker.pos, loose.pos);
+        assert!((marker.normal - vec3(0.0, 0.0, 1.0)).magnitude() < 1e-6, "{:?}", marker.normal);
+        assert!((loose.normal - vec3(0.0, 0.0, -1.0)).magnitude() < 1e-6, "{:?}", loose.normal);
+
+        Ok(())
+    }
+
+    #[test]
+    fn a_missing_mtl_falls_back_to_the_default_material() -> Result<()> {
+        let directory = write_model("no_mtl", &[("scene.obj", OBJ)])?;
+        let model = load_obj(directory.join("scene.obj"))?;
+        fs::remove_dir_all(&directory)?;
+
+        assert_eq!(model.materials, [Material::default()]);
+        assert!(model.meshes.iter().all(|m| m.material == 0));
+
+        Ok(())
+    }
+}
@@ -0,0 +1,123 @@
+//================================================
+// Offscreen
+//================================================
+#![allow(unused_variables)]
+//! Headless rendering: offscreen render targets stand in for the swapchain
+//! images, and the app renders into them with its usual render function
+//! (without acquiring or presenting) before a frame is read back to CPU memory
+//! (e.g. for golden images).
+//!
+//! The apps built on [`AppData`] use [`create_render_targets`] and
+//! [`read_render_target`]. The earlier chapters, which keep their own app data
+//! with plain handles, pass their allocator (or [`Transfer`]) to
+//! [`create_render_target_images`] and [`read_render_target_image`] instead.
+
+use crate::app_data::AppData;
+use crate::commands::Transfer;
+use crate::images::{self, ImageInfo};
+use crate::memory::Allocator;
+use crate::owned::Image;
+use crate::swapchain;
+
+use std::rc::Rc;
+
+use anyhow::{anyhow, Result};
+use vulkanalia::prelude::v1_0::*;
+
+/// The format of the offscreen render targets (read back as RGBA8 as-is).
+pub const RENDER_TARGET_FORMAT: vk::Format = vk::Format::R8G8B8A8_SRGB;
+
+/// The usage of the offscreen render targets (rendered to and then read back).
+const RENDER_TARGET_USAGE: vk::ImageUsageFlags = vk::ImageUsageFlags::from_bits_truncate(
+    vk::ImageUsageFlags::COLOR_ATTACHMENT.bits() | vk::ImageUsageFlags::TRANSFER_SRC.bits(),
+);
+
+/// A frame read back from a render target as tightly packed RGBA8 pixels.
+#[derive(Clone, Debug)]
+pub struct Frame {
+    pub width:  u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+/// Creates the color render targets used in place of a swapchain when headless.
+pub unsafe fn create_render_targets(
+    instance: &Instance,
+    device: &Rc<Device>,
+    data: &mut AppData,
+    extent: vk::Extent2D,
+    count: usize,
+) -> Result<()> {
+    data.swapchain_format = RENDER_TARGET_FORMAT;
+    data.swapchain_extent = extent;
+
+    for image in create_render_target_images(device, &data.allocator, extent, count)? {
+        data.swapchain_images.push(*image);
+        data.render_targets.push(image);
+    }
+
+    // Image Views
+    swapchain::create_swapchain_image_views(device, data)?;
+
+    Ok(())
+}
+
+/// Copies a rendered target (in `TRANSFER_SRC_OPTIMAL`) back to CPU memory.
+pub unsafe fn read_render_target(
+    instance: &Instance,
+    device: &Rc<Device>,
+    data: &AppData,
+    image_index: usize,
+) -> Result<Frame> {
+    let image = data.swapchain_images[image_index];
+    read_render_target_image(device, &Transfer::from(data), image, data.swapchain_extent)
+}
+
+/// Creates `count` color render targets in memory from `allocator`.
+pub unsafe fn create_render_target_images(
+    device: &Rc<Device>,
+    allocator: &Rc<Allocator>,
+    extent: vk::Extent2D,
+    count: usize,
+) -> Result<Vec<Image>> {
+    if count == 0 || extent.width == 0 || extent.height == 0 {
+        return Err(anyhow!("Invalid render target configuration ({}x{}, {} targets).", extent.width, extent.height, count));
+    }
+
+    let info = ImageInfo {
+        extent,
+        mip_levels: 1,
+        layers: 1,
+        flags: vk::ImageCreateFlags::empty(),
+        samples: vk::SampleCountFlags::_1,
+        format: RENDER_TARGET_FORMAT,
+        tiling: vk::ImageTiling::OPTIMAL,
+        usage: RENDER_TARGET_USAGE,
+    };
+
+    (0..count)
+        .map(|_| images::allocate_image(device, allocator, &info, vk::MemoryPropertyFlags::DEVICE_LOCAL))
+        .collect()
+}
+
+/// Copies a rendered target (in `TRANSFER_SRC_OPTIMAL`) of the given size back
+/// to CPU memory with the command pool, queue and allocator of `transfer`.
+pub unsafe fn read_render_target_image(
+    device: &Rc<Device>,
+    transfer: &Transfer,
+    image: vk::Image,
+    extent: vk::Extent2D,
+) -> Result<Frame> {
+    let vk::Extent2D { width, height } = extent;
+    let layout = vk::ImageLayout::TRANSFER_SRC_OPTIMAL;
+    let pixels = images::read_image_with(device, transfer, image, layout, width, height)?;
+
+    Ok(Frame { width, height, pixels })
+}
+
+/// Returns the render target a headless frame renders into after the one at
+/// `image_index` (there is no swapchain to acquire from, so the frames cycle
+/// through the targets).
+pub fn next_render_target(image_index: usize, count: usize) -> usize {
+    (image_index + 1) % count
+}
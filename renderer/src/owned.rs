@@ -0,0 +1,159 @@
+//================================================
+// Owned
+//================================================
+//! Vulkan handles that destroy themselves when they are dropped.
+//!
+//! The creating functions of the other modules return [`Owned`] handles (and
+//! [`Buffer`]s / [`Image`]s that also own the memory bound to them), so that
+//! replacing a field of `AppData` releases the previous handle and dropping
+//! `AppData` releases everything in the order its fields are declared.
+
+use crate::memory::{Allocation, Allocator};
+use crate::tracker;
+
+use std::fmt;
+use std::mem;
+use std::ops::Deref;
+use std::panic::Location;
+use std::rc::Rc;
+
+use vulkanalia::prelude::v1_0::*;
+use vulkanalia::vk::KhrSwapchainExtension;
+
+/// A handle that is destroyed with the logical device it was created by.
+pub trait Destroy: vk::Handle<Repr = u64> {
+    unsafe fn destroy(self, device: &Device);
+}
+
+macro_rules! destroy {
+    ($($handle:ty => $destroy:ident),* $(,)?) => {
+        $(impl Destroy for $handle {
+            unsafe fn destroy(self, device: &Device) {
+                device.$destroy(self, None);
+            }
+        })*
+    };
+}
+
+destroy! {
+    vk::Buffer => destroy_buffer,
+    vk::Image => destroy_image,
+    vk::ImageView => destroy_image_view,
+    vk::Sampler => destroy_sampler,
+    vk::ShaderModule => destroy_shader_module,
+    vk::RenderPass => destroy_render_pass,
+    vk::PipelineLayout => destroy_pipeline_layout,
+    vk::Pipeline => destroy_pipeline,
+    vk::PipelineCache => destroy_pipeline_cache,
+    vk::Framebuffer => destroy_framebuffer,
+    vk::DescriptorSetLayout => destroy_descriptor_set_layout,
+    vk::DescriptorPool => destroy_descriptor_pool,
+    vk::CommandPool => destroy_command_pool,
+    vk::Semaphore => destroy_semaphore,
+    vk::Fence => destroy_fence,
+    vk::QueryPool => destroy_query_pool,
+    vk::SwapchainKHR => destroy_swapchain_khr,
+}
+
+/// A handle owned by whoever holds this, destroyed when dropped.
+///
+/// The default value owns nothing (a null handle).
+pub struct Owned<T: Destroy> {
+    handle: T,
+    device: Option<Rc<Device>>,
+}
+
+impl<T: Destroy> Owned<T> {
+    /// Takes ownership of a handle created by `device` (tracked as created by
+    /// the caller in debug builds).
+    #[track_caller]
+    pub fn new(device: &Rc<Device>, handle: T) -> Self {
+        tracker::track(handle, Location::caller());
+        Self { handle, device: Some(device.clone()) }
+    }
+}
+
+impl<T: Destroy> Default for Owned<T> {
+    fn default() -> Self {
+        Self { handle: T::default(), device: None }
+    }
+}
+
+impl<T: Destroy> Deref for Owned<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.handle
+    }
+}
+
+impl<T: Destroy> Drop for Owned<T> {
+    fn drop(&mut self) {
+        if let Some(device) = self.device.take() {
+            if tracker::untrack(self.handle) {
+                unsafe { self.handle.destroy(&device) };
+            }
+        }
+    }
+}
+
+impl<T: Destroy> fmt::Debug for Owned<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.handle.fmt(f)
+    }
+}
+
+/// A buffer or image that owns the memory bound to it.
+#[derive(Default)]
+pub struct Bound<T: Destroy> {
+    handle: Owned<T>,
+    memory: Allocation,
+    allocator: Option<Rc<Allocator>>,
+}
+
+/// A buffer and its memory.
+pub type Buffer = Bound<vk::Buffer>;
+
+/// An image and its memory.
+pub type Image = Bound<vk::Image>;
+
+impl<T: Destroy> Bound<T> {
+    /// Takes ownership of a handle and the memory `allocator` allocated for it.
+    pub fn new(handle: Owned<T>, allocator: &Rc<Allocator>, memory: Allocation) -> Self {
+        Self { handle, memory, allocator: Some(allocator.clone()) }
+    }
+
+    /// The memory bound to the handle (see [`Allocation::mapped`]).
+    pub fn memory(&self) -> &Allocation {
+        &self.memory
+    }
+}
+
+impl<T: Destroy> Deref for Bound<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.handle
+    }
+}
+
+impl<T: Destroy> Drop for Bound<T> {
+    fn drop(&mut self) {
+        // The handle is destroyed before the memory it is bound to is released
+        let device = self.handle.device.clone();
+        drop(mem::take(&mut self.handle));
+
+        if let (Some(device), Some(allocator)) = (device, self.allocator.take()) {
+            unsafe { allocator.free(&device, self.memory) };
+        }
+    }
+}
+
+impl<T: Destroy> fmt::Debug for Bound<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Bound")
+            .field("handle", &self.handle)
+            .field("memory", &self.memory)
+            .finish()
+    }
+}
@@ -0,0 +1,194 @@
+//================================================
+// Physical Device
+//================================================
+#![allow(unused_variables)]
+
+use crate::app_data::AppData;
+use crate::structs::{QueueFamilyIndices, SwapchainSupport};
+use crate::error::SuitabilityError;
+use crate::memory::Allocator;
+use crate::settings;
+
+use std::collections::HashSet;
+use std::rc::Rc;
+
+use anyhow::{anyhow, Result};
+use vulkanalia::prelude::v1_0::*;
+use log::*;
+
+/// The required device extensions.
+pub const DEVICE_EXTENSIONS: &[vk::ExtensionName] = &[vk::KHR_SWAPCHAIN_EXTENSION.name];
+
+pub unsafe fn pick_physical_device(instance: &Instance, data: &mut AppData) -> Result<()> {
+    for physical_device in instance.enumerate_physical_devices()? {
+        let properties = instance.get_physical_device_properties(physical_device);
+
+        if let Err(error) = check_physical_device(instance, data, physical_device) {
+            warn!("Skipping physical device (`{}`): {}", properties.device_name, error);
+        } else {
+            info!("Selected physical device (`{}`).", properties.device_name);
+            data.physical_device = physical_device;
+            data.msaa_samples = get_msaa_samples(instance, data);
+            data.allocator = Rc::new(Allocator::new(instance, physical_device));
+            return Ok(());
+        }
+    }
+
+    Err(anyhow!("Failed to find suitable physical device."))
+}
+
+unsafe fn check_physical_device(
+    instance: &Instance,
+    data: &AppData,
+    physical_device: vk::PhysicalDevice,
+) -> Result<()> {
+    // get { graphics, present }
+    QueueFamilyIndices::get(instance, data, physical_device)?;
+
+    // headless apps render into offscreen images, so no swapchain is required
+    if !data.headless {
+        check_physical_device_swapchain(instance, data, physical_device)?;
+    }
+
+    let features = instance.get_physical_device_features(physical_device);
+    if features.sampler_anisotropy != vk::TRUE {
+        return Err(anyhow!(SuitabilityError("No sampler anisotropy.")));
+    }
+
+    Ok(())
+}
+
+unsafe fn check_physical_device_swapchain(
+    instance: &Instance,
+    data: &AppData,
+    physical_device: vk::PhysicalDevice,
+) -> Result<()> {
+    // check swapchain support
+    check_physical_device_extensions(instance, physical_device)?;
+
+    // query details of swapchain support
+    // { capabilities, formats, present_modes }
+    let support = SwapchainSupport::get(instance, data, physical_device)?;
+    if support.formats.is_empty() || support.present_modes.is_empty() {
+        return Err(anyhow!(SuitabilityError("Insufficient swapchain support.")));
+    }
+
+    Ok(())
+}
+
+unsafe fn check_physical_device_extensions(instance: &Instance, physical_device: vk::PhysicalDevice) -> Result<()> {
+    let extensions = instance
+        .enumerate_device_extension_properties(physical_device, None)?
+        .iter()
+        .map(|e| e.extension_name)
+        .collect::<HashSet<_>>();
+    if DEVICE_EXTENSIONS.iter().all(|e| extensions.contains(e)) {
+        Ok(())
+    } else {
+        Err(anyhow!(SuitabilityError("Missing required device extensions.")))
+    }
+}
+
+/// Returns the requested MSAA sample count (see [`Settings::msaa_samples`]),
+/// or the highest supported one below it.
+unsafe fn get_msaa_samples(instance: &Instance, data: &AppData) -> vk::SampleCountFlags {
+    let supported = get_supported_msaa_samples(instance, data);
+    let requested = data.settings.msaa_samples.map(settings::sample_count_flags);
+    msaa_samples(requested, supported)
+}
+
+/// Returns `requested` if it is `supported`, or else the highest supported
+/// sample count below it (or the highest one when none is requested).
+fn msaa_samples(requested: Option<vk::SampleCountFlags>, supported: vk::SampleCountFlags) -> vk::SampleCountFlags {
+    let requested = match requested {
+        Some(requested) if supported.contains(requested) => return requested,
+        Some(requested) => requested,
+        None => return max_msaa_samples(supported),
+    };
+
+    let below = vk::SampleCountFlags::from_bits_truncate(requested.bits().saturating_sub(1));
+    let samples = max_msaa_samples(supported & below);
+    warn!("{:?} MSAA requested but the device only supports {:?}, falling back to {:?}.", requested, supported, samples);
+    samples
+}
+
+/// Returns the MSAA sample counts the device supports for both color and depth
+/// attachments.
+pub unsafe fn get_supported_msaa_samples(instance: &Instance, data: &AppData) -> vk::SampleCountFlags {
+    let properties = instance.get_physical_device_properties(data.physical_device);
+    properties.limits.framebuffer_color_sample_counts
+        & properties.limits.framebuffer_depth_sample_counts
+        | vk::SampleCountFlags::_1
+}
+
+/// Returns the highest MSAA sample count the device supports for both color
+/// and depth attachments.
+pub unsafe fn get_max_msaa_samples(instance: &Instance, data: &AppData) -> vk::SampleCountFlags {
+    max_msaa_samples(get_supported_msaa_samples(instance, data))
+}
+
+/// Returns the highest sample count in `counts`.
+fn max_msaa_samples(counts: vk::SampleCountFlags) -> vk::SampleCountFlags {
+    [
+        vk::SampleCountFlags::_64,
+        vk::SampleCountFlags::_32,
+        vk::SampleCountFlags::_16,
+        vk::SampleCountFlags::_8,
+        vk::SampleCountFlags::_4,
+        vk::SampleCountFlags::_2,
+    ]
+    .iter()
+    .cloned()
+    .find(|c| counts.contains(*c))
+    .unwrap_or(vk::SampleCountFlags::_1)
+}
+
+/// Returns the next `supported` sample count after `current` (skipping the
+/// counts the device does not support, and wrapping around to no
+/// multisampling after the highest one).
+pub fn next_msaa_samples(current: vk::SampleCountFlags, supported: vk::SampleCountFlags) -> vk::SampleCountFlags {
+    let mut next = current;
+    loop {
+        next = vk::SampleCountFlags::from_bits_truncate(next.bits() << 1);
+        if next.is_empty() {
+            return vk::SampleCountFlags::_1;
+        } else if supported.contains(next) {
+            return next;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn cycle(supported: vk::SampleCountFlags, steps: usize) -> Vec<u32> {
+        let mut samples = vk::SampleCountFlags::_1;
+        let mut cycle = vec![];
+        for _ in 0..steps {
+            samples = next_msaa_samples(samples, supported);
+            cycle.push(samples.bits());
+        }
+        cycle
+    }
+
+    #[test]
+    fn msaa_samples_cycle_up_to_the_maximum() {
+        let supported = vk::SampleCountFlags::from_bits_truncate(0b1111);
+        assert_eq!(cycle(supported, 5), vec![2, 4, 8, 1, 2]);
+
+        assert_eq!(next_msaa_samples(vk::SampleCountFlags::_1, vk::SampleCountFlags::_1), vk::SampleCountFlags::_1);
+        assert_eq!(next_msaa_samples(vk::SampleCountFlags::_64, vk::SampleCountFlags::all()), vk::SampleCountFlags::_1);
+    }
+
+    #[test]
+    fn unsupported_msaa_samples_are_skipped() {
+        let supported = vk::SampleCountFlags::_1 | vk::SampleCountFlags::_4;
+        assert_eq!(cycle(supported, 4), vec![4, 1, 4, 1]);
+
+        assert_eq!(msaa_samples(None, supported), vk::SampleCountFlags::_4);
+        assert_eq!(msaa_samples(Some(vk::SampleCountFlags::_4), supported), vk::SampleCountFlags::_4);
+        assert_eq!(msaa_samples(Some(vk::SampleCountFlags::_2), supported), vk::SampleCountFlags::_1);
+        assert_eq!(msaa_samples(Some(vk::SampleCountFlags::_8), supported), vk::SampleCountFlags::_4);
+    }
+}
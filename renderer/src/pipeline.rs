@@ -0,0 +1,431 @@
+//================================================
+// Pipeline
+//================================================
+#![allow(unused_variables)]
+
+use crate::app_data::AppData;
+use crate::assets::Assets;
+use crate::structs::Vertex;
+use crate::{color_objects, depth_objects, framebuffers, sync_objects};
+use crate::owned::Owned;
+use crate::reflect::{self, Module, PushConstant};
+use crate::shaders::ShaderStage;
+
+use std::path::Path;
+use std::rc::Rc;
+
+use anyhow::{anyhow, Result};
+use vulkanalia::bytecode::Bytecode;
+use vulkanalia::prelude::v1_0::*;
+
+/// Describes the pipeline of a chapter on top of the shared fixed functions.
+#[derive(Copy, Clone, Debug)]
+pub struct PipelineInfo<'a> {
+    /// The directory with the `shader.vert` and `shader.frag` sources, which
+    /// are found in the asset roots (see [`crate::assets`]) and compiled at
+    /// runtime (see [`crate::shaders`]).
+    pub sources: &'a str,
+    /// The prebuilt SPIR-V bytecode of the vertex shader.
+    pub vert: &'a [u8],
+    /// The prebuilt SPIR-V bytecode of the fragment shader.
+    pub frag: &'a [u8],
+    /// The file the pipeline cache is kept in between runs, next to the assets
+    /// (see [`crate::pipeline_cache`] and [`Assets::output`]).
+    pub cache: &'a str,
+    /// Whether fragments are alpha blended over the color attachment.
+    pub blend: bool,
+}
+
+/// The bytecode of the shaders of a pipeline and their reflected interfaces.
+#[derive(Clone, Debug)]
+pub struct Shaders {
+    pub vert: Vec<u8>,
+    pub frag: Vec<u8>,
+    pub modules: [Module; 2],
+}
+
+/// Loads (see [`crate::shaders::ShaderCache::load`]) and reflects the shaders
+/// of a pipeline.
+pub fn load_shaders(data: &mut AppData, pipeline: &PipelineInfo) -> Result<Shaders> {
+    // Sources that are in no asset root fall back to the prebuilt bytecode
+    let assets = Assets::new(&data.settings);
+    let source = |name: &str| {
+        let path = Path::new(pipeline.sources).join(name);
+        assets.resolve(&path).unwrap_or(path)
+    };
+
+    let vert = data.shaders.load(&source("shader.vert"), ShaderStage::Vertex, pipeline.vert);
+    let frag = data.shaders.load(&source("shader.frag"), ShaderStage::Fragment, pipeline.frag);
+    let modules = [reflect::reflect(&vert)?, reflect::reflect(&frag)?];
+    Ok(Shaders { vert, frag, modules })
+}
+
+/// Creates the render pass, which resolves a multisampled color attachment
+/// into the swapchain image unless `data.msaa_samples` is 1.
+pub unsafe fn create_render_pass(instance: &Instance, device: &Rc<Device>, data: &mut AppData) -> Result<()> {
+    data.render_pass = build_render_pass(instance, device, data, data.msaa_samples)?;
+
+    Ok(())
+}
+
+/// Builds a render pass for color and depth attachments with `samples`
+/// samples (see [`create_render_pass`]).
+pub unsafe fn build_render_pass(
+    instance: &Instance,
+    device: &Rc<Device>,
+    data: &AppData,
+    samples: vk::SampleCountFlags,
+) -> Result<Owned<vk::RenderPass>> {
+    let multisampled = samples != vk::SampleCountFlags::_1;
+
+    // Offscreen render targets are read back instead of presented
+    let final_layout = if data.headless {
+        vk::ImageLayout::TRANSFER_SRC_OPTIMAL
+    } else {
+        vk::ImageLayout::PRESENT_SRC_KHR
+    };
+
+    // Attachments
+    let color_attachment = vk::AttachmentDescription::builder()
+        .format(data.swapchain_format)
+        .samples(samples)
+        .load_op(vk::AttachmentLoadOp::CLEAR)
+        .store_op(vk::AttachmentStoreOp::STORE)
+        .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
+        .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
+        .initial_layout(vk::ImageLayout::UNDEFINED)
+        .final_layout(if multisampled { vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL } else { final_layout });
+
+    let depth_stencil_attachment = vk::AttachmentDescription::builder()
+        .format(depth_objects::get_depth_format(instance, data)?)
+        .samples(samples)
+        .load_op(vk::AttachmentLoadOp::CLEAR)
+        .store_op(vk::AttachmentStoreOp::DONT_CARE)
+        .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
+        .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
+        .initial_layout(vk::ImageLayout::UNDEFINED)
+        .final_layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL);
+
+    let color_resolve_attachment = vk::AttachmentDescription::builder()
+        .format(data.swapchain_format)
+        .samples(vk::SampleCountFlags::_1)
+        .load_op(vk::AttachmentLoadOp::DONT_CARE)
+        .store_op(vk::AttachmentStoreOp::STORE)
+        .stencil_load_op(vk::AttachmentLoadOp::DONT_CARE)
+        .stencil_store_op(vk::AttachmentStoreOp::DONT_CARE)
+        .initial_layout(vk::ImageLayout::UNDEFINED)
+        .final_layout(final_layout);
+
+    // Subpasses
+    let color_attachment_ref = vk::AttachmentReference::builder()
+        .attachment(0)
+        .layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL);
+
+    let depth_stencil_attachment_ref = vk::AttachmentReference::builder()
+        .attachment(1)
+        .layout(vk::ImageLayout::DEPTH_STENCIL_ATTACHMENT_OPTIMAL);
+
+    let color_resolve_attachment_ref = vk::AttachmentReference::builder()
+        .attachment(2)
+        .layout(vk::ImageLayout::COLOR_ATTACHMENT_OPTIMAL);
+
+    let color_attachments = &[color_attachment_ref];
+    let resolve_attachments = &[color_resolve_attachment_ref];
+    let mut subpass = vk::SubpassDescription::builder()
+        .pipeline_bind_point(vk::PipelineBindPoint::GRAPHICS)
+        .color_attachments(color_attachments)
+        .depth_stencil_attachment(&depth_stencil_attachment_ref);
+
+    if multisampled {
+        subpass = subpass.resolve_attachments(resolve_attachments);
+    }
+
+    // Dependencies
+    let dependency = vk::SubpassDependency::builder()
+        .src_subpass(vk::SUBPASS_EXTERNAL)
+        .dst_subpass(0)
+        .src_stage_mask(
+            vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT | vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS)
+        .src_access_mask(vk::AccessFlags::empty())
+        .dst_stage_mask(
+            vk::PipelineStageFlags::COLOR_ATTACHMENT_OUTPUT | vk::PipelineStageFlags::EARLY_FRAGMENT_TESTS)
+        .dst_access_mask(
+            vk::AccessFlags::COLOR_ATTACHMENT_WRITE | vk::AccessFlags::DEPTH_STENCIL_ATTACHMENT_WRITE);
+
+    // Create
+    let attachments = if multisampled {
+        vec![color_attachment, depth_stencil_attachment, color_resolve_attachment]
+    } else {
+        vec![color_attachment, depth_stencil_attachment]
+    };
+    let subpasses = &[subpass];
+    let dependencies = &[dependency];
+    let info = vk::RenderPassCreateInfo::builder()
+        .attachments(&attachments)
+        .subpasses(subpasses)
+        .dependencies(dependencies);
+
+    Ok(Owned::new(device, device.create_render_pass(&info, None)?))
+}
+
+/// Switches the render pass and pipeline to another MSAA sample count (which
+/// has to be supported by the device), rebuilding the attachments and
+/// framebuffers that depend on it.
+///
+/// Everything is built before anything is replaced, so on error the previous
+/// sample count is kept.
+pub unsafe fn set_msaa_samples(
+    instance: &Instance,
+    device: &Rc<Device>,
+    data: &mut AppData,
+    samples: vk::SampleCountFlags,
+    pipeline: &PipelineInfo,
+) -> Result<()> {
+    sync_objects::wait_for_frames(device, data)?;
+
+    let render_pass = build_render_pass(instance, device, data, samples)?;
+    let (pipeline, pipeline_layout, push_constants) = build_pipeline(device, data, pipeline, *render_pass, samples)?;
+    let (color_image, color_image_view) = color_objects::build_color_objects(instance, device, data, samples)?;
+    let (depth_image, depth_image_view) = depth_objects::build_depth_objects(instance, device, data, samples)?;
+    let framebuffers = framebuffers::build_framebuffers(device, data, *render_pass, *color_image_view, *depth_image_view)?;
+
+    data.msaa_samples = samples;
+    data.framebuffers = framebuffers;
+    data.pipeline = pipeline;
+    data.pipeline_layout = pipeline_layout;
+    data.push_constants = push_constants;
+    data.render_pass = render_pass;
+    data.color_image_view = color_image_view;
+    data.color_image = color_image;
+    data.depth_image_view = depth_image_view;
+    data.depth_image = depth_image;
+
+    Ok(())
+}
+
+/// Creates the pipeline (and its layout) for the shaders of `pipeline`, whose
+/// descriptor bindings have to match the descriptor set layout and whose
+/// vertex inputs have to match [`Vertex`].
+///
+/// Nothing is replaced until the new pipeline has been created, so on error
+/// the previous pipeline and layout are left in place.
+pub unsafe fn create_pipeline(device: &Rc<Device>, data: &mut AppData, pipeline: &PipelineInfo) -> Result<()> {
+    let render_pass = *data.render_pass;
+    let (pipeline, pipeline_layout, push_constants) = build_pipeline(device, data, pipeline, render_pass, data.msaa_samples)?;
+
+    data.pipeline = pipeline;
+    data.pipeline_layout = pipeline_layout;
+    data.push_constants = push_constants;
+
+    Ok(())
+}
+
+/// Builds a pipeline (and its layout) for `render_pass`, whose attachments
+/// have `samples` samples (see [`create_pipeline`]), returning it with the
+/// push constants of its shaders.
+pub unsafe fn build_pipeline(
+    device: &Rc<Device>,
+    data: &mut AppData,
+    pipeline: &PipelineInfo,
+    render_pass: vk::RenderPass,
+    samples: vk::SampleCountFlags,
+) -> Result<(Owned<vk::Pipeline>, Owned<vk::PipelineLayout>, Vec<PushConstant>)> {
+    // --------------------------------------------------
+    // Shader -> Reflection
+    // --------------------------------------------------
+    let shaders = load_shaders(data, pipeline)?;
+
+    let bindings = reflect::descriptor_set_layout_bindings(&shaders.modules)?;
+    let key = |b: &vk::DescriptorSetLayoutBinding| (b.binding, b.descriptor_type, b.descriptor_count, b.stage_flags);
+    if !bindings.iter().map(key).eq(data.descriptor_bindings.iter().map(key)) {
+        return Err(anyhow!(
+            "The descriptor bindings of the shaders in `{}` no longer match the descriptor set layout.",
+            pipeline.sources,
+        ));
+    }
+
+    let attribute_descriptions = reflect::vertex_attribute_descriptions(
+        &shaders.modules[0],
+        &Vertex::attribute_descriptions(),
+    )?;
+    let push_constant_ranges = reflect::push_constant_ranges(&shaders.modules);
+    let push_constants = reflect::push_constants(&shaders.modules)?;
+
+    // --------------------------------------------------
+    // Shader -> Shader module -> Shader stage
+    // --------------------------------------------------
+    let vert_shader_module = create_shader_module(device, &shaders.vert)?;
+    let frag_shader_module = create_shader_module(device, &shaders.frag)?;
+
+    let vert_stage = vk::PipelineShaderStageCreateInfo::builder()
+        .stage(vk::ShaderStageFlags::VERTEX)
+        .module(*vert_shader_module)
+        .name(b"main\0");
+
+    let frag_stage = vk::PipelineShaderStageCreateInfo::builder()
+        .stage(vk::ShaderStageFlags::FRAGMENT)
+        .module(*frag_shader_module)
+        .name(b"main\0");
+
+    // ------------------------------------------------
+    // Fixed functions
+    // ------------------------------------------------
+    // Vertex Input State
+    let binding_descriptions = &[Vertex::binding_description()];
+    let vertex_input_state = vk::PipelineVertexInputStateCreateInfo::builder()
+        .vertex_binding_descriptions(binding_descriptions)
+        .vertex_attribute_descriptions(&attribute_descriptions);
+
+    // Input Assembly State
+    let input_assembly_state = vk::PipelineInputAssemblyStateCreateInfo::builder()
+        .topology(vk::PrimitiveTopology::TRIANGLE_LIST)
+        .primitive_restart_enable(false);
+
+    // Viewport State (set when recording, see `set_viewport`)
+    let viewport_state = vk::PipelineViewportStateCreateInfo::builder()
+        .viewport_count(1)
+        .scissor_count(1);
+
+    // Rasterization State
+    let rasterization_state = vk::PipelineRasterizationStateCreateInfo::builder()
+        .depth_clamp_enable(false)
+        .rasterizer_discard_enable(false)
+        .polygon_mode(vk::PolygonMode::FILL)
+        .line_width(1.0)
+        .cull_mode(vk::CullModeFlags::BACK)
+        .front_face(vk::FrontFace::COUNTER_CLOCKWISE)
+        .depth_bias_enable(false);
+
+    // Multisample State
+    let multisample_state = vk::PipelineMultisampleStateCreateInfo::builder()
+        .sample_shading_enable(samples != vk::SampleCountFlags::_1)
+        .min_sample_shading(0.2)
+        .rasterization_samples(samples);
+
+    // Depth Stencil State
+    let depth_stencil_state = vk::PipelineDepthStencilStateCreateInfo::builder()
+        .depth_test_enable(true)
+        .depth_write_enable(true)
+        .depth_compare_op(vk::CompareOp::LESS)
+        .depth_bounds_test_enable(false)
+        .stencil_test_enable(false);
+
+    // Color Blend State
+    let attachment = vk::PipelineColorBlendAttachmentState::builder()
+        .color_write_mask(vk::ColorComponentFlags::all())
+        .blend_enable(pipeline.blend)
+        .src_color_blend_factor(vk::BlendFactor::SRC_ALPHA)
+        .dst_color_blend_factor(vk::BlendFactor::ONE_MINUS_SRC_ALPHA)
+        .color_blend_op(vk::BlendOp::ADD)
+        .src_alpha_blend_factor(vk::BlendFactor::ONE)
+        .dst_alpha_blend_factor(vk::BlendFactor::ZERO)
+        .alpha_blend_op(vk::BlendOp::ADD);
+
+    let attachments = &[attachment];
+    let color_blend_state = vk::PipelineColorBlendStateCreateInfo::builder()
+        .logic_op_enable(false)
+        .logic_op(vk::LogicOp::COPY)
+        .attachments(attachments)
+        .blend_constants([0.0, 0.0, 0.0, 0.0]);
+
+    // Dynamic State
+    let dynamic_states = &[vk::DynamicState::VIEWPORT, vk::DynamicState::SCISSOR];
+    let dynamic_state = vk::PipelineDynamicStateCreateInfo::builder()
+        .dynamic_states(dynamic_states);
+
+    // ------------------------------------------------ 
+    // Pipeline Layout
+    // ------------------------------------------------
+    let set_layouts = &[*data.descriptor_set_layout];
+
+    let layout_info = vk::PipelineLayoutCreateInfo::builder()
+        .set_layouts(set_layouts)
+        .push_constant_ranges(&push_constant_ranges);
+
+    let pipeline_layout = Owned::new(device, device.create_pipeline_layout(&layout_info, None)?);
+
+    // ------------------------------------------------ 
+    // Create
+    // ------------------------------------------------
+    let stages = &[vert_stage, frag_stage];
+    let info = vk::GraphicsPipelineCreateInfo::builder()
+        .stages(stages)
+        .vertex_input_state(&vertex_input_state)
+        .input_assembly_state(&input_assembly_state)
+        .viewport_state(&viewport_state)
+        .rasterization_state(&rasterization_state)
+        .multisample_state(&multisample_state)
+        .depth_stencil_state(&depth_stencil_state)
+        .color_blend_state(&color_blend_state)
+        .dynamic_state(&dynamic_state)
+        .layout(*pipeline_layout)
+        .render_pass(render_pass)
+        .subpass(0);
+
+    let pipeline = device
+        .create_graphics_pipelines(*data.pipeline_cache, &[info], None)?
+        .0[0];
+
+    // The shader modules are destroyed when they go out of scope (and the new
+    // layout too if the pipeline could not be created)
+    Ok((Owned::new(device, pipeline), pipeline_layout, push_constants))
+}
+
+/// Pushes the value of the push constant block member named `name` (in every
+/// stage whose push constant range overlaps it).
+pub unsafe fn cmd_push_constant(
+    device: &Device,
+    command_buffer: vk::CommandBuffer,
+    data: &AppData,
+    name: &str,
+    bytes: &[u8],
+) -> Result<()> {
+    let member = data
+        .push_constants
+        .iter()
+        .find(|m| m.name == name)
+        .ok_or_else(|| anyhow!("The shaders have no push constant named `{}`.", name))?;
+
+    if bytes.len() != member.size as usize {
+        return Err(anyhow!(
+            "Push constant `{}` is {} bytes in the shaders but {} bytes were pushed.",
+            name,
+            member.size,
+            bytes.len(),
+        ));
+    }
+
+    device.cmd_push_constants(command_buffer, *data.pipeline_layout, member.stages, member.offset, bytes);
+
+    Ok(())
+}
+
+/// Sets the dynamic viewport and scissor of the pipeline to cover `extent`
+/// (the swapchain extent, so the pipeline does not depend on it).
+pub unsafe fn set_viewport(device: &Device, command_buffer: vk::CommandBuffer, extent: vk::Extent2D) {
+    let viewport = vk::Viewport::builder()
+        .x(0.0)
+        .y(0.0)
+        .width(extent.width as f32)
+        .height(extent.height as f32)
+        .min_depth(0.0)
+        .max_depth(1.0);
+
+    let scissor = vk::Rect2D::builder()
+        .offset(vk::Offset2D { x: 0, y: 0 })
+        .extent(extent);
+
+    device.cmd_set_viewport(command_buffer, 0, &[viewport]);
+    device.cmd_set_scissor(command_buffer, 0, &[scissor]);
+}
+
+#[track_caller]
+pub unsafe fn create_shader_module(device: &Rc<Device>, bytecode: &[u8]) -> Result<Owned<vk::ShaderModule>> {
+    let bytecode = Bytecode::new(bytecode)?;
+
+    let info = vk::ShaderModuleCreateInfo::builder()
+        .code_size(bytecode.code_size())
+        .code(bytecode.code());
+
+    Ok(Owned::new(device, device.create_shader_module(&info, None)?))
+}
\ No newline at end of file
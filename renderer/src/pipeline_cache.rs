@@ -0,0 +1,168 @@
+//================================================
+// Pipeline Cache
+//================================================
+//! A pipeline cache kept on disk between runs, so pipelines (which are also
+//! rebuilt on resizes and shader reloads) are only compiled from scratch once.
+//!
+//! A cache file is only used if its header matches the physical device (the
+//! driver ignores mismatching data, but a file from another GPU or driver
+//! version is just dead weight).
+
+use crate::app_data::AppData;
+use crate::assets::Assets;
+use crate::owned::Owned;
+use crate::pipeline::PipelineInfo;
+
+use std::fs;
+use std::rc::Rc;
+
+use anyhow::{anyhow, Result};
+use log::*;
+use vulkanalia::prelude::v1_0::*;
+
+/// The size of `VkPipelineCacheHeaderVersionOne`.
+const HEADER_SIZE: usize = 32;
+
+/// Creates the pipeline cache, seeded from the cache file of `pipeline` if
+/// there is a valid one.
+pub unsafe fn create_pipeline_cache(
+    instance: &Instance,
+    device: &Rc<Device>,
+    data: &mut AppData,
+    pipeline: &PipelineInfo,
+) -> Result<()> {
+    let properties = instance.get_physical_device_properties(data.physical_device);
+    let path = Assets::new(&data.settings).output(pipeline.cache);
+
+    let initial_data = match fs::read(&path) {
+        Ok(cache) => match validate(&cache, &properties) {
+            Ok(()) => {
+                debug!("Loaded the pipeline cache `{}` ({} bytes).", path.display(), cache.len());
+                cache
+            }
+            Err(e) => {
+                warn!("Ignoring the pipeline cache `{}`: {}", path.display(), e);
+                vec![]
+            }
+        },
+        Err(e) => {
+            debug!("No pipeline cache `{}` ({}).", path.display(), e);
+            vec![]
+        }
+    };
+
+    let info = vk::PipelineCacheCreateInfo::builder().initial_data(&initial_data);
+    data.pipeline_cache = Owned::new(device, device.create_pipeline_cache(&info, None)?);
+
+    Ok(())
+}
+
+/// Writes the contents of the pipeline cache to the cache file of `pipeline`.
+pub unsafe fn save_pipeline_cache(device: &Device, data: &AppData, pipeline: &PipelineInfo) -> Result<()> {
+    if data.pipeline_cache.is_null() {
+        return Ok(());
+    }
+
+    let cache = device.get_pipeline_cache_data(*data.pipeline_cache)?;
+
+    // Written next to the cache file first, so a crash never leaves half a file
+    let path = Assets::new(&data.settings).output(pipeline.cache);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let temporary = path.with_extension("tmp");
+    fs::write(&temporary, &cache)?;
+    fs::rename(&temporary, &path)?;
+
+    debug!("Saved the pipeline cache `{}` ({} bytes).", path.display(), cache.len());
+
+    Ok(())
+}
+
+/// Checks that the header of pipeline cache data was written for the device
+/// with `properties`.
+pub fn validate(cache: &[u8], properties: &vk::PhysicalDeviceProperties) -> Result<()> {
+    if cache.len() < HEADER_SIZE {
+        return Err(anyhow!("truncated header ({} bytes)", cache.len()));
+    }
+
+    let word = |i: usize| u32::from_le_bytes([cache[i], cache[i + 1], cache[i + 2], cache[i + 3]]);
+    let (length, version, vendor_id, device_id) = (word(0), word(4), word(8), word(12));
+    let uuid = &cache[16..HEADER_SIZE];
+
+    if (length as usize) < HEADER_SIZE || length as usize > cache.len() {
+        return Err(anyhow!("invalid header length {}", length));
+    }
+
+    if version != vk::PipelineCacheHeaderVersion::ONE.as_raw() as u32 {
+        return Err(anyhow!("unsupported header version {}", version));
+    }
+
+    if vendor_id != properties.vendor_id || device_id != properties.device_id {
+        return Err(anyhow!(
+            "written for device {:04x}:{:04x}, not {:04x}:{:04x}",
+            vendor_id,
+            device_id,
+            properties.vendor_id,
+            properties.device_id,
+        ));
+    }
+
+    if uuid != &properties.pipeline_cache_uuid[..] {
+        return Err(anyhow!("written by another driver version"));
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn properties() -> vk::PhysicalDeviceProperties {
+        vk::PhysicalDeviceProperties {
+            vendor_id: 0x10de,
+            device_id: 0x2684,
+            pipeline_cache_uuid: [7; 16].into(),
+            ..Default::default()
+        }
+    }
+
+    fn header(vendor_id: u32, device_id: u32, uuid: [u8; 16]) -> Vec<u8> {
+        let mut cache = vec![];
+        cache.extend_from_slice(&(HEADER_SIZE as u32).to_le_bytes());
+        cache.extend_from_slice(&1u32.to_le_bytes());
+        cache.extend_from_slice(&vendor_id.to_le_bytes());
+        cache.extend_from_slice(&device_id.to_le_bytes());
+        cache.extend_from_slice(&uuid);
+        cache.extend_from_slice(&[0xAB; 64]);
+        cache
+    }
+
+    #[test]
+    fn caches_of_the_same_device_are_accepted() {
+        assert!(validate(&header(0x10de, 0x2684, [7; 16]), &properties()).is_ok());
+    }
+
+    #[test]
+    fn caches_of_other_devices_or_drivers_are_rejected() {
+        assert!(validate(&header(0x1002, 0x2684, [7; 16]), &properties()).is_err());
+        assert!(validate(&header(0x10de, 0x2685, [7; 16]), &properties()).is_err());
+        assert!(validate(&header(0x10de, 0x2684, [8; 16]), &properties()).is_err());
+    }
+
+    #[test]
+    fn malformed_headers_are_rejected() {
+        let cache = header(0x10de, 0x2684, [7; 16]);
+        assert!(validate(&cache[..HEADER_SIZE - 1], &properties()).is_err());
+
+        let mut version = cache.clone();
+        version[4] = 2;
+        assert!(validate(&version, &properties()).is_err());
+
+        let mut length = cache;
+        length[0] = 16;
+        assert!(validate(&length, &properties()).is_err());
+    }
+}
@@ -0,0 +1,187 @@
+//================================================
+// Queries
+//================================================
+//! Pipeline statistics and occlusion queries around individual draws.
+//!
+//! Each frame in flight has `queries_per_frame` queries of both kinds, reset
+//! by its primary command buffer before the render pass and begun and ended
+//! around a draw (e.g. in the secondary command buffer of a model). Their
+//! results are read without waiting once the frame's fence was signaled.
+//!
+//! Pipeline statistics need the `pipeline_statistics_query` device feature,
+//! without it only the occlusion queries are used.
+
+use crate::app_data::AppData;
+use crate::owned::Owned;
+
+use std::fmt;
+use std::rc::Rc;
+
+use anyhow::Result;
+use log::*;
+use vulkanalia::prelude::v1_0::*;
+
+/// The pipeline statistics that are queried (reported in the order of their bits).
+const STATISTICS: vk::QueryPipelineStatisticFlags = vk::QueryPipelineStatisticFlags::from_bits_truncate(
+    vk::QueryPipelineStatisticFlags::INPUT_ASSEMBLY_VERTICES.bits()
+        | vk::QueryPipelineStatisticFlags::INPUT_ASSEMBLY_PRIMITIVES.bits()
+        | vk::QueryPipelineStatisticFlags::FRAGMENT_SHADER_INVOCATIONS.bits(),
+);
+
+/// What the GPU did for a draw.
+#[derive(Copy, Clone, Debug, Default, PartialEq, Eq)]
+pub struct DrawStatistics {
+    /// The vertices, primitives and fragment shader invocations (`None` if
+    /// pipeline statistics are not supported).
+    pub statistics: Option<[u64; 3]>,
+    /// The number of samples that passed the depth test.
+    pub samples_passed: u64,
+}
+
+impl DrawStatistics {
+    /// Whether any part of the draw was visible.
+    pub fn visible(&self) -> bool {
+        self.samples_passed > 0
+    }
+}
+
+impl fmt::Display for DrawStatistics {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let visibility = if self.visible() { "visible" } else { "hidden" };
+        write!(f, "{} ({} samples)", visibility, self.samples_passed)?;
+
+        match self.statistics {
+            Some([vertices, primitives, fragments]) => write!(
+                f,
+                ", {} vertices, {} primitives, {} fragment invocations",
+                vertices, primitives, fragments,
+            ),
+            None => Ok(()),
+        }
+    }
+}
+
+/// Creates the query pools with room for `per_frame` draws of each of the
+/// `count` frames in flight.
+pub unsafe fn create_query_pools(device: &Rc<Device>, data: &mut AppData, count: usize, per_frame: u32) -> Result<()> {
+    let query_count = count as u32 * per_frame;
+
+    if data.pipeline_statistics_query {
+        let info = vk::QueryPoolCreateInfo::builder()
+            .query_type(vk::QueryType::PIPELINE_STATISTICS)
+            .query_count(query_count)
+            .pipeline_statistics(STATISTICS);
+
+        data.statistics_query_pool = Owned::new(device, device.create_query_pool(&info, None)?);
+    } else {
+        warn!("Pipeline statistics queries are not supported, only occlusion is queried.");
+    }
+
+    let info = vk::QueryPoolCreateInfo::builder()
+        .query_type(vk::QueryType::OCCLUSION)
+        .query_count(query_count);
+
+    data.occlusion_query_pool = Owned::new(device, device.create_query_pool(&info, None)?);
+    data.queries_per_frame = per_frame;
+
+    Ok(())
+}
+
+/// Resets the queries of a frame in flight (outside of a render pass).
+pub unsafe fn cmd_reset_queries(device: &Device, command_buffer: vk::CommandBuffer, data: &AppData, frame: usize) {
+    let first = frame as u32 * data.queries_per_frame;
+    for pool in [*data.statistics_query_pool, *data.occlusion_query_pool] {
+        if !pool.is_null() {
+            device.cmd_reset_query_pool(command_buffer, pool, first, data.queries_per_frame);
+        }
+    }
+}
+
+/// Begins the queries of a draw of a frame in flight.
+pub unsafe fn cmd_begin_queries(device: &Device, command_buffer: vk::CommandBuffer, data: &AppData, frame: usize, draw: usize) {
+    let query = frame as u32 * data.queries_per_frame + draw as u32;
+    for pool in [*data.statistics_query_pool, *data.occlusion_query_pool] {
+        if !pool.is_null() {
+            device.cmd_begin_query(command_buffer, pool, query, vk::QueryControlFlags::empty());
+        }
+    }
+}
+
+/// Ends the queries of a draw of a frame in flight.
+pub unsafe fn cmd_end_queries(device: &Device, command_buffer: vk::CommandBuffer, data: &AppData, frame: usize, draw: usize) {
+    let query = frame as u32 * data.queries_per_frame + draw as u32;
+    for pool in [*data.statistics_query_pool, *data.occlusion_query_pool] {
+        if !pool.is_null() {
+            device.cmd_end_query(command_buffer, pool, query);
+        }
+    }
+}
+
+/// Reads the results of the first `draws` queries of a frame in flight that
+/// finished, without waiting for them (`None` for draws that have no results,
+/// e.g. because the frame drew fewer).
+pub unsafe fn get_draw_statistics(device: &Device, data: &AppData, frame: usize, draws: usize) -> Result<Vec<Option<DrawStatistics>>> {
+    let first = frame as u32 * data.queries_per_frame;
+    let draws = draws.min(data.queries_per_frame as usize);
+    if data.occlusion_query_pool.is_null() || draws == 0 {
+        return Ok(vec![]);
+    }
+
+    // Each query is followed by whether it is available
+    let flags = vk::QueryResultFlags::_64 | vk::QueryResultFlags::WITH_AVAILABILITY;
+
+    let mut occlusion = vec![[0u64; 2]; draws];
+    read_results(device, *data.occlusion_query_pool, first, &mut occlusion, flags)?;
+
+    let mut statistics = vec![[0u64; 4]; draws];
+    if !data.statistics_query_pool.is_null() {
+        read_results(device, *data.statistics_query_pool, first, &mut statistics, flags)?;
+    }
+
+    let results = occlusion
+        .iter()
+        .zip(&statistics)
+        .map(|([samples_passed, available], [vertices, primitives, fragments, statistics_available])| {
+            (*available != 0).then(|| DrawStatistics {
+                statistics: (*statistics_available != 0).then_some([*vertices, *primitives, *fragments]),
+                samples_passed: *samples_passed,
+            })
+        })
+        .collect();
+
+    Ok(results)
+}
+
+/// Reads the results of consecutive queries (`NOT_READY` leaves the results of
+/// the unavailable queries marked as such).
+unsafe fn read_results<const N: usize>(
+    device: &Device,
+    pool: vk::QueryPool,
+    first: u32,
+    results: &mut [[u64; N]],
+    flags: vk::QueryResultFlags,
+) -> Result<()> {
+    let stride = (N * 8) as vk::DeviceSize;
+    let bytes = std::slice::from_raw_parts_mut(results.as_mut_ptr().cast::<u8>(), results.len() * N * 8);
+    device.get_query_pool_results(pool, first, results.len() as u32, bytes, stride, flags)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn draw_statistics_are_displayed() {
+        let hidden = DrawStatistics { statistics: None, samples_passed: 0 };
+        assert!(!hidden.visible());
+        assert_eq!(hidden.to_string(), "hidden (0 samples)");
+
+        let visible = DrawStatistics { statistics: Some([11484, 3828, 51234]), samples_passed: 40960 };
+        assert!(visible.visible());
+        assert_eq!(
+            visible.to_string(),
+            "visible (40960 samples), 11484 vertices, 3828 primitives, 51234 fragment invocations",
+        );
+    }
+}
@@ -0,0 +1,747 @@
+//================================================
+// Reflect
+//================================================
+//! Reflection of the descriptor bindings, push constants and vertex inputs
+//! declared by SPIR-V shader modules, so the descriptor set layout, pipeline
+//! layout and vertex attributes follow the shaders instead of being written by
+//! hand to match them.
+//!
+//! Modules are parsed with `rspirv`, and only the interface of their (first)
+//! entry point is reflected: its vertex inputs and the resources the entry
+//! point and the functions it calls actually use.
+
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+
+use anyhow::{anyhow, Result};
+use rspirv::dr::{self, Instruction, Operand};
+use rspirv::spirv::{Decoration, Dim, ExecutionModel, Op, StorageClass, Word};
+use vulkanalia::prelude::v1_0::*;
+
+/// A descriptor binding used by a shader.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Binding {
+    pub set: u32,
+    pub binding: u32,
+    pub descriptor_type: vk::DescriptorType,
+    pub count: u32,
+    pub stages: vk::ShaderStageFlags,
+    pub name: String,
+}
+
+/// A member of a push constant block, with the stages it has to be pushed to.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PushConstant {
+    pub name: String,
+    pub offset: u32,
+    pub size: u32,
+    pub stages: vk::ShaderStageFlags,
+}
+
+/// A vertex shader input.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Input {
+    pub location: u32,
+    pub format: vk::Format,
+    pub name: String,
+}
+
+/// The interface of a shader module.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Module {
+    pub stage: vk::ShaderStageFlags,
+    pub bindings: Vec<Binding>,
+    /// The bytes of the push constant block used by the shader.
+    pub push_constants: Option<vk::PushConstantRange>,
+    /// The members of the push constant block (sorted by offset).
+    pub push_constant_members: Vec<PushConstant>,
+    /// The inputs of a vertex shader (sorted by location).
+    pub inputs: Vec<Input>,
+}
+
+/// Reflects the interface of the entry point of a SPIR-V shader module.
+pub fn reflect(bytecode: &[u8]) -> Result<Module> {
+    let module = dr::load_bytes(bytecode).map_err(|e| anyhow!("Invalid SPIR-V module ({}).", e))?;
+    Reflector::new(&module).module(&module)
+}
+
+/// The ids of a module that the interface is looked up in.
+#[derive(Default)]
+struct Reflector<'a> {
+    names: HashMap<Word, &'a str>,
+    member_names: HashMap<(Word, u32), &'a str>,
+    decorations: HashMap<(Word, Decoration), u32>,
+    member_decorations: HashMap<(Word, u32, Decoration), u32>,
+    types: HashMap<Word, &'a Instruction>,
+    constants: HashMap<Word, u32>,
+}
+
+fn id(operand: Option<&Operand>) -> Option<Word> {
+    match operand {
+        Some(Operand::IdRef(id)) => Some(*id),
+        _ => None,
+    }
+}
+
+fn literal(operand: Option<&Operand>) -> Option<u32> {
+    match operand {
+        Some(Operand::LiteralInt32(value)) => Some(*value),
+        _ => None,
+    }
+}
+
+fn string(operand: Option<&Operand>) -> Option<&str> {
+    match operand {
+        Some(Operand::LiteralString(value)) => Some(value),
+        _ => None,
+    }
+}
+
+impl<'a> Reflector<'a> {
+    fn new(module: &'a dr::Module) -> Self {
+        let mut reflector = Self::default();
+
+        for name in &module.debug_names {
+            let operands = &name.operands;
+            match (name.class.opcode, id(operands.first())) {
+                (Op::Name, Some(target)) => {
+                    reflector.names.insert(target, string(operands.get(1)).unwrap_or_default());
+                }
+                (Op::MemberName, Some(target)) => {
+                    let member = literal(operands.get(1)).unwrap_or_default();
+                    reflector.member_names.insert((target, member), string(operands.get(2)).unwrap_or_default());
+                }
+                _ => {}
+            }
+        }
+
+        for annotation in &module.annotations {
+            let operands = &annotation.operands;
+            match (annotation.class.opcode, id(operands.first())) {
+                (Op::Decorate, Some(target)) => {
+                    if let Some(Operand::Decoration(decoration)) = operands.get(1) {
+                        let value = literal(operands.get(2)).unwrap_or_default();
+                        reflector.decorations.insert((target, *decoration), value);
+                    }
+                }
+                (Op::MemberDecorate, Some(target)) => {
+                    let member = literal(operands.get(1)).unwrap_or_default();
+                    if let Some(Operand::Decoration(decoration)) = operands.get(2) {
+                        let value = literal(operands.get(3)).unwrap_or_default();
+                        reflector.member_decorations.insert((target, member, *decoration), value);
+                    }
+                }
+                _ => {}
+            }
+        }
+
+        for instruction in &module.types_global_values {
+            match (instruction.class.opcode, instruction.result_id) {
+                (Op::Constant, Some(result)) => {
+                    if let Some(value) = literal(instruction.operands.first()) {
+                        reflector.constants.insert(result, value);
+                    }
+                }
+                (Op::Variable, _) => {}
+                (_, Some(result)) => {
+                    reflector.types.insert(result, instruction);
+                }
+                _ => {}
+            }
+        }
+
+        reflector
+    }
+
+    fn module(&self, module: &dr::Module) -> Result<Module> {
+        let entry_point = module
+            .entry_points
+            .first()
+            .ok_or_else(|| anyhow!("SPIR-V module has no entry point."))?;
+
+        let stage = match entry_point.operands.first() {
+            Some(Operand::ExecutionModel(model)) => match model {
+                ExecutionModel::Vertex => vk::ShaderStageFlags::VERTEX,
+                ExecutionModel::TessellationControl => vk::ShaderStageFlags::TESSELLATION_CONTROL,
+                ExecutionModel::TessellationEvaluation => vk::ShaderStageFlags::TESSELLATION_EVALUATION,
+                ExecutionModel::Geometry => vk::ShaderStageFlags::GEOMETRY,
+                ExecutionModel::Fragment => vk::ShaderStageFlags::FRAGMENT,
+                ExecutionModel::GLCompute => vk::ShaderStageFlags::COMPUTE,
+                model => return Err(anyhow!("Unsupported execution model {:?}.", model)),
+            },
+            _ => return Err(anyhow!("Invalid entry point.")),
+        };
+
+        // The interface lists the inputs (and, since SPIR-V 1.4, every global
+        // the entry point uses), the other globals are found by their uses
+        let function = id(entry_point.operands.get(1)).ok_or_else(|| anyhow!("Invalid entry point."))?;
+        let interface = entry_point.operands.iter().skip(3).filter_map(|o| id(Some(o))).collect::<HashSet<_>>();
+        let used = used_ids(module, function);
+
+        let mut reflected = Module { stage, ..Default::default() };
+        for variable in module.types_global_values.iter().filter(|i| i.class.opcode == Op::Variable) {
+            let (Some(pointer), Some(id)) = (variable.result_type, variable.result_id) else {
+                continue;
+            };
+            if !interface.contains(&id) && !used.contains(&id) {
+                continue;
+            }
+
+            let type_ = self
+                .types
+                .get(&pointer)
+                .filter(|t| t.class.opcode == Op::TypePointer)
+                .and_then(|t| self::id(t.operands.get(1)))
+                .ok_or_else(|| anyhow!("Variable {} is not a pointer.", id))?;
+
+            let name = self.name(id, type_);
+            match variable.operands.first() {
+                Some(Operand::StorageClass(
+                    storage_class @ (StorageClass::UniformConstant | StorageClass::Uniform | StorageClass::StorageBuffer),
+                )) => {
+                    let (descriptor_type, count) = self.descriptor(type_, *storage_class, &name)?;
+                    reflected.bindings.push(Binding {
+                        set: self.decoration(id, Decoration::DescriptorSet).unwrap_or(0),
+                        binding: self.decoration(id, Decoration::Binding).unwrap_or(0),
+                        descriptor_type,
+                        count,
+                        stages: stage,
+                        name,
+                    });
+                }
+                Some(Operand::StorageClass(StorageClass::PushConstant)) => {
+                    let members = self.members(type_, stage)?;
+                    let offset = members.iter().map(|m| m.offset).min().unwrap_or(0);
+                    let end = members.iter().map(|m| m.offset + m.size).max().unwrap_or(0);
+                    reflected.push_constants = Some(vk::PushConstantRange {
+                        stage_flags: stage,
+                        offset,
+                        size: end - offset,
+                    });
+                    reflected.push_constant_members = members;
+                }
+                Some(Operand::StorageClass(StorageClass::Input)) if stage == vk::ShaderStageFlags::VERTEX => {
+                    if self.decoration(id, Decoration::BuiltIn).is_some() {
+                        continue;
+                    }
+
+                    let location = self
+                        .decoration(id, Decoration::Location)
+                        .ok_or_else(|| anyhow!("Vertex input `{}` has no location.", name))?;
+                    let format = self
+                        .format(type_)
+                        .ok_or_else(|| anyhow!("Vertex input `{}` has an unsupported type.", name))?;
+                    reflected.inputs.push(Input { location, format, name });
+                }
+                _ => {}
+            }
+        }
+
+        reflected.bindings.sort_by_key(|b| (b.set, b.binding));
+        reflected.push_constant_members.sort_by_key(|m| m.offset);
+        reflected.inputs.sort_by_key(|i| i.location);
+        Ok(reflected)
+    }
+
+    fn name(&self, id: Word, type_: Word) -> String {
+        // Uniform blocks are usually only named by their type
+        match self.names.get(&id) {
+            Some(name) if !name.is_empty() => name.to_string(),
+            _ => self.names.get(&type_).map(|n| n.to_string()).unwrap_or_else(|| format!("%{}", id)),
+        }
+    }
+
+    fn decoration(&self, id: Word, decoration: Decoration) -> Option<u32> {
+        self.decorations.get(&(id, decoration)).copied()
+    }
+
+    fn opcode(&self, id: Word) -> Option<Op> {
+        self.types.get(&id).map(|t| t.class.opcode)
+    }
+
+    fn operand(&self, id: Word, index: usize) -> Option<&'a Operand> {
+        self.types.get(&id).and_then(|t| t.operands.get(index))
+    }
+
+    fn descriptor(&self, type_: Word, storage_class: StorageClass, name: &str) -> Result<(vk::DescriptorType, u32)> {
+        let (type_, count) = match self.opcode(type_) {
+            Some(Op::TypeArray) => {
+                let length = id(self.operand(type_, 1)).and_then(|l| self.constants.get(&l).copied());
+                (id(self.operand(type_, 0)).unwrap_or_default(), length.unwrap_or(1))
+            }
+            // A layout needs to know how many descriptors there are, and
+            // variable counts need descriptor indexing (which is not enabled)
+            Some(Op::TypeRuntimeArray) => {
+                return Err(anyhow!("Descriptor `{}` is a runtime-sized array, which is not supported.", name));
+            }
+            _ => (type_, 1),
+        };
+
+        let block = |decoration| self.decoration(type_, decoration).is_some();
+        let image = |index| self.operand(type_, index);
+        let descriptor_type = match (self.opcode(type_), storage_class) {
+            (Some(Op::TypeStruct), StorageClass::StorageBuffer) => vk::DescriptorType::STORAGE_BUFFER,
+            (Some(Op::TypeStruct), _) if block(Decoration::BufferBlock) => vk::DescriptorType::STORAGE_BUFFER,
+            (Some(Op::TypeStruct), _) if block(Decoration::Block) => vk::DescriptorType::UNIFORM_BUFFER,
+            (Some(Op::TypeSampledImage), _) => vk::DescriptorType::COMBINED_IMAGE_SAMPLER,
+            (Some(Op::TypeSampler), _) => vk::DescriptorType::SAMPLER,
+            (Some(Op::TypeImage), _) => match (image(1), literal(image(5))) {
+                (Some(Operand::Dim(Dim::DimSubpassData)), _) => vk::DescriptorType::INPUT_ATTACHMENT,
+                (Some(Operand::Dim(Dim::DimBuffer)), Some(2)) => vk::DescriptorType::STORAGE_TEXEL_BUFFER,
+                (Some(Operand::Dim(Dim::DimBuffer)), _) => vk::DescriptorType::UNIFORM_TEXEL_BUFFER,
+                (_, Some(2)) => vk::DescriptorType::STORAGE_IMAGE,
+                _ => vk::DescriptorType::SAMPLED_IMAGE,
+            },
+            (opcode, _) => return Err(anyhow!("Descriptor `{}` has an unsupported type ({:?}).", name, opcode)),
+        };
+
+        Ok((descriptor_type, count))
+    }
+
+    /// Returns the members of a push constant block, with the members of
+    /// nested structs in their place (named `outer.inner`, or only `inner` for
+    /// the unnamed wrapper structs some compilers put around blocks).
+    fn members(&self, id: Word, stage: vk::ShaderStageFlags) -> Result<Vec<PushConstant>> {
+        if self.opcode(id) != Some(Op::TypeStruct) {
+            return Err(anyhow!("Push constants are not a block."));
+        }
+
+        let types = &self.types[&id].operands;
+        if types.is_empty() {
+            return Err(anyhow!("Push constant block is empty."));
+        }
+
+        let mut members = vec![];
+        for (index, type_) in types.iter().enumerate() {
+            let index = index as u32;
+            let member = |decoration| self.member_decorations.get(&(id, index, decoration)).copied();
+            let name = self.member_names.get(&(id, index)).filter(|n| !n.is_empty());
+            let offset = member(Decoration::Offset).unwrap_or(0);
+            let type_ = self::id(Some(type_)).unwrap_or_default();
+
+            if self.opcode(type_) == Some(Op::TypeStruct) {
+                for nested in self.members(type_, stage)? {
+                    members.push(PushConstant {
+                        name: name.map(|n| format!("{}.{}", n, nested.name)).unwrap_or(nested.name),
+                        offset: offset + nested.offset,
+                        ..nested
+                    });
+                }
+            } else {
+                members.push(PushConstant {
+                    name: name.map(|n| n.to_string()).unwrap_or_else(|| index.to_string()),
+                    offset,
+                    size: self.size(type_, member(Decoration::MatrixStride))?,
+                    stages: stage,
+                });
+            }
+        }
+
+        Ok(members)
+    }
+
+    fn size(&self, id: Word, matrix_stride: Option<u32>) -> Result<u32> {
+        let count = |index| literal(self.operand(id, index)).unwrap_or(1);
+        let element = |index| self::id(self.operand(id, index)).unwrap_or_default();
+        Ok(match self.opcode(id) {
+            Some(Op::TypeInt | Op::TypeFloat) => count(0) / 8,
+            Some(Op::TypeVector) => self.size(element(0), None)? * count(1),
+            Some(Op::TypeMatrix) => match matrix_stride {
+                Some(stride) => stride * count(1),
+                None => self.size(element(0), None)? * count(1),
+            },
+            Some(Op::TypeArray) => {
+                let length = self.constants.get(&element(1)).copied().unwrap_or(1);
+                match self.decoration(id, Decoration::ArrayStride) {
+                    Some(stride) => stride * length,
+                    None => self.size(element(0), matrix_stride)? * length,
+                }
+            }
+            Some(Op::TypeStruct) => {
+                let members = self.members(id, vk::ShaderStageFlags::empty())?;
+                members.iter().map(|m| m.offset + m.size).max().unwrap_or(0)
+            }
+            opcode => return Err(anyhow!("Cannot determine the size of {:?}.", opcode)),
+        })
+    }
+
+    fn format(&self, id: Word) -> Option<vk::Format> {
+        let (component, count) = match self.opcode(id)? {
+            Op::TypeVector => (self::id(self.operand(id, 0))?, literal(self.operand(id, 1))?),
+            _ => (id, 1),
+        };
+
+        let width = literal(self.operand(component, 0))?;
+        let signed = literal(self.operand(component, 1));
+
+        use vk::Format as F;
+        let formats = match (self.opcode(component)?, width, signed) {
+            (Op::TypeFloat, 32, _) => [F::R32_SFLOAT, F::R32G32_SFLOAT, F::R32G32B32_SFLOAT, F::R32G32B32A32_SFLOAT],
+            (Op::TypeInt, 32, Some(1)) => [F::R32_SINT, F::R32G32_SINT, F::R32G32B32_SINT, F::R32G32B32A32_SINT],
+            (Op::TypeInt, 32, Some(0)) => [F::R32_UINT, F::R32G32_UINT, F::R32G32B32_UINT, F::R32G32B32A32_UINT],
+            _ => return None,
+        };
+
+        formats.get(count.checked_sub(1)? as usize).copied()
+    }
+}
+
+/// Returns the ids used by a function and the functions it calls.
+fn used_ids(module: &dr::Module, function: Word) -> HashSet<Word> {
+    let functions = module
+        .functions
+        .iter()
+        .filter_map(|f| Some((f.def.as_ref()?.result_id?, f)))
+        .collect::<HashMap<_, _>>();
+
+    let mut used = HashSet::new();
+    let mut pending = vec![function];
+    let mut visited = HashSet::new();
+    while let Some(function) = pending.pop() {
+        if !visited.insert(function) {
+            continue;
+        }
+
+        let Some(function) = functions.get(&function) else {
+            continue;
+        };
+
+        for instruction in function.blocks.iter().flat_map(|b| &b.instructions) {
+            let ids = instruction.operands.iter().filter_map(|o| id(Some(o)));
+            if instruction.class.opcode == Op::FunctionCall {
+                pending.extend(id(instruction.operands.first()));
+            }
+            used.extend(ids);
+        }
+    }
+
+    used
+}
+
+impl fmt::Display for Binding {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "`{}` (set {}, binding {}, {:?} x {})",
+            self.name, self.set, self.binding, self.descriptor_type, self.count,
+        )
+    }
+}
+
+//================================================
+// Layouts
+//================================================
+
+/// Merges the descriptor bindings of the shaders of a pipeline (which have to
+/// agree on the bindings they share) into the bindings of descriptor set 0.
+pub fn descriptor_set_layout_bindings(modules: &[Module]) -> Result<Vec<vk::DescriptorSetLayoutBinding>> {
+    let mut merged: Vec<Binding> = vec![];
+    for binding in modules.iter().flat_map(|m| &m.bindings) {
+        if binding.set != 0 {
+            return Err(anyhow!("Descriptor {} is not in set 0.", binding));
+        }
+
+        match merged.iter_mut().find(|b| b.binding == binding.binding) {
+            Some(b) if b.descriptor_type != binding.descriptor_type || b.count != binding.count => {
+                return Err(anyhow!("Shaders disagree about binding {}: {} vs {}.", binding.binding, b, binding));
+            }
+            Some(b) => b.stages |= binding.stages,
+            None => merged.push(binding.clone()),
+        }
+    }
+
+    merged.sort_by_key(|b| b.binding);
+    Ok(merged
+        .iter()
+        .map(|b| {
+            vk::DescriptorSetLayoutBinding::builder()
+                .binding(b.binding)
+                .descriptor_type(b.descriptor_type)
+                .descriptor_count(b.count)
+                .stage_flags(b.stages)
+                .build()
+        })
+        .collect())
+}
+
+/// Returns the push constant ranges used by the shaders of a pipeline.
+pub fn push_constant_ranges(modules: &[Module]) -> Vec<vk::PushConstantRange> {
+    modules.iter().filter_map(|m| m.push_constants).collect()
+}
+
+/// Merges the push constant block members of the shaders of a pipeline (which
+/// have to agree on the members they share). The stages of a member are those
+/// of every range that overlaps it, which `cmd_push_constants` requires.
+pub fn push_constants(modules: &[Module]) -> Result<Vec<PushConstant>> {
+    let mut merged: Vec<PushConstant> = vec![];
+    for member in modules.iter().flat_map(|m| &m.push_constant_members) {
+        match merged.iter().find(|m| m.name == member.name) {
+            Some(m) if (m.offset, m.size) != (member.offset, member.size) => {
+                return Err(anyhow!(
+                    "Shaders disagree about push constant `{}`: {} bytes at {} vs {} bytes at {}.",
+                    member.name,
+                    m.size,
+                    m.offset,
+                    member.size,
+                    member.offset,
+                ));
+            }
+            Some(_) => {}
+            None => merged.push(member.clone()),
+        }
+    }
+
+    let ranges = push_constant_ranges(modules);
+    for member in &mut merged {
+        member.stages = ranges
+            .iter()
+            .filter(|r| r.offset < member.offset + member.size && member.offset < r.offset + r.size)
+            .fold(vk::ShaderStageFlags::empty(), |stages, r| stages | r.stage_flags);
+    }
+
+    merged.sort_by_key(|m| m.offset);
+    Ok(merged)
+}
+
+/// Returns the attributes of `vertex` (the attribute descriptions of a Rust
+/// vertex type) read by a vertex shader, or an error naming the first input
+/// the vertex type does not provide in the format the shader expects.
+pub fn vertex_attribute_descriptions(
+    module: &Module,
+    vertex: &[vk::VertexInputAttributeDescription],
+) -> Result<Vec<vk::VertexInputAttributeDescription>> {
+    module
+        .inputs
+        .iter()
+        .map(|input| match vertex.iter().find(|a| a.location == input.location) {
+            Some(attribute) if attribute.format == input.format => Ok(*attribute),
+            Some(attribute) => Err(anyhow!(
+                "Vertex input `{}` (location {}) is {:?} in the shader but {:?} in `Vertex`.",
+                input.name,
+                input.location,
+                input.format,
+                attribute.format,
+            )),
+            None => Err(anyhow!(
+                "Vertex input `{}` (location {}, {:?}) has no attribute in `Vertex`.",
+                input.name,
+                input.location,
+                input.format,
+            )),
+        })
+        .collect()
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use rspirv::binary::Assemble;
+    use rspirv::spirv::{AddressingModel, Capability, FunctionControl, MemoryModel};
+
+    use crate::structs::Vertex;
+
+    const VERT_19: &[u8] = include_bytes!("../../shaders/19/vert.spv");
+    const VERT_23: &[u8] = include_bytes!("../../shaders/23/vert.spv");
+    const FRAG_23: &[u8] = include_bytes!("../../shaders/23/frag.spv");
+
+    /// Builds a fragment shader whose `main` uses a uniform block at binding 0
+    /// and a descriptor of the type `declare` returns at binding 1, and whose
+    /// second entry point also uses a sampler at binding 2.
+    fn fragment_shader(declare: impl Fn(&mut dr::Builder) -> Word) -> Vec<u8> {
+        let mut b = dr::Builder::new();
+        b.capability(Capability::Shader);
+        b.memory_model(AddressingModel::Logical, MemoryModel::GLSL450);
+
+        let float = b.type_float(32);
+        let block = b.type_struct([float]);
+        b.decorate(block, Decoration::Block, []);
+        b.member_decorate(block, 0, Decoration::Offset, [Operand::LiteralInt32(0)]);
+        let pointer = b.type_pointer(None, StorageClass::Uniform, block);
+        let uniforms = b.variable(pointer, None, StorageClass::Uniform, None);
+        b.decorate(uniforms, Decoration::Binding, [Operand::LiteralInt32(0)]);
+
+        let descriptor = declare(&mut b);
+        let pointer = b.type_pointer(None, StorageClass::UniformConstant, descriptor);
+        let declared = b.variable(pointer, None, StorageClass::UniformConstant, None);
+        b.decorate(declared, Decoration::Binding, [Operand::LiteralInt32(1)]);
+
+        let sampler = b.type_sampler();
+        let pointer = b.type_pointer(None, StorageClass::UniformConstant, sampler);
+        let unused = b.variable(pointer, None, StorageClass::UniformConstant, None);
+        b.decorate(unused, Decoration::Binding, [Operand::LiteralInt32(2)]);
+
+        let void = b.type_void();
+        let function = b.type_function(void, []);
+
+        // `main` reaches its descriptors through a function call
+        let load = b.begin_function(void, None, FunctionControl::NONE, function).unwrap();
+        b.begin_block(None).unwrap();
+        b.load(block, None, uniforms, None, []).unwrap();
+        b.load(descriptor, None, declared, None, []).unwrap();
+        b.ret().unwrap();
+        b.end_function().unwrap();
+
+        let main = b.begin_function(void, None, FunctionControl::NONE, function).unwrap();
+        b.begin_block(None).unwrap();
+        b.function_call(void, None, load, []).unwrap();
+        b.ret().unwrap();
+        b.end_function().unwrap();
+        b.entry_point(ExecutionModel::Fragment, main, "main", []);
+
+        let other = b.begin_function(void, None, FunctionControl::NONE, function).unwrap();
+        b.begin_block(None).unwrap();
+        b.function_call(void, None, load, []).unwrap();
+        b.load(sampler, None, unused, None, []).unwrap();
+        b.ret().unwrap();
+        b.end_function().unwrap();
+        b.entry_point(ExecutionModel::Fragment, other, "other", [unused]);
+
+        let words = b.module().assemble();
+        words.iter().flat_map(|w| w.to_le_bytes()).collect()
+    }
+
+    #[test]
+    fn vertex_shader_interface() -> Result<()> {
+        let module = reflect(VERT_23)?;
+        assert_eq!(module.stage, vk::ShaderStageFlags::VERTEX);
+
+        assert_eq!(module.bindings.len(), 1);
+        assert_eq!(module.bindings[0].binding, 0);
+        assert_eq!(module.bindings[0].descriptor_type, vk::DescriptorType::UNIFORM_BUFFER);
+        assert_eq!(module.bindings[0].count, 1);
+
+        let range = module.push_constants.unwrap();
+        assert_eq!((range.stage_flags, range.offset, range.size), (vk::ShaderStageFlags::VERTEX, 0, 64));
+        let members = module.push_constant_members.iter().map(|m| (m.name.as_str(), m.offset, m.size));
+        assert_eq!(members.collect::<Vec<_>>(), vec![("model", 0, 64)]);
+
+        let inputs = module.inputs.iter().map(|i| (i.location, i.format)).collect::<Vec<_>>();
+        assert_eq!(inputs, vec![
+            (0, vk::Format::R32G32B32_SFLOAT),
+            (1, vk::Format::R32G32B32_SFLOAT),
+            (2, vk::Format::R32G32_SFLOAT),
+        ]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn fragment_shader_interface() -> Result<()> {
+        let module = reflect(FRAG_23)?;
+        assert_eq!(module.stage, vk::ShaderStageFlags::FRAGMENT);
+
+        let bindings = module.bindings.iter().map(|b| (b.binding, b.descriptor_type)).collect::<Vec<_>>();
+        assert_eq!(bindings, vec![
+            (1, vk::DescriptorType::SAMPLED_IMAGE),
+            (2, vk::DescriptorType::SAMPLER),
+        ]);
+
+        let range = module.push_constants.unwrap();
+        assert_eq!((range.stage_flags, range.offset, range.size), (vk::ShaderStageFlags::FRAGMENT, 0, 68));
+        let members = module.push_constant_members.iter().map(|m| (m.name.as_str(), m.offset, m.size));
+        assert_eq!(members.collect::<Vec<_>>(), vec![("model", 0, 64), ("opacity", 64, 4)]);
+        assert!(module.inputs.is_empty());
+
+        Ok(())
+    }
+
+    #[test]
+    fn layouts_are_merged_across_stages() -> Result<()> {
+        let modules = [reflect(VERT_23)?, reflect(FRAG_23)?];
+
+        let bindings = descriptor_set_layout_bindings(&modules)?;
+        let bindings = bindings.iter().map(|b| (b.binding, b.descriptor_type, b.stage_flags)).collect::<Vec<_>>();
+        assert_eq!(bindings, vec![
+            (0, vk::DescriptorType::UNIFORM_BUFFER, vk::ShaderStageFlags::VERTEX),
+            (1, vk::DescriptorType::SAMPLED_IMAGE, vk::ShaderStageFlags::FRAGMENT),
+            (2, vk::DescriptorType::SAMPLER, vk::ShaderStageFlags::FRAGMENT),
+        ]);
+
+        assert_eq!(push_constant_ranges(&modules).len(), 2);
+        assert!(push_constant_ranges(&[reflect(VERT_19)?]).is_empty());
+
+        // `model` is in the ranges of both stages, `opacity` only in the fragment one
+        let members = push_constants(&modules)?;
+        let members = members.iter().map(|m| (m.name.as_str(), m.offset, m.stages)).collect::<Vec<_>>();
+        assert_eq!(members, vec![
+            ("model", 0, vk::ShaderStageFlags::VERTEX | vk::ShaderStageFlags::FRAGMENT),
+            ("opacity", 64, vk::ShaderStageFlags::FRAGMENT),
+        ]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn push_constant_mismatches_are_reported() -> Result<()> {
+        let mut fragment = reflect(FRAG_23)?;
+        fragment.push_constant_members[1].name = "model".into();
+
+        let error = push_constants(&[reflect(VERT_23)?, fragment]).unwrap_err().to_string();
+        assert!(error.contains("`model`"), "{}", error);
+
+        Ok(())
+    }
+
+    #[test]
+    fn only_the_entry_point_interface_is_reflected() -> Result<()> {
+        let module = reflect(&fragment_shader(|b| b.type_sampler()))?;
+        let bindings = module.bindings.iter().map(|b| (b.binding, b.descriptor_type)).collect::<Vec<_>>();
+        assert_eq!(bindings, vec![
+            (0, vk::DescriptorType::UNIFORM_BUFFER),
+            (1, vk::DescriptorType::SAMPLER),
+        ]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn descriptor_arrays_are_counted() -> Result<()> {
+        let module = reflect(&fragment_shader(|b| {
+            let sampler = b.type_sampler();
+            let uint = b.type_int(32, 0);
+            let length = b.constant_u32(uint, 4);
+            b.type_array(sampler, length)
+        }))?;
+        assert_eq!((module.bindings[1].descriptor_type, module.bindings[1].count), (vk::DescriptorType::SAMPLER, 4));
+
+        // Runtime-sized arrays have no count a layout could be created with
+        let bytecode = fragment_shader(|b| {
+            let sampler = b.type_sampler();
+            b.type_runtime_array(sampler)
+        });
+        let error = reflect(&bytecode).unwrap_err().to_string();
+        assert!(error.contains("runtime-sized"), "{}", error);
+
+        Ok(())
+    }
+
+    #[test]
+    fn vertex_layout_mismatches_are_reported() -> Result<()> {
+        let module = reflect(VERT_23)?;
+        let attributes = Vertex::attribute_descriptions();
+        assert_eq!(vertex_attribute_descriptions(&module, &attributes)?.len(), 3);
+
+        let mut wrong = attributes;
+        wrong[1].format = vk::Format::R32G32B32A32_SFLOAT;
+        let error = vertex_attribute_descriptions(&module, &wrong).unwrap_err().to_string();
+        assert!(error.contains("location 1"), "{}", error);
+
+        let error = vertex_attribute_descriptions(&module, &attributes[..2]).unwrap_err().to_string();
+        assert!(error.contains("no attribute"), "{}", error);
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_modules_are_rejected() {
+        assert!(reflect(&[]).is_err());
+        assert!(reflect(&[0; 20]).is_err());
+
+        // An instruction running past the end of the module
+        let mut truncated = VERT_23.to_vec();
+        truncated.extend_from_slice(&(4 << 16 | Op::Variable as u32).to_le_bytes());
+        assert!(reflect(&truncated).is_err());
+    }
+}
@@ -0,0 +1,374 @@
+//================================================
+// Scene
+//================================================
+//! glTF 2.0 scenes (`.gltf` with external or embedded buffers and images, or
+//! binary `.glb`) loaded into the same [`Model`] as OBJ files.
+//!
+//! Every triangle primitive becomes a [`Mesh`], every node of the default
+//! scene a [`Node`] with its transform, and every material a [`Material`]
+//! whose textures are decoded to [`Pixels`] up front. glTF is Y-up while the
+//! demos are Z-up, so the root nodes are rotated accordingly.
+
+use crate::model::{self, Material, Mesh, Model, Node};
+use crate::structs::{Mat4, Vertex};
+use crate::decode::{self, Pixels};
+use crate::texture::TextureSource;
+
+use std::path::Path;
+use std::rc::Rc;
+
+use anyhow::{anyhow, Context, Result};
+use cgmath::{vec2, vec3, Deg};
+use gltf::image::Format;
+use log::*;
+
+/// Loads a glTF scene (its default scene, or the first one if it has none).
+pub fn load_gltf(path: impl AsRef<Path>) -> Result<Model> {
+    let path = path.as_ref();
+    let (document, buffers, images) =
+        gltf::import(path).with_context(|| format!("Failed to load glTF scene `{}`.", path.display()))?;
+
+    let images = images.into_iter().map(|i| to_pixels(i).map(Rc::new)).collect::<Result<Vec<_>>>()?;
+    let mut model = Model {
+        materials: document.materials().map(|m| load_material(&m, &images)).collect(),
+        ..Default::default()
+    };
+
+    // Meshes (the range of meshes each glTF mesh's primitives became)
+    let mut primitives = Vec::with_capacity(document.meshes().len());
+    for mesh in document.meshes() {
+        let first = model.meshes.len();
+        for primitive in mesh.primitives() {
+            load_primitive(&mut model, &mesh, &primitive, &buffers)?;
+        }
+        primitives.push(first..model.meshes.len());
+    }
+
+    // Nodes (parents before their children)
+    let scene = document
+        .default_scene()
+        .or_else(|| document.scenes().next())
+        .ok_or_else(|| anyhow!("glTF file `{}` has no scene.", path.display()))?;
+
+    let up = Mat4::from_angle_x(Deg(90.0));
+    let mut stack = scene.nodes().map(|n| (n, None, up)).collect::<Vec<_>>();
+    stack.reverse();
+
+    while let Some((node, parent, parent_world)) = stack.pop() {
+        let transform = Mat4::from(node.transform().matrix());
+        let world = parent_world * transform;
+        let index = model.nodes.len();
+
+        model.nodes.push(Node {
+            name: node.name().map(String::from).unwrap_or_else(|| format!("node {}", node.index())),
+            parent,
+            transform,
+            world,
+            meshes: node.mesh().map(|m| primitives[m.index()].clone().collect()).unwrap_or_default(),
+        });
+
+        stack.extend(node.children().collect::<Vec<_>>().into_iter().rev().map(|c| (c, Some(index), world)));
+    }
+
+    Ok(model)
+}
+
+/// Appends the vertices, indices and mesh of a triangle primitive to a model
+/// (other primitives are skipped).
+fn load_primitive(model: &mut Model, mesh: &gltf::Mesh, primitive: &gltf::Primitive, buffers: &[gltf::buffer::Data]) -> Result<()> {
+    let name = mesh.name().map(String::from).unwrap_or_else(|| format!("mesh {}", mesh.index()));
+    if primitive.mode() != gltf::mesh::Mode::Triangles {
+        warn!("Skipping a {:?} primitive of `{}` (only triangles are drawn).", primitive.mode(), name);
+        return Ok(());
+    }
+
+    let reader = primitive.reader(|b| buffers.get(b.index()).map(|d| &d.0[..]));
+    let positions = reader
+        .read_positions()
+        .ok_or_else(|| anyhow!("A primitive of `{}` has no positions.", name))?
+        .collect::<Vec<_>>();
+
+    // Attributes that are missing get their defaults (and normals are computed)
+    let count = positions.len();
+    let tex_coords = reader
+        .read_tex_coords(0)
+        .map(|t| t.into_f32().collect())
+        .unwrap_or_else(|| vec![[0.0; 2]; count]);
+    let colors = reader
+        .read_colors(0)
+        .map(|c| c.into_rgb_f32().collect())
+        .unwrap_or_else(|| vec![[1.0; 3]; count]);
+    let indices = reader
+        .read_indices()
+        .map(|i| i.into_u32().collect())
+        .unwrap_or_else(|| (0..count as u32).collect::<Vec<_>>());
+    let normals = match reader.read_normals() {
+        Some(normals) => normals.map(|n| vec3(n[0], n[1], n[2])).collect(),
+        None => model::compute_normals(&positions.concat(), &indices),
+    };
+
+    let (material, diffuse) = match primitive.material().index() {
+        Some(index) => (index, model.materials[index].diffuse),
+        None => {
+            model.materials.push(gltf_default_material());
+            (model.materials.len() - 1, [1.0; 3])
+        }
+    };
+
+    let first_vertex = model.vertices.len() as u32;
+    let first_index = model.indices.len() as u32;
+
+    for i in 0..count {
+        let ([x, y, z], [u, v], [r, g, b]) = (positions[i], tex_coords[i], colors[i]);
+        model.vertices.push(Vertex {
+            pos: vec3(x, y, z),
+            color: vec3(r * diffuse[0], g * diffuse[1], b * diffuse[2]),
+            tex_coord: vec2(u, v),
+            normal: normals[i],
+        });
+    }
+    model.indices.extend(indices.iter().map(|i| first_vertex + i));
+
+    model.meshes.push(Mesh {
+        name,
+        first_index,
+        index_count: indices.len() as u32,
+        material,
+    });
+
+    Ok(())
+}
+
+/// The material glTF primitives without one are drawn with.
+fn gltf_default_material() -> Material {
+    Material { metallic: 1.0, ..Default::default() }
+}
+
+fn load_material(material: &gltf::Material, images: &[Rc<Pixels>]) -> Material {
+    let texture = |texture: gltf::Texture| Some(TextureSource::Pixels(images[texture.source().index()].clone()));
+    let pbr = material.pbr_metallic_roughness();
+    let [r, g, b, _] = pbr.base_color_factor();
+
+    Material {
+        name: material.name().map(String::from).unwrap_or_else(|| format!("material {}", material.index().unwrap_or_default())),
+        diffuse: [r, g, b],
+        diffuse_texture: pbr.base_color_texture().and_then(|t| texture(t.texture())),
+        metallic: pbr.metallic_factor(),
+        roughness: pbr.roughness_factor(),
+        metallic_roughness_texture: pbr.metallic_roughness_texture().and_then(|t| texture(t.texture())),
+        normal_texture: material.normal_texture().and_then(|t| texture(t.texture())),
+        emissive: material.emissive_factor(),
+        emissive_texture: material.emissive_texture().and_then(|t| texture(t.texture())),
+    }
+}
+
+/// Converts a decoded glTF image to RGBA8 (keeping the most significant byte
+/// of 16-bit channels and clamping float channels).
+fn to_pixels(image: gltf::image::Data) -> Result<Pixels> {
+    let (channels, size) = match image.format {
+        Format::R8 => (1, 1),
+        Format::R8G8 => (2, 1),
+        Format::R8G8B8 => (3, 1),
+        Format::R8G8B8A8 => (4, 1),
+        Format::R16 => (1, 2),
+        Format::R16G16 => (2, 2),
+        Format::R16G16B16 => (3, 2),
+        Format::R16G16B16A16 => (4, 2),
+        Format::R32G32B32FLOAT => (3, 4),
+        Format::R32G32B32A32FLOAT => (4, 4),
+    };
+
+    let texels = (image.width * image.height) as usize;
+    if image.pixels.len() != texels * channels * size {
+        return Err(anyhow!("Invalid {}x{} {:?} image.", image.width, image.height, image.format));
+    }
+
+    let channel = |c: &[u8]| match size {
+        1 => c[0],
+        2 => (u16::from_ne_bytes([c[0], c[1]]) >> 8) as u8,
+        _ => (f32::from_ne_bytes([c[0], c[1], c[2], c[3]]).clamp(0.0, 1.0) * 255.0).round() as u8,
+    };
+
+    let samples = image.pixels.chunks_exact(size).map(channel).collect::<Vec<_>>();
+    let data = decode::expand_to_rgba(&samples, channels)?;
+
+    Ok(Pixels { width: image.width, height: image.height, data })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::fs;
+    use std::path::PathBuf;
+
+    use cgmath::{point3, InnerSpace, Matrix4, Transform};
+
+    /// A unit right triangle in the XY plane (positions, texture coordinates
+    /// and indices) with room for a 2x2 PNG after it.
+    fn triangle_buffer() -> Vec<u8> {
+        let floats: [f32; 15] = [
+            0.0, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0, // positions
+            0.0, 0.0, 1.0, 0.0, 0.0, 1.0, // texture coordinates
+        ];
+
+        let mut buffer = floats.iter().flat_map(|f| f.to_le_bytes()).collect::<Vec<_>>();
+        buffer.extend([0u16, 1, 2].iter().flat_map(|i| i.to_le_bytes()));
+        buffer.extend([0, 0]);
+        buffer
+    }
+
+    /// A 2x2 RGB PNG (red, green, blue and white).
+    fn png() -> Vec<u8> {
+        let mut bytes = vec![];
+        let mut encoder = png::Encoder::new(&mut bytes, 2, 2);
+        encoder.set_color(png::ColorType::Rgb);
+        let mut writer = encoder.write_header().unwrap();
+        writer.write_image_data(&[255, 0, 0, 0, 255, 0, 0, 0, 255, 255, 255, 255]).unwrap();
+        drop(writer);
+        bytes
+    }
+
+    /// The JSON of a scene with a parent node translated by 1 along X and a
+    /// child node scaled by 2 that draws the triangle with a textured material
+    /// (with the image after the triangle if its length is given).
+    fn json(buffer: &str, image: &str, image_length: Option<usize>) -> String {
+        let length = triangle_buffer().len();
+        let image_view = match image_length {
+            Some(image_length) => format!(r#",{{ "buffer": 0, "byteOffset": {}, "byteLength": {} }}"#, length, image_length),
+            None => String::new(),
+        };
+
+        format!(
+            r#"{{
+  "asset": {{ "version": "2.0" }},
+  "scene": 0,
+  "scenes": [{{ "nodes": [0] }}],
+  "nodes": [
+    {{ "name": "parent", "translation": [1, 0, 0], "children": [1] }},
+    {{ "name": "child", "scale": [2, 2, 2], "mesh": 0 }}
+  ],
+  "meshes": [{{
+    "name": "triangle",
+    "primitives": [{{ "attributes": {{ "POSITION": 0, "TEXCOORD_0": 1 }}, "indices": 2, "material": 0 }}]
+  }}],
+  "materials": [{{
+    "name": "painted",
+    "pbrMetallicRoughness": {{ "baseColorFactor": [1, 0.5, 0.25, 1], "baseColorTexture": {{ "index": 0 }}, "metallicFactor": 0.25 }},
+    "emissiveFactor": [0, 0, 1]
+  }}],
+  "textures": [{{ "source": 0 }}],
+  "images": [{image}],
+  "buffers": [{{ {buffer} "byteLength": {total} }}],
+  "bufferViews": [
+    {{ "buffer": 0, "byteOffset": 0, "byteLength": 36 }},
+    {{ "buffer": 0, "byteOffset": 36, "byteLength": 24 }},
+    {{ "buffer": 0, "byteOffset": 60, "byteLength": 6 }}{image_view}
+  ],
+  "accessors": [
+    {{ "bufferView": 0, "componentType": 5126, "count": 3, "type": "VEC3", "min": [0, 0, 0], "max": [1, 1, 0] }},
+    {{ "bufferView": 1, "componentType": 5126, "count": 3, "type": "VEC2" }},
+    {{ "bufferView": 2, "componentType": 5123, "count": 3, "type": "SCALAR" }}
+  ]
+}}"#,
+            total = length + image_length.unwrap_or_default(),
+        )
+    }
+
+    /// Writes files to a new directory in the temporary directory.
+    fn write_files(name: &str, files: &[(&str, &[u8])]) -> Result<PathBuf> {
+        let directory = std::env::temp_dir().join(format!("scene_{}_{}", name, std::process::id()));
+        fs::create_dir_all(&directory)?;
+        for (file, contents) in files {
+            fs::write(directory.join(file), contents)?;
+        }
+        Ok(directory)
+    }
+
+    fn check_scene(model: &Model) {
+        // Vertices and indices
+        assert_eq!(model.indices, [0, 1, 2]);
+        let positions = model.vertices.iter().map(|v| v.pos).collect::<Vec<_>>();
+        assert_eq!(positions, [vec3(0.0, 0.0, 0.0), vec3(1.0, 0.0, 0.0), vec3(0.0, 1.0, 0.0)]);
+        assert_eq!(model.vertices[1].tex_coord, vec2(1.0, 0.0));
+        assert_eq!(model.vertices[0].color, vec3(1.0, 0.5, 0.25));
+        assert_eq!(model.vertices[0].normal, vec3(0.0, 0.0, 1.0));
+        assert_eq!(model.meshes, [Mesh { name: "triangle".into(), first_index: 0, index_count: 3, material: 0 }]);
+
+        // Nodes (the parent is also rotated from Y-up to Z-up)
+        let names = model.nodes.iter().map(|n| (n.name.as_str(), n.parent, n.meshes.clone())).collect::<Vec<_>>();
+        assert_eq!(names, [("parent", None, vec![]), ("child", Some(0), vec![0])]);
+        assert_eq!(model.nodes[1].transform, Matrix4::from_scale(2.0));
+        let corner = model.nodes[1].world.transform_point(point3(0.0, 1.0, 0.0));
+        assert!((corner - point3(1.0, 0.0, 2.0)).magnitude() < 1e-6, "{:?}", corner);
+
+        // Materials
+        let material = &model.materials[0];
+        assert_eq!((material.name.as_str(), material.diffuse, material.metallic), ("painted", [1.0, 0.5, 0.25], 0.25));
+        assert_eq!((material.roughness, material.emissive), (1.0, [0.0, 0.0, 1.0]));
+        assert_eq!(material.normal_texture, None);
+        let pixels = Pixels {
+            width: 2,
+            height: 2,
+            data: vec![255, 0, 0, 255, 0, 255, 0, 255, 0, 0, 255, 255, 255, 255, 255, 255],
+        };
+        assert_eq!(material.diffuse_texture, Some(TextureSource::Pixels(Rc::new(pixels))));
+    }
+
+    #[test]
+    fn gltf_files_with_external_buffers_and_images() -> Result<()> {
+        let json = json(r#""uri": "triangle.bin","#, r#"{ "uri": "texture.png" }"#, None);
+        let files: &[(&str, &[u8])] = &[("scene.gltf", json.as_bytes()), ("triangle.bin", &triangle_buffer()), ("texture.png", &png())];
+        let directory = write_files("gltf", files)?;
+        let model = load_gltf(directory.join("scene.gltf"));
+        fs::remove_dir_all(&directory)?;
+
+        check_scene(&model?);
+        Ok(())
+    }
+
+    #[test]
+    fn glb_files_with_embedded_images() -> Result<()> {
+        let png = png();
+        let json = json("", r#"{ "bufferView": 3, "mimeType": "image/png" }"#, Some(png.len()));
+
+        // The binary chunk holds the triangle followed by the image (both 4-byte aligned)
+        let mut binary = triangle_buffer();
+        binary.extend(&png);
+        binary.resize(binary.len().next_multiple_of(4), 0);
+        let mut json = json.into_bytes();
+        json.resize(json.len().next_multiple_of(4), b' ');
+
+        let mut glb = b"glTF".to_vec();
+        glb.extend(2u32.to_le_bytes());
+        glb.extend((12 + 8 + json.len() as u32 + 8 + binary.len() as u32).to_le_bytes());
+        glb.extend((json.len() as u32).to_le_bytes());
+        glb.extend(b"JSON");
+        glb.extend(json);
+        glb.extend((binary.len() as u32).to_le_bytes());
+        glb.extend(b"BIN\0");
+        glb.extend(binary);
+
+        let directory = write_files("glb", &[("scene.glb", &glb)])?;
+        let model = load_gltf(directory.join("scene.glb"));
+        fs::remove_dir_all(&directory)?;
+
+        check_scene(&model?);
+        Ok(())
+    }
+
+    #[test]
+    fn images_are_converted_to_rgba() -> Result<()> {
+        let image = |format, pixels: Vec<u8>| gltf::image::Data { pixels, format, width: 1, height: 1 };
+
+        assert_eq!(to_pixels(image(Format::R8, vec![7]))?.data, [7, 7, 7, 255]);
+        assert_eq!(to_pixels(image(Format::R8G8, vec![7, 9]))?.data, [7, 7, 7, 9]);
+        let rgb16 = [0x1234u16, 0xff00, 0x0080].iter().flat_map(|c| c.to_ne_bytes()).collect();
+        assert_eq!(to_pixels(image(Format::R16G16B16, rgb16))?.data, [0x12, 0xff, 0x00, 255]);
+        let rgba32 = [0.5f32, 2.0, -1.0, 1.0].iter().flat_map(|c| c.to_ne_bytes()).collect();
+        assert_eq!(to_pixels(image(Format::R32G32B32A32FLOAT, rgba32))?.data, [128, 255, 0, 255]);
+        assert!(to_pixels(image(Format::R8G8B8A8, vec![1, 2, 3])).is_err());
+
+        Ok(())
+    }
+}
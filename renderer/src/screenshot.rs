@@ -0,0 +1,96 @@
+//================================================
+// Screenshot
+//================================================
+use crate::app_data::AppData;
+use crate::assets::Assets;
+use crate::offscreen::Frame;
+use crate::images;
+
+use std::fs::{self, File};
+use std::io::BufWriter;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use anyhow::{anyhow, Result};
+use vulkanalia::prelude::v1_0::*;
+
+/// The directory screenshots are saved to (next to the assets, see
+/// [`Assets::output`]).
+pub const SCREENSHOT_DIR: &str = "screenshots";
+
+/// Copies a presentable swapchain image (the resolved color attachment of the
+/// render pass, in `PRESENT_SRC_KHR`) back to CPU memory as RGBA8.
+pub unsafe fn capture_swapchain_image(
+    instance: &Instance,
+    device: &Rc<Device>,
+    data: &AppData,
+    image_index: usize,
+) -> Result<Frame> {
+    if !data.swapchain_usage.contains(vk::ImageUsageFlags::TRANSFER_SRC) {
+        return Err(anyhow!("Swapchain images do not support being copied from."));
+    }
+
+    let vk::Extent2D { width, height } = data.swapchain_extent;
+
+    let mut pixels = images::read_image(
+        instance,
+        device,
+        data,
+        data.swapchain_images[image_index],
+        vk::ImageLayout::PRESENT_SRC_KHR,
+        width,
+        height,
+    )?;
+
+    swizzle_to_rgba(data.swapchain_format, &mut pixels)?;
+
+    Ok(Frame { width, height, pixels })
+}
+
+/// Converts pixels in an 8-bit four channel format to opaque RGBA8 in place.
+pub fn swizzle_to_rgba(format: vk::Format, pixels: &mut [u8]) -> Result<()> {
+    let bgra = match format {
+        vk::Format::B8G8R8A8_SRGB | vk::Format::B8G8R8A8_UNORM => true,
+        vk::Format::R8G8B8A8_SRGB | vk::Format::R8G8B8A8_UNORM => false,
+        _ => return Err(anyhow!("Unsupported screenshot format ({:?}).", format)),
+    };
+
+    for pixel in pixels.chunks_exact_mut(4) {
+        if bgra {
+            pixel.swap(0, 2);
+        }
+
+        // The swapchain is composited as opaque, whatever alpha was blended to
+        pixel[3] = 255;
+    }
+
+    Ok(())
+}
+
+/// Saves a frame as a PNG file.
+pub fn save_png(frame: &Frame, path: &Path) -> Result<()> {
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+
+    let writer = BufWriter::new(File::create(path)?);
+    let mut encoder = png::Encoder::new(writer, frame.width, frame.height);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+
+    let mut writer = encoder.write_header()?;
+    writer.write_image_data(&frame.pixels)?;
+
+    Ok(())
+}
+
+/// Returns a new timestamped path in the screenshot directory.
+pub fn screenshot_path(assets: &Assets) -> PathBuf {
+    let millis = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default();
+
+    assets.output(Path::new(SCREENSHOT_DIR).join(format!("screenshot-{}.png", millis)))
+}
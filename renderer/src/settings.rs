@@ -0,0 +1,497 @@
+//================================================
+// Settings
+//================================================
+//! The user-selectable options of the apps, read from an optional config file
+//! and then the command line (which takes precedence).
+//!
+//! Both use the same keys: a config file has one `key = value` per line (`#`
+//! starts a comment), and the command line (parsed with `clap`) takes a key as
+//! `--key value` or `--key=value`. Switches are given as `--key` and `--no-key`
+//! on the command line and as `key = on` or `key = off` in a config file.
+//!
+//! The surface or device may not support what was requested, in which case
+//! the closest supported option is used and a warning is logged.
+
+use crate::assets;
+use crate::instance::VALIDATION_ENABLED;
+use crate::sync_objects::MAX_FRAMES_IN_FLIGHT;
+
+use std::fs;
+use std::iter;
+use std::path::{Path, PathBuf};
+
+use anyhow::{anyhow, Context, Result};
+use clap::{Parser, ValueEnum};
+use vulkanalia::prelude::v1_0::*;
+
+/// The config file that is read when `--config` is not given (if it is in one
+/// of the asset roots, see [`assets::find_default`]).
+pub const DEFAULT_CONFIG: &str = "settings.conf";
+
+/// A present mode that can be requested.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum PresentMode {
+    Fifo,
+    FifoRelaxed,
+    Mailbox,
+    Immediate,
+}
+
+impl From<PresentMode> for vk::PresentModeKHR {
+    fn from(mode: PresentMode) -> Self {
+        match mode {
+            PresentMode::Fifo => vk::PresentModeKHR::FIFO,
+            PresentMode::FifoRelaxed => vk::PresentModeKHR::FIFO_RELAXED,
+            PresentMode::Mailbox => vk::PresentModeKHR::MAILBOX,
+            PresentMode::Immediate => vk::PresentModeKHR::IMMEDIATE,
+        }
+    }
+}
+
+/// A swapchain format that can be requested.
+#[derive(Copy, Clone, Debug, PartialEq, Eq, ValueEnum)]
+pub enum Format {
+    Bgra8Srgb,
+    Rgba8Srgb,
+    Bgra8Unorm,
+    Rgba8Unorm,
+}
+
+impl From<Format> for vk::Format {
+    fn from(format: Format) -> Self {
+        match format {
+            Format::Bgra8Srgb => vk::Format::B8G8R8A8_SRGB,
+            Format::Rgba8Srgb => vk::Format::R8G8B8A8_SRGB,
+            Format::Bgra8Unorm => vk::Format::B8G8R8A8_UNORM,
+            Format::Rgba8Unorm => vk::Format::R8G8B8A8_UNORM,
+        }
+    }
+}
+
+/// The command line options, which are only applied when given (on top of
+/// the config file and the defaults).
+#[derive(Clone, Debug, Default, Parser)]
+#[command(
+    about = "Vulkan Tutorial (Rust)",
+    after_help = "Every option except --config is also accepted as a `key = value` line in a config file.",
+)]
+struct Args {
+    /// Read settings from PATH [default: settings.conf in the asset roots]
+    #[arg(long, value_name = "PATH")]
+    config: Option<PathBuf>,
+    /// The present mode [default: fifo with --vsync, immediate with --no-vsync]
+    #[arg(long, value_enum, value_name = "MODE")]
+    present_mode: Option<PresentMode>,
+    /// Limit presentation to the display refresh rate (fifo) when no present mode is given [default]
+    #[arg(long, overrides_with = "no_vsync")]
+    vsync: bool,
+    /// Present as soon as possible (immediate) when no present mode is given
+    #[arg(long, overrides_with = "vsync")]
+    no_vsync: bool,
+    /// The minimum number of swapchain images
+    #[arg(long, value_name = "COUNT", value_parser = parse_count)]
+    image_count: Option<u32>,
+    /// The number of frames recorded while others render [default: 2]
+    #[arg(long, value_name = "COUNT", value_parser = parse_count)]
+    frames_in_flight: Option<u32>,
+    /// The swapchain format
+    #[arg(long, value_enum)]
+    format: Option<Format>,
+    /// The size of the window
+    #[arg(long, value_name = "WIDTHxHEIGHT", value_parser = parse_size)]
+    size: Option<(u32, u32)>,
+    /// The width of the window [default: 1024]
+    #[arg(long, value_parser = parse_count)]
+    width: Option<u32>,
+    /// The height of the window [default: 768]
+    #[arg(long, value_parser = parse_count)]
+    height: Option<u32>,
+    /// Use a borderless fullscreen window
+    #[arg(long, overrides_with = "no_fullscreen")]
+    fullscreen: bool,
+    #[arg(long, overrides_with = "fullscreen", hide = true)]
+    no_fullscreen: bool,
+    /// The MSAA sample count (1, 2, 4, 8, ...) [default: the highest supported]
+    #[arg(long, value_name = "SAMPLES", value_parser = parse_msaa)]
+    msaa: Option<u32>,
+    /// Enable the Vulkan validation layers [default: in debug builds]
+    #[arg(long, overrides_with = "no_validation")]
+    validation: bool,
+    /// Disable the Vulkan validation layers
+    #[arg(long, overrides_with = "validation")]
+    no_validation: bool,
+    /// Filter mip levels on the CPU (sharper, but slower to load)
+    #[arg(long, overrides_with = "no_cpu_mipmaps")]
+    cpu_mipmaps: bool,
+    #[arg(long, overrides_with = "cpu_mipmaps", hide = true)]
+    no_cpu_mipmaps: bool,
+    /// Look for models and textures in PATH first
+    #[arg(long, value_name = "PATH")]
+    assets: Option<PathBuf>,
+    /// The model to draw, OBJ or glTF [default: resources/viking_room.obj]
+    #[arg(long, value_name = "PATH")]
+    model: Option<PathBuf>,
+    /// The texture to draw it with (demo_25 uses the model's materials unless this is given) [default: resources/viking_room.png]
+    #[arg(long, value_name = "PATH")]
+    texture: Option<PathBuf>,
+    /// Read key bindings from PATH [default: input.conf in the asset roots]
+    #[arg(long, value_name = "PATH")]
+    bindings: Option<PathBuf>,
+    /// Write the timings of every frame to PATH on exit
+    #[arg(long, value_name = "PATH")]
+    stats_csv: Option<PathBuf>,
+}
+
+/// The value of a `--key`/`--no-key` pair of switches, if either was given.
+fn switch(on: bool, off: bool) -> Option<bool> {
+    match (on, off) {
+        (true, _) => Some(true),
+        (_, true) => Some(false),
+        _ => None,
+    }
+}
+
+/// The user-selectable options of an app.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Settings {
+    /// The requested present mode (`None` to pick one based on `vsync`).
+    pub present_mode: Option<vk::PresentModeKHR>,
+    /// Whether presentation waits for vertical blanks when no present mode is
+    /// requested.
+    pub vsync: bool,
+    /// The requested minimum number of swapchain images (`None` for one more
+    /// than the surface requires).
+    pub image_count: Option<u32>,
+    /// The number of frames the CPU may record while the GPU renders others.
+    pub frames_in_flight: usize,
+    /// The requested swapchain format (`None` for `B8G8R8A8_SRGB`).
+    pub format: Option<vk::Format>,
+    pub width: u32,
+    pub height: u32,
+    pub fullscreen: bool,
+    /// The requested MSAA sample count (`None` for the highest supported).
+    pub msaa_samples: Option<u32>,
+    /// Whether the validation layers are enabled.
+    pub validation: bool,
+    /// Whether mip levels are filtered on the CPU (see [`crate::mipmaps`])
+    /// even where the GPU could blit them.
+    pub cpu_mipmaps: bool,
+    /// The directory assets are looked for in first (see [`crate::assets`]).
+    pub assets: Option<PathBuf>,
+    /// The model (`None` for [`DEFAULT_MODEL`](crate::assets::DEFAULT_MODEL)).
+    pub model: Option<PathBuf>,
+    /// The texture (`None` for [`DEFAULT_TEXTURE`](crate::assets::DEFAULT_TEXTURE)).
+    pub texture: Option<PathBuf>,
+    /// The key bindings file (`None` for [`DEFAULT_BINDINGS`](crate::input::DEFAULT_BINDINGS)).
+    pub bindings: Option<PathBuf>,
+    /// Where the frame timings are written on exit (`None` to not write them).
+    pub stats_csv: Option<PathBuf>,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Self {
+            present_mode: None,
+            vsync: true,
+            image_count: None,
+            frames_in_flight: MAX_FRAMES_IN_FLIGHT,
+            format: None,
+            width: 1024,
+            height: 768,
+            fullscreen: false,
+            msaa_samples: None,
+            validation: VALIDATION_ENABLED,
+            cpu_mipmaps: false,
+            assets: None,
+            model: None,
+            texture: None,
+            bindings: None,
+            stats_csv: None,
+        }
+    }
+}
+
+impl Settings {
+    /// Reads the settings of the process from its command line arguments (and
+    /// config file), printing the usage or the error and exiting on `--help`
+    /// or invalid arguments.
+    pub fn load() -> Result<Self> {
+        Self::from_args(Args::parse())
+    }
+
+    /// Reads settings from command line arguments (without the program name),
+    /// applied on top of the config file they name or the default one.
+    pub fn parse<I: IntoIterator<Item = String>>(args: I) -> Result<Self> {
+        Self::from_args(Args::try_parse_from(iter::once(String::new()).chain(args))?)
+    }
+
+    fn from_args(args: Args) -> Result<Self> {
+        let mut settings = Self::default();
+        if let Some(path) = args.config.clone().or_else(|| assets::find_default(DEFAULT_CONFIG)) {
+            settings.read(&path)?;
+        }
+
+        settings.apply(args);
+        Ok(settings)
+    }
+
+    /// Applies the settings of a config file.
+    pub fn read(&mut self, path: &Path) -> Result<()> {
+        let text = fs::read_to_string(path).with_context(|| format!("Failed to read `{}`.", path.display()))?;
+        self.apply_config(&text).with_context(|| format!("Invalid config file `{}`.", path.display()))
+    }
+
+    /// Applies the `key = value` lines of a config file.
+    pub fn apply_config(&mut self, text: &str) -> Result<()> {
+        for (number, key, value) in config_entries(text)? {
+            self.set(key, value).with_context(|| format!("Line {}", number))?;
+        }
+
+        Ok(())
+    }
+
+    /// Applies command line arguments (without the program name, and `--config`
+    /// is skipped).
+    pub fn apply_args(&mut self, args: &[String]) -> Result<()> {
+        self.apply(Args::try_parse_from(iter::once("").chain(args.iter().map(String::as_str)))?);
+        Ok(())
+    }
+
+    /// Applies the command line options that were given.
+    fn apply(&mut self, args: Args) {
+        let Args {
+            config: _,
+            present_mode,
+            vsync,
+            no_vsync,
+            image_count,
+            frames_in_flight,
+            format,
+            size,
+            width,
+            height,
+            fullscreen,
+            no_fullscreen,
+            msaa,
+            validation,
+            no_validation,
+            cpu_mipmaps,
+            no_cpu_mipmaps,
+            assets,
+            model,
+            texture,
+            bindings,
+            stats_csv,
+        } = args;
+
+        if let Some(present_mode) = present_mode {
+            self.present_mode = Some(present_mode.into());
+        }
+        self.vsync = switch(vsync, no_vsync).unwrap_or(self.vsync);
+        self.image_count = image_count.or(self.image_count);
+        self.frames_in_flight = frames_in_flight.map_or(self.frames_in_flight, |n| n as usize);
+        if let Some(format) = format {
+            self.format = Some(format.into());
+        }
+        if let Some((width, height)) = size {
+            (self.width, self.height) = (width, height);
+        }
+        self.width = width.unwrap_or(self.width);
+        self.height = height.unwrap_or(self.height);
+        self.fullscreen = switch(fullscreen, no_fullscreen).unwrap_or(self.fullscreen);
+        self.msaa_samples = msaa.or(self.msaa_samples);
+        self.validation = switch(validation, no_validation).unwrap_or(self.validation);
+        self.cpu_mipmaps = switch(cpu_mipmaps, no_cpu_mipmaps).unwrap_or(self.cpu_mipmaps);
+        self.assets = assets.or(self.assets.take());
+        self.model = model.or(self.model.take());
+        self.texture = texture.or(self.texture.take());
+        self.bindings = bindings.or(self.bindings.take());
+        self.stats_csv = stats_csv.or(self.stats_csv.take());
+    }
+
+    /// Sets an option from a config file by key (`-` and `_` are
+    /// interchangeable).
+    pub fn set(&mut self, key: &str, value: &str) -> Result<()> {
+        let key = key.replace('-', "_");
+        // Paths keep their case and dashes
+        let path = Some(PathBuf::from(value));
+        let value = value.to_lowercase();
+
+        let invalid = || anyhow!("Invalid value `{}` for `{}` (see `--help`).", value, key);
+        let switch = || parse_switch(&value).ok_or_else(invalid);
+        let choice = |value: &str| value.replace('_', "-");
+        match key.as_str() {
+            "present_mode" => {
+                let mode = PresentMode::from_str(&choice(&value), true).map_err(|_| invalid())?;
+                self.present_mode = Some(mode.into());
+            }
+            "vsync" => self.vsync = switch()?,
+            "image_count" => self.image_count = Some(parse_count(&value).map_err(|_| invalid())?),
+            "frames_in_flight" => self.frames_in_flight = parse_count(&value).map_err(|_| invalid())? as usize,
+            "format" => {
+                let format = Format::from_str(&choice(&value), true).map_err(|_| invalid())?;
+                self.format = Some(format.into());
+            }
+            "size" => (self.width, self.height) = parse_size(&value).map_err(|_| invalid())?,
+            "width" => self.width = parse_count(&value).map_err(|_| invalid())?,
+            "height" => self.height = parse_count(&value).map_err(|_| invalid())?,
+            "fullscreen" => self.fullscreen = switch()?,
+            "msaa" => self.msaa_samples = Some(parse_msaa(&value).map_err(|_| invalid())?),
+            "validation" => self.validation = switch()?,
+            "cpu_mipmaps" => self.cpu_mipmaps = switch()?,
+            "assets" => self.assets = path,
+            "model" => self.model = path,
+            "texture" => self.texture = path,
+            "bindings" => self.bindings = path,
+            "stats_csv" => self.stats_csv = path,
+            _ => return Err(anyhow!("Unknown option `{}` (see `--help`).", key)),
+        }
+
+        Ok(())
+    }
+}
+
+/// Splits the text of a config file into its `key = value` entries (with
+/// their line numbers), skipping blank lines and `#` comments.
+pub fn config_entries(text: &str) -> Result<Vec<(usize, &str, &str)>> {
+    let mut entries = vec![];
+    for (index, line) in text.lines().enumerate() {
+        let line = line.split('#').next().unwrap_or_default().trim();
+        if line.is_empty() {
+            continue;
+        }
+
+        let (key, value) = line
+            .split_once('=')
+            .ok_or_else(|| anyhow!("Line {}: expected `key = value`.", index + 1))?;
+        entries.push((index + 1, key.trim(), value.trim().trim_matches('"')));
+    }
+
+    Ok(entries)
+}
+
+/// Parses a count that has to be at least one.
+fn parse_count(value: &str) -> Result<u32> {
+    value.parse().ok().filter(|c| *c > 0).ok_or_else(|| anyhow!("expected a positive number"))
+}
+
+/// Parses a window size given as `<width>x<height>`.
+fn parse_size(value: &str) -> Result<(u32, u32)> {
+    let invalid = || anyhow!("expected <width>x<height>");
+    let (width, height) = value.split_once('x').ok_or_else(invalid)?;
+    Ok((parse_count(width).map_err(|_| invalid())?, parse_count(height).map_err(|_| invalid())?))
+}
+
+/// Parses an MSAA sample count (a power of two up to 64).
+fn parse_msaa(value: &str) -> Result<u32> {
+    let samples = value.parse::<u32>().ok().filter(|s| s.is_power_of_two() && *s <= 64);
+    samples.ok_or_else(|| anyhow!("expected 1, 2, 4, 8, 16, 32 or 64"))
+}
+
+fn parse_switch(value: &str) -> Option<bool> {
+    match value {
+        "true" | "yes" | "on" | "1" => Some(true),
+        "false" | "no" | "off" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// Converts a sample count to its flag (`count` has to be a power of two).
+pub fn sample_count_flags(count: u32) -> vk::SampleCountFlags {
+    vk::SampleCountFlags::from_bits_truncate(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn args(args: &[&str]) -> Vec<String> {
+        args.iter().map(|a| a.to_string()).collect()
+    }
+
+    #[test]
+    fn command_line_options() -> Result<()> {
+        let mut settings = Settings::default();
+        settings.apply_args(&args(&[
+            "--present-mode", "immediate",
+            "--image-count=3",
+            "--frames-in-flight", "3",
+            "--size", "1280x720",
+            "--fullscreen",
+            "--msaa", "4",
+            "--no-validation",
+            "--cpu-mipmaps",
+            "--format=rgba8-unorm",
+            "--model", "Models/Sponza.gltf",
+        ]))?;
+
+        assert_eq!(settings, Settings {
+            present_mode: Some(vk::PresentModeKHR::IMMEDIATE),
+            vsync: true,
+            image_count: Some(3),
+            frames_in_flight: 3,
+            format: Some(vk::Format::R8G8B8A8_UNORM),
+            width: 1280,
+            height: 720,
+            fullscreen: true,
+            msaa_samples: Some(4),
+            validation: false,
+            cpu_mipmaps: true,
+            assets: None,
+            model: Some(PathBuf::from("Models/Sponza.gltf")),
+            texture: None,
+            bindings: None,
+            stats_csv: None,
+        });
+
+        Ok(())
+    }
+
+    #[test]
+    fn config_files_are_overridden_by_the_command_line() -> Result<()> {
+        let mut settings = Settings::default();
+        settings.apply_config("# Window\nwidth = 640\nheight=480 # small\n\nvsync = off\nmsaa = \"2\"\nbindings = Keys/My-Bindings.conf\n")?;
+        settings.apply_args(&args(&["--vsync", "--width", "800"]))?;
+
+        assert_eq!((settings.width, settings.height), (800, 480));
+        assert!(settings.vsync);
+        assert_eq!(settings.msaa_samples, Some(2));
+        assert_eq!(settings.bindings, Some(PathBuf::from("Keys/My-Bindings.conf")));
+
+        Ok(())
+    }
+
+    #[test]
+    fn config_paths_are_read_before_the_other_options() -> Result<()> {
+        let dir = std::env::temp_dir().join(format!("renderer-settings-{}", std::process::id()));
+        fs::create_dir_all(&dir)?;
+        let path = dir.join("settings.conf");
+        fs::write(&path, "vsync = off
+width = 640
+")?;
+
+        let config = format!("--config={}", path.display());
+        let settings = Settings::parse(args(&["--width", "800", &config]));
+        fs::remove_dir_all(&dir)?;
+
+        let settings = settings?;
+        assert_eq!((settings.width, settings.height, settings.vsync), (800, 768, false));
+
+        Ok(())
+    }
+
+    #[test]
+    fn invalid_options_are_rejected() {
+        let mut settings = Settings::default();
+        assert!(settings.apply_args(&args(&["--present-mode", "sometimes"])).is_err());
+        assert!(settings.apply_args(&args(&["--msaa", "3"])).is_err());
+        assert!(settings.apply_args(&args(&["--image-count"])).is_err());
+        assert!(settings.apply_args(&args(&["--frames-in-flight", "0"])).is_err());
+        assert!(settings.apply_args(&args(&["--size", "wide"])).is_err());
+        assert!(settings.apply_args(&args(&["--frobnicate"])).is_err());
+        assert!(settings.apply_args(&args(&["positional"])).is_err());
+
+        let error = settings.apply_config("width = 1\nheight\n").unwrap_err();
+        assert!(error.to_string().contains("Line 2"), "{}", error);
+    }
+}
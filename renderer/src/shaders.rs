@@ -0,0 +1,286 @@
+//================================================
+// Shaders
+//================================================
+//! Shaders compiled from their GLSL sources at runtime.
+//!
+//! The sources are compiled in process with naga's GLSL front end and SPIR-V
+//! back end, and the directories they are in are watched for changes. When a
+//! source is missing the prebuilt bytecode is used, and when a source fails to
+//! compile the error is logged and the last good bytecode is kept, so a typo
+//! never takes the app down.
+
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver};
+use std::time::SystemTime;
+
+use anyhow::{anyhow, Result};
+use log::*;
+use naga::back::spv;
+use naga::front::glsl;
+use naga::valid::{Capabilities, ValidationFlags, Validator};
+use notify::{RecommendedWatcher, RecursiveMode, Watcher};
+
+pub use naga::ShaderStage;
+
+/// The compiled bytecode of a shader source and the source it is current for.
+#[derive(Clone, Debug)]
+struct Entry {
+    modified: Option<SystemTime>,
+    bytecode: Vec<u8>,
+}
+
+/// Watches the directories of the loaded sources (rather than the sources
+/// themselves, which editors often replace when saving).
+#[derive(Debug)]
+struct SourceWatcher {
+    watcher: RecommendedWatcher,
+    events: Receiver<notify::Result<notify::Event>>,
+    directories: HashSet<PathBuf>,
+}
+
+impl SourceWatcher {
+    fn new() -> Result<Self> {
+        let (sender, events) = mpsc::channel();
+        let watcher = notify::recommended_watcher(sender)?;
+        Ok(Self { watcher, events, directories: HashSet::new() })
+    }
+
+    fn watch(&mut self, path: &Path) -> Result<()> {
+        let directory = canonical(path).parent().map(Path::to_path_buf).unwrap_or_default();
+        if !self.directories.contains(&directory) {
+            self.watcher.watch(&directory, RecursiveMode::NonRecursive)?;
+            self.directories.insert(directory);
+        }
+
+        Ok(())
+    }
+
+    /// The sources (as [`canonical`] paths) with events since the last call.
+    fn touched(&self) -> HashSet<PathBuf> {
+        self.events
+            .try_iter()
+            .filter_map(|e| e.ok())
+            .flat_map(|e| e.paths)
+            .map(|p| canonical(&p))
+            .collect()
+    }
+}
+
+/// The shaders compiled so far, keyed by the path of their source.
+#[derive(Debug, Default)]
+pub struct ShaderCache {
+    entries: HashMap<PathBuf, Entry>,
+    watcher: Option<SourceWatcher>,
+}
+
+impl ShaderCache {
+    /// Returns the SPIR-V bytecode for a shader source, compiling it if it
+    /// changed since it was last loaded and falling back to `prebuilt`.
+    pub fn load(&mut self, path: &Path, stage: ShaderStage, prebuilt: &[u8]) -> Vec<u8> {
+        let modified = match fs::metadata(path).and_then(|m| m.modified()) {
+            Ok(modified) => Some(modified),
+            Err(e) => {
+                debug!("Using the prebuilt shader for `{}` ({}).", path.display(), e);
+                return prebuilt.to_vec();
+            }
+        };
+
+        if let Err(e) = self.watch(path) {
+            warn!("Failed to watch `{}` for changes: {}", path.display(), e);
+        }
+
+        if let Some(entry) = self.entries.get(path) {
+            if entry.modified == modified {
+                return entry.bytecode.clone();
+            }
+        }
+
+        let bytecode = match compile(path, stage) {
+            Ok(bytecode) => {
+                info!("Compiled shader `{}`.", path.display());
+                bytecode
+            }
+            Err(e) => {
+                error!("{}", e);
+                match self.entries.get(path) {
+                    Some(entry) => entry.bytecode.clone(),
+                    None => prebuilt.to_vec(),
+                }
+            }
+        };
+
+        // Failed compiles are remembered too, to only retry once the source changes
+        let entry = Entry { modified, bytecode: bytecode.clone() };
+        self.entries.insert(path.to_path_buf(), entry);
+
+        bytecode
+    }
+
+    /// Returns whether a loaded shader source was modified since it was last
+    /// loaded (going by the events of the watched directories since the
+    /// previous call).
+    pub fn changed(&mut self) -> bool {
+        let Some(watcher) = &self.watcher else {
+            return false;
+        };
+
+        let touched = watcher.touched();
+        self.entries.iter().any(|(path, entry)| {
+            let modified = fs::metadata(path).and_then(|m| m.modified()).ok();
+            touched.contains(&canonical(path)) && modified.is_some() && modified != entry.modified
+        })
+    }
+
+    fn watch(&mut self, path: &Path) -> Result<()> {
+        let watcher = match &mut self.watcher {
+            Some(watcher) => watcher,
+            None => self.watcher.insert(SourceWatcher::new()?),
+        };
+
+        watcher.watch(path)
+    }
+}
+
+/// Compiles a GLSL shader source to SPIR-V bytecode.
+pub fn compile(path: &Path, stage: ShaderStage) -> Result<Vec<u8>> {
+    let name = path.display().to_string();
+    let source = fs::read_to_string(path).map_err(|e| anyhow!("Failed to read `{}`: {}", name, e))?;
+
+    let module = glsl::Frontend::default()
+        .parse(&glsl::Options::from(stage), &source)
+        .map_err(|e| anyhow!("Failed to compile `{}`:\n{}", name, e.emit_to_string_with_path(&source, &name)))?;
+
+    let info = Validator::new(ValidationFlags::all(), Capabilities::all())
+        .validate(&module)
+        .map_err(|e| anyhow!("Failed to compile `{}`:\n{}", name, e.emit_to_string_with_path(&source, &name)))?;
+
+    // The sources are written for Vulkan, so their coordinates are kept as-is
+    let options = spv::Options { flags: spv::WriterFlags::DEBUG, ..Default::default() };
+    let words = spv::write_vec(&module, &info, &options, None)
+        .map_err(|e| anyhow!("Failed to compile `{}`: {}", name, e))?;
+
+    Ok(words.iter().flat_map(|w| w.to_le_bytes()).collect())
+}
+
+/// A path with its directory canonicalized, so the paths a source is loaded
+/// with and the ones in watcher events compare equal (even once it is removed).
+fn canonical(path: &Path) -> PathBuf {
+    let directory = match path.parent() {
+        Some(directory) if !directory.as_os_str().is_empty() => directory,
+        _ => Path::new("."),
+    };
+
+    let directory = directory.canonicalize().unwrap_or_else(|_| directory.to_path_buf());
+    match path.file_name() {
+        Some(name) => directory.join(name),
+        None => directory,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use std::env;
+    use std::fs::File;
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    const PREBUILT: &[u8] = &[3, 2, 35, 7];
+
+    const SPIRV_MAGIC: u32 = 0x07230203;
+
+    const FRAGMENT: &str = "#version 450
+layout(location = 0) in vec3 fragColor;
+layout(location = 0) out vec4 outColor;
+void main() { outColor = vec4(fragColor, 1.0); }
+";
+
+    fn is_spirv(bytecode: &[u8]) -> bool {
+        bytecode.len().is_multiple_of(4) && bytecode.get(..4) == Some(&SPIRV_MAGIC.to_le_bytes()[..])
+    }
+
+    /// Waits (for a few seconds at most) until the watcher saw a change.
+    fn wait_for_change(cache: &mut ShaderCache) -> bool {
+        let start = Instant::now();
+        while start.elapsed() < Duration::from_secs(5) {
+            if cache.changed() {
+                return true;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+        false
+    }
+
+    #[test]
+    fn missing_sources_use_the_prebuilt_bytecode() {
+        let mut cache = ShaderCache::default();
+        let path = Path::new("./does/not/exist.vert");
+
+        assert_eq!(cache.load(path, ShaderStage::Vertex, PREBUILT), PREBUILT);
+        assert!(!cache.changed());
+    }
+
+    #[test]
+    fn the_workspace_shaders_compile() -> Result<()> {
+        // The sources of the chapters whose pipelines are built by this crate
+        let shaders = Path::new(env!("CARGO_MANIFEST_DIR")).join("../shaders");
+        for directory in ["20", "23"].map(|d| shaders.join(d)) {
+            let vert = compile(&directory.join("shader.vert"), ShaderStage::Vertex)?;
+            let frag = compile(&directory.join("shader.frag"), ShaderStage::Fragment)?;
+            assert!(is_spirv(&vert) && is_spirv(&frag), "{}", directory.display());
+
+            // The prebuilt bytecode is what the sources compile to
+            assert_eq!(vert, fs::read(directory.join("vert.spv"))?, "{}", directory.display());
+            assert_eq!(frag, fs::read(directory.join("frag.spv"))?, "{}", directory.display());
+        }
+
+        Ok(())
+    }
+
+    #[test]
+    fn compile_errors_name_the_source() -> Result<()> {
+        let dir = env::temp_dir().join(format!("renderer-shaders-errors-{}", std::process::id()));
+        fs::create_dir_all(&dir)?;
+        let path = dir.join("broken.frag");
+        fs::write(&path, "#version 450\nvoid main() { this is not glsl }\n")?;
+
+        let error = compile(&path, ShaderStage::Fragment).unwrap_err().to_string();
+        fs::remove_dir_all(&dir)?;
+
+        assert!(error.starts_with(&format!("Failed to compile `{}`", path.display())), "{}", error);
+        assert!(error.contains("broken.frag:2:"), "{}", error);
+        Ok(())
+    }
+
+    #[test]
+    fn broken_sources_are_only_compiled_again_once_changed() -> Result<()> {
+        let dir = env::temp_dir().join(format!("renderer-shaders-{}", std::process::id()));
+        fs::create_dir_all(&dir)?;
+        let path = dir.join("shader.frag");
+        fs::write(&path, "#version 450\nvoid main() { this is not glsl }\n")?;
+
+        let mut cache = ShaderCache::default();
+        assert_eq!(cache.load(&path, ShaderStage::Fragment, PREBUILT), PREBUILT);
+        assert!(!cache.changed());
+
+        // Fixing the source is seen by the watcher and compiles it
+        fs::write(&path, FRAGMENT)?;
+        let modified = SystemTime::now() + Duration::from_secs(10);
+        File::options().write(true).open(&path)?.set_modified(modified)?;
+        let changed = wait_for_change(&mut cache);
+        let bytecode = cache.load(&path, ShaderStage::Fragment, PREBUILT);
+        let unchanged = !cache.changed();
+        let compiled = compile(&path, ShaderStage::Fragment)?;
+
+        fs::remove_dir_all(&dir)?;
+
+        assert!(changed);
+        assert!(is_spirv(&bytecode));
+        assert_eq!(bytecode, compiled);
+        assert!(unchanged);
+        Ok(())
+    }
+}
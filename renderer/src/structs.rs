@@ -13,8 +13,8 @@ use anyhow::{anyhow, Result};
 use vulkanalia::prelude::v1_0::*;
 use vulkanalia::vk::KhrSurfaceExtension;
 
-type Vec2 = cgmath::Vector2<f32>;
-type Vec3 = cgmath::Vector3<f32>;
+pub type Vec2 = cgmath::Vector2<f32>;
+pub type Vec3 = cgmath::Vector3<f32>;
 pub type Mat4 = cgmath::Matrix4<f32>;
 
 #[derive(Copy, Clone, Debug)]
@@ -33,16 +33,20 @@ impl QueueFamilyIndices {
             .position(|p| p.queue_flags.contains(vk::QueueFlags::GRAPHICS))
             .map(|i| i as u32);
 
-        // present
+        // present (headless apps have no surface, the graphics queue stands in)
         let mut present = None;
-        for (index, properties) in properties.iter().enumerate() {
-            if instance.get_physical_device_surface_support_khr(
-                physical_device,
-                index as u32,
-                data.surface,
-            )? {
-                present = Some(index as u32);
-                break;
+        if data.headless {
+            present = graphics;
+        } else {
+            for (index, properties) in properties.iter().enumerate() {
+                if instance.get_physical_device_surface_support_khr(
+                    physical_device,
+                    index as u32,
+                    data.surface,
+                )? {
+                    present = Some(index as u32);
+                    break;
+                }
             }
         }
         
@@ -81,24 +85,18 @@ impl SwapchainSupport {
     }
 }
 
-#[repr(C)]
-#[derive(Copy, Clone, Debug)]
-pub struct UniformBufferObject {
-    pub view:  Mat4,
-    pub proj:  Mat4,
-}
-
 #[repr(C)]
 #[derive(Copy, Clone, Debug)]
 pub struct Vertex {
     pub pos:       Vec3,
     pub color:     Vec3,
     pub tex_coord: Vec2,
+    pub normal:    Vec3,
 }
 
 impl Vertex {
-    pub fn new(pos: Vec3, color: Vec3, tex_coord: Vec2) -> Self {
-        Self { pos, color, tex_coord }
+    pub fn new(pos: Vec3, color: Vec3, tex_coord: Vec2, normal: Vec3) -> Self {
+        Self { pos, color, tex_coord, normal }
     }
 
     pub fn binding_description() -> vk::VertexInputBindingDescription {
@@ -109,7 +107,7 @@ impl Vertex {
             .build()
     }
 
-    pub fn attribute_descriptions() -> [vk::VertexInputAttributeDescription; 3] {
+    pub fn attribute_descriptions() -> [vk::VertexInputAttributeDescription; 4] {
         let pos = vk::VertexInputAttributeDescription::builder()
             .binding(0)
             .location(0)
@@ -128,15 +126,24 @@ impl Vertex {
             .format(vk::Format::R32G32_SFLOAT)
             .offset((size_of::<Vec3>() + size_of::<Vec3>()) as u32)
             .build();
+        let normal = vk::VertexInputAttributeDescription::builder()
+            .binding(0)
+            .location(3)
+            .format(vk::Format::R32G32B32_SFLOAT)
+            .offset((size_of::<Vec3>() + size_of::<Vec3>() + size_of::<Vec2>()) as u32)
+            .build();
         
-        [pos, color, tex_coord]
+        [pos, color, tex_coord, normal]
     }
 }
 
 
 impl PartialEq for Vertex {
     fn eq(&self, other: &Self) -> bool {
-        self.pos == other.pos && self.color == other.color && self.tex_coord == other.tex_coord
+        self.pos == other.pos
+            && self.color == other.color
+            && self.tex_coord == other.tex_coord
+            && self.normal == other.normal
     }
 }
 
@@ -152,5 +159,8 @@ impl Hash for Vertex {
         self.color[2].to_bits().hash(state);
         self.tex_coord[0].to_bits().hash(state);
         self.tex_coord[1].to_bits().hash(state);
+        self.normal[0].to_bits().hash(state);
+        self.normal[1].to_bits().hash(state);
+        self.normal[2].to_bits().hash(state);
     }
 }
\ No newline at end of file
@@ -0,0 +1,163 @@
+use crate::app_data::AppData;
+use crate::structs::{QueueFamilyIndices, SwapchainSupport};
+use crate::images;
+use crate::owned::Owned;
+use crate::settings::Settings;
+
+use std::mem;
+use std::rc::Rc;
+
+use anyhow::Result;
+use log::*;
+use winit::window::Window;
+use vulkanalia::prelude::v1_0::*;
+use vulkanalia::vk::KhrSwapchainExtension;
+
+pub unsafe fn create_swapchain(window: &Window, instance: &Instance, device: &Rc<Device>, data: &mut AppData) -> Result<()> {
+    // The previous swapchain is handed to the new one and kept (retired) until
+    // the next recreation, so presents still queued on it can finish
+    data.swapchain_image_views.clear();
+    let old_swapchain = mem::take(&mut data.swapchain);
+
+    // Image
+    let indices = QueueFamilyIndices::get(instance, data, data.physical_device)?;
+    let support = SwapchainSupport::get(instance, data, data.physical_device)?;
+
+    let settings = &data.settings;
+    let surface_format = get_swapchain_surface_format(&support.formats, settings);
+    let present_mode = get_swapchain_present_mode(&support.present_modes, settings);
+    let image_count = get_swapchain_image_count(support.capabilities, settings);
+    let extent = get_swapchain_extent(window, support.capabilities);
+
+    data.swapchain_format = surface_format.format;
+    data.swapchain_extent = extent;
+
+    // Screenshots copy out of the swapchain images when the surface allows it
+    let mut image_usage = vk::ImageUsageFlags::COLOR_ATTACHMENT;
+    if support.capabilities.supported_usage_flags.contains(vk::ImageUsageFlags::TRANSFER_SRC) {
+        image_usage |= vk::ImageUsageFlags::TRANSFER_SRC;
+    }
+
+    let mut queue_family_indices = vec![];
+    let image_sharing_mode = if indices.graphics != indices.present {
+        queue_family_indices.push(indices.graphics);
+        queue_family_indices.push(indices.present);
+        vk::SharingMode::CONCURRENT
+    } else {
+        vk::SharingMode::EXCLUSIVE
+    };
+
+    // Create
+    let info = vk::SwapchainCreateInfoKHR::builder()
+        .surface(data.surface)
+        .min_image_count(image_count)
+        .image_format(surface_format.format)
+        .image_color_space(surface_format.color_space)
+        .image_extent(extent)
+        .image_array_layers(1)
+        .image_usage(image_usage)
+        .image_sharing_mode(image_sharing_mode)
+        .queue_family_indices(&queue_family_indices)
+        .pre_transform(support.capabilities.current_transform)
+        .composite_alpha(vk::CompositeAlphaFlagsKHR::OPAQUE)
+        .present_mode(present_mode)
+        .clipped(true)
+        .old_swapchain(*old_swapchain);
+
+    data.swapchain = Owned::new(device, device.create_swapchain_khr(&info, None)?);
+    data.swapchain_usage = image_usage;
+    data.retired_swapchain = old_swapchain;
+
+    // Images
+    data.swapchain_images = device.get_swapchain_images_khr(*data.swapchain)?;
+
+    Ok(())
+}
+
+fn get_swapchain_surface_format(formats: &[vk::SurfaceFormatKHR], settings: &Settings) -> vk::SurfaceFormatKHR {
+    let find = |format| formats
+        .iter()
+        .cloned()
+        .find(|f| {
+            f.format == format &&
+            f.color_space == vk::ColorSpaceKHR::SRGB_NONLINEAR});
+
+    if let Some(format) = settings.format {
+        match find(format) {
+            Some(surface_format) => return surface_format,
+            None => warn!("Swapchain format {:?} is not supported by the surface, falling back.", format),
+        }
+    }
+
+    find(vk::Format::B8G8R8A8_SRGB).unwrap_or_else(|| formats[0])
+}
+
+fn get_swapchain_present_mode(present_modes: &[vk::PresentModeKHR], settings: &Settings) -> vk::PresentModeKHR {
+    let requested = match settings.present_mode {
+        Some(present_mode) => present_mode,
+        // MAILBOX does not wait for vertical blanks, only for an explicit request
+        None if settings.vsync => vk::PresentModeKHR::FIFO,
+        None => vk::PresentModeKHR::IMMEDIATE,
+    };
+
+    // FIFO is the only mode every surface supports
+    if present_modes.contains(&requested) {
+        requested
+    } else {
+        warn!("Present mode {:?} is not supported by the surface, falling back to FIFO.", requested);
+        vk::PresentModeKHR::FIFO
+    }
+}
+
+fn get_swapchain_image_count(capabilities: vk::SurfaceCapabilitiesKHR, settings: &Settings) -> u32 {
+    let requested = settings.image_count.unwrap_or(capabilities.min_image_count + 1);
+
+    let mut image_count = requested.max(capabilities.min_image_count);
+    if capabilities.max_image_count != 0 && image_count > capabilities.max_image_count {
+        image_count = capabilities.max_image_count;
+    }
+
+    if settings.image_count.is_some() && image_count != requested {
+        warn!("{} swapchain images requested but the surface needs {} to {}, using {}.",
+            requested, capabilities.min_image_count, capabilities.max_image_count, image_count);
+    }
+
+    image_count
+}
+
+fn get_swapchain_extent(window: &Window, capabilities: vk::SurfaceCapabilitiesKHR) -> vk::Extent2D {
+    if capabilities.current_extent.width != u32::max_value() {
+        capabilities.current_extent
+    } else {
+        let size = window.inner_size();
+        let clamp = |min: u32, max: u32, v: u32| min.max(max.min(v));
+        vk::Extent2D::builder()
+            .width(clamp(
+                capabilities.min_image_extent.width,
+                capabilities.max_image_extent.width,
+                size.width,
+            ))
+            .height(clamp(
+                capabilities.min_image_extent.height,
+                capabilities.max_image_extent.height,
+                size.height,
+            ))
+            .build()
+    }
+}
+
+pub unsafe fn create_swapchain_image_views(device: &Rc<Device>, data: &mut AppData) -> Result<()> {
+    data.swapchain_image_views = data
+        .swapchain_images
+        .iter()
+        .map(|i| images::create_image_view(
+            device, 
+            *i, 
+            data.swapchain_format, 
+            vk::ImageAspectFlags::COLOR, 
+            1
+        ))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    Ok(())
+}
\ No newline at end of file
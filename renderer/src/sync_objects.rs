@@ -0,0 +1,43 @@
+//================================================
+// Sync Objects
+//================================================
+use crate::app_data::AppData;
+use crate::owned::Owned;
+
+use std::rc::Rc;
+
+use anyhow::Result;
+use vulkanalia::prelude::v1_0::*;
+
+/// The default number of frames that can be processed concurrently (see
+/// [`Settings::frames_in_flight`](crate::settings::Settings::frames_in_flight)).
+pub const MAX_FRAMES_IN_FLIGHT: usize = 2;
+
+/// Creates the semaphores and fence of each frame in flight.
+pub unsafe fn create_sync_objects(device: &Rc<Device>, data: &mut AppData) -> Result<()> {
+    let semaphore_info = vk::SemaphoreCreateInfo::builder();
+    let fence_info = vk::FenceCreateInfo::builder().flags(vk::FenceCreateFlags::SIGNALED);
+
+    for _ in 0..data.settings.frames_in_flight {
+        data.image_available_semaphores
+            .push(Owned::new(device, device.create_semaphore(&semaphore_info, None)?));
+        data.render_finished_semaphores
+            .push(Owned::new(device, device.create_semaphore(&semaphore_info, None)?));
+
+        data.in_flight_fences.push(Owned::new(device, device.create_fence(&fence_info, None)?));
+    }
+
+    Ok(())
+}
+
+/// Waits until the GPU finished every frame in flight, which covers all the
+/// work that uses the swapchain images and the resources created with them
+/// (without waiting for the whole device to idle).
+pub unsafe fn wait_for_frames(device: &Device, data: &AppData) -> Result<()> {
+    let fences = data.in_flight_fences.iter().map(|f| **f).collect::<Vec<_>>();
+    if !fences.is_empty() {
+        device.wait_for_fences(&fences, true, u64::MAX)?;
+    }
+
+    Ok(())
+}
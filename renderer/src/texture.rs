@@ -0,0 +1,328 @@
+//================================================
+// Texture
+//================================================
+use crate::app_data::AppData;
+use crate::buffers;
+use crate::compressed;
+use crate::decode::{self, Pixels};
+use crate::images;
+use crate::mipmaps::{self, MipFilter};
+use crate::owned::{Image, Owned};
+
+use std::path::{Path, PathBuf};
+use std::ptr::copy_nonoverlapping as memcpy;
+use std::rc::Rc;
+
+use anyhow::Result;
+use log::*;
+use vulkanalia::prelude::v1_0::*;
+
+/// Where the image of a texture comes from.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum TextureSource {
+    /// An image file.
+    File(PathBuf),
+    /// An image that was already decoded (e.g. embedded in a glTF file).
+    Pixels(Rc<Pixels>),
+}
+
+/// What the texels of a texture are, which decides whether they are sampled
+/// as sRGB (and converted to linear) or as is.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum TextureUsage {
+    /// Colors (e.g. base color or emissive textures).
+    Color,
+    /// Linear data (e.g. normal or metallic-roughness textures).
+    Data,
+}
+
+impl TextureUsage {
+    /// The format RGBA8 textures with this usage are created in.
+    pub fn format(self) -> vk::Format {
+        match self {
+            Self::Color => vk::Format::R8G8B8A8_SRGB,
+            Self::Data => vk::Format::R8G8B8A8_UNORM,
+        }
+    }
+}
+
+/// A sampled image, its view, format and number of mip levels.
+#[derive(Debug, Default)]
+pub struct Texture {
+    pub image:      Image,
+    pub view:       Owned<vk::ImageView>,
+    pub format:     vk::Format,
+    pub mip_levels: u32,
+}
+
+/// Creates a texture from pixels in the format of its usage, with a full mip
+/// chain if `mipmaps` is set (or just the base level otherwise).
+pub unsafe fn create_texture(
+    instance: &Instance,
+    device: &Rc<Device>,
+    data: &AppData,
+    pixels: &Pixels,
+    usage: TextureUsage,
+    mipmaps: bool,
+) -> Result<Texture> {
+    let format = usage.format();
+    let (image, mip_levels) = upload_texture_image(instance, device, data, pixels, format, mipmaps)?;
+    let view = images::create_image_view(device, *image, format, vk::ImageAspectFlags::COLOR, mip_levels)?;
+
+    Ok(Texture { image, view, format, mip_levels })
+}
+
+/// Loads a PNG or JPEG (of any size and color type) into the texture image,
+/// with a full mip chain if `mipmaps` is set (or just the base level otherwise).
+///
+/// KTX2 and DDS files are uploaded in their own format with the mip levels
+/// they contain (only the first layer of array textures is sampled).
+pub unsafe fn create_texture_image(
+    instance: &Instance,
+    device: &Rc<Device>,
+    data: &mut AppData,
+    path: &Path,
+    mipmaps: bool,
+) -> Result<()> {
+    if compressed::is_compressed_file(path) {
+        let image = compressed::load_compressed(path)?;
+        data.texture_image = compressed::upload_compressed_image(instance, device, data, &image)?;
+        data.texture_format = image.format;
+        data.mip_levels = image.mip_levels();
+
+        return Ok(());
+    }
+
+    let pixels = decode::load_image(path)?;
+    let format = TextureUsage::Color.format();
+    let (image, mip_levels) = upload_texture_image(instance, device, data, &pixels, format, mipmaps)?;
+
+    data.texture_image = image;
+    data.texture_format = format;
+    data.mip_levels = mip_levels;
+
+    Ok(())
+}
+
+/// Creates a texture for each material: its diffuse texture, or a single
+/// texel of its diffuse color if it has none (or it fails to load).
+pub unsafe fn create_material_textures(instance: &Instance, device: &Rc<Device>, data: &mut AppData) -> Result<()> {
+    let mut textures = Vec::with_capacity(data.materials.len());
+
+    for material in &data.materials {
+        let texture = material.diffuse_texture.as_ref().and_then(|source| {
+            create_material_texture(instance, device, data, source, TextureUsage::Color)
+                .map_err(|e| warn!("Material `{}` is drawn without its texture: {:#}", material.name, e))
+                .ok()
+        });
+
+        let texture = match texture {
+            Some(texture) => texture,
+            None => {
+                let [r, g, b] = material.diffuse.map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8);
+                let pixels = Pixels { width: 1, height: 1, data: vec![r, g, b, 255] };
+                create_texture(instance, device, data, &pixels, TextureUsage::Color, true)?
+            }
+        };
+
+        textures.push(texture);
+    }
+
+    data.material_textures = textures;
+
+    Ok(())
+}
+
+/// Creates a texture of a material in the format of its usage (KTX2 and DDS
+/// files keep the format they are stored in).
+unsafe fn create_material_texture(
+    instance: &Instance,
+    device: &Rc<Device>,
+    data: &AppData,
+    source: &TextureSource,
+    usage: TextureUsage,
+) -> Result<Texture> {
+    let pixels = match source {
+        TextureSource::File(path) if compressed::is_compressed_file(path) => {
+            let image = compressed::load_compressed(path)?;
+            if image.layers > 1 {
+                warn!("Only the first of the {} layers of `{}` is drawn.", image.layers, path.display());
+            }
+
+            return compressed::create_compressed_texture(instance, device, data, &image);
+        }
+        TextureSource::File(path) => Rc::new(decode::load_image(path)?),
+        TextureSource::Pixels(pixels) => pixels.clone(),
+    };
+
+    create_texture(instance, device, data, &pixels, usage, true)
+}
+
+/// Copies pixels into a new image (in an RGBA8 format) ready for sampling, with
+/// a full mip chain if `mipmaps` is set, and returns it with its number of mip
+/// levels.
+///
+/// The mip levels are blitted on the GPU, or filtered on the CPU (and uploaded
+/// with the image) if the format cannot be blitted or `--cpu-mipmaps` is set.
+unsafe fn upload_texture_image(
+    instance: &Instance,
+    device: &Rc<Device>,
+    data: &AppData,
+    pixels: &Pixels,
+    format: vk::Format,
+    mipmaps: bool,
+) -> Result<(Image, u32)> {
+    let (width, height) = (pixels.width, pixels.height);
+
+    let mip_levels = if mipmaps { mipmaps::mip_levels(width, height) } else { 1 };
+
+    let blit = mipmaps && !data.settings.cpu_mipmaps && images::supports_linear_blit(instance, data, format);
+    let smaller_levels = if mipmaps && !blit {
+        if !data.settings.cpu_mipmaps {
+            info!("{:?} images cannot be blitted, their mip levels are filtered on the CPU.", format);
+        }
+        let srgb = format == TextureUsage::Color.format();
+        mipmaps::generate_mip_chain(pixels, srgb, MipFilter::Kaiser)
+    } else {
+        vec![]
+    };
+    let levels = std::iter::once(pixels).chain(&smaller_levels).collect::<Vec<_>>();
+
+    // ----------------------------------------
+    // Texture image
+    // ----------------------------------------
+    // 1. Create a staging buffer visible to the cpu (with every level that is not blitted)
+    let size = levels.iter().map(|l| l.data.len()).sum::<usize>();
+    let staging_buffer = buffers::create_buffer(
+        instance,
+        device,
+        data,
+        size as u64,
+        vk::BufferUsageFlags::TRANSFER_SRC,
+        vk::MemoryPropertyFlags::HOST_COHERENT | vk::MemoryPropertyFlags::HOST_VISIBLE,
+    )?;
+
+    // 2. Copy data to staging buffer
+    let mut regions = Vec::with_capacity(levels.len());
+    let mut offset = 0;
+    for (level, pixels) in levels.iter().enumerate() {
+        memcpy(pixels.data.as_ptr(), staging_buffer.memory().mapped.cast::<u8>().add(offset), pixels.data.len());
+
+        let subresource = vk::ImageSubresourceLayers::builder()
+            .aspect_mask(vk::ImageAspectFlags::COLOR)
+            .mip_level(level as u32)
+            .base_array_layer(0)
+            .layer_count(1);
+
+        regions.push(
+            vk::BufferImageCopy::builder()
+                .buffer_offset(offset as u64)
+                .image_subresource(subresource)
+                .image_extent(vk::Extent3D { width: pixels.width, height: pixels.height, depth: 1 })
+                .build(),
+        );
+        offset += pixels.data.len();
+    }
+
+    // 3. Create texture image object (blitted mip levels are read from each other)
+    let mut usage = vk::ImageUsageFlags::SAMPLED | vk::ImageUsageFlags::TRANSFER_DST;
+    if blit {
+        usage |= vk::ImageUsageFlags::TRANSFER_SRC;
+    }
+
+    let image = images::create_image(
+        instance,
+        device,
+        data,
+        width,
+        height,
+        mip_levels,
+        vk::SampleCountFlags::_1,
+        format,
+        vk::ImageTiling::OPTIMAL,
+        usage,
+        vk::MemoryPropertyFlags::DEVICE_LOCAL,
+    )?;
+
+    // ----------------------------------------
+    // Layout transition
+    // ----------------------------------------
+    // Transition + Copy (image)
+    images::transition_image_layout(
+        device,
+        data,
+        *image,
+        format,
+        vk::ImageLayout::UNDEFINED,
+        vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+        mip_levels,
+    )?;
+
+    // Copy buffer to image (a region per level)
+    images::copy_buffer_to_image_regions(device, data, *staging_buffer, *image, &regions)?;
+
+    // Cleanup
+    drop(staging_buffer);
+
+    // Mipmaps (which also leaves every level ready for sampling)
+    if blit {
+        images::generate_mipmaps(
+            instance,
+            device,
+            data,
+            *image,
+            format,
+            width,
+            height,
+            mip_levels,
+        )?;
+    } else {
+        images::transition_image_layout(
+            device,
+            data,
+            *image,
+            format,
+            vk::ImageLayout::TRANSFER_DST_OPTIMAL,
+            vk::ImageLayout::SHADER_READ_ONLY_OPTIMAL,
+            mip_levels,
+        )?;
+    }
+
+    Ok((image, mip_levels))
+}
+
+pub unsafe fn create_texture_image_view(device: &Rc<Device>, data: &mut AppData) -> Result<()> {
+    data.texture_image_view = images::create_image_view(
+        device,
+        *data.texture_image,
+        data.texture_format,
+        vk::ImageAspectFlags::COLOR,
+        data.mip_levels,
+    )?;
+
+    Ok(())
+}
+
+pub unsafe fn create_texture_sampler(device: &Rc<Device>, data: &mut AppData) -> Result<()> {
+    let info = vk::SamplerCreateInfo::builder()
+        .mag_filter(vk::Filter::LINEAR)
+        .min_filter(vk::Filter::LINEAR)
+        .address_mode_u(vk::SamplerAddressMode::REPEAT)
+        .address_mode_v(vk::SamplerAddressMode::REPEAT)
+        .address_mode_w(vk::SamplerAddressMode::REPEAT)
+        .anisotropy_enable(true)
+        .max_anisotropy(16.0)
+        .border_color(vk::BorderColor::INT_OPAQUE_BLACK)
+        .unnormalized_coordinates(false)
+        .compare_enable(false)
+        .compare_op(vk::CompareOp::ALWAYS)
+        .mipmap_mode(vk::SamplerMipmapMode::LINEAR)
+        .min_lod(0.0)
+        // Every mip level of each texture (which may have different numbers)
+        .max_lod(vk::LOD_CLAMP_NONE)
+        .mip_lod_bias(0.0);
+
+    data.texture_sampler = Owned::new(device, device.create_sampler(&info, None)?);
+
+    Ok(())
+}
\ No newline at end of file
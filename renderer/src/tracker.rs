@@ -0,0 +1,129 @@
+//================================================
+// Tracker
+//================================================
+//! Bookkeeping of the live [`Owned`](crate::owned::Owned) handles in debug
+//! builds, to find leaks and handles that are destroyed twice.
+//!
+//! Every owned handle is recorded with the call site that created it (through
+//! `#[track_caller]` on the creating functions) and removed again when it is
+//! destroyed. Tracking is tied to [`VALIDATION_ENABLED`] and does nothing in
+//! release builds.
+
+use crate::instance::VALIDATION_ENABLED;
+
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::panic::Location;
+
+use log::*;
+use vulkanalia::prelude::v1_0::*;
+
+/// Whether owned handles are tracked.
+pub const TRACKING_ENABLED: bool = VALIDATION_ENABLED;
+
+/// A live handle and where it was created.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct Tracked {
+    pub object_type: vk::ObjectType,
+    pub handle: u64,
+    pub location: &'static Location<'static>,
+}
+
+thread_local! {
+    static LIVE: RefCell<HashMap<(vk::ObjectType, u64), &'static Location<'static>>> = RefCell::new(HashMap::new());
+}
+
+/// Records a handle that was just created at `location`.
+pub fn track<T: Handle<Repr = u64>>(handle: T, location: &'static Location<'static>) {
+    if !TRACKING_ENABLED || handle.is_null() {
+        return;
+    }
+
+    let key = (T::TYPE, handle.as_raw());
+    if let Some(previous) = LIVE.with(|l| l.borrow_mut().insert(key, location)) {
+        error!("{:?} {:#x} created at {} is already owned (created at {}).", T::TYPE, key.1, location, previous);
+    }
+}
+
+/// Forgets a handle that is about to be destroyed, returning `false` (and
+/// logging an error) if it is not live, i.e. it was already destroyed.
+pub fn untrack<T: Handle<Repr = u64>>(handle: T) -> bool {
+    if !TRACKING_ENABLED || handle.is_null() {
+        return true;
+    }
+
+    let key = (T::TYPE, handle.as_raw());
+    let live = LIVE.with(|l| l.borrow_mut().remove(&key)).is_some();
+    if !live {
+        error!("{:?} {:#x} is destroyed twice.", T::TYPE, key.1);
+    }
+
+    live
+}
+
+/// Returns the handles that are currently live, oldest call site first.
+pub fn live() -> Vec<Tracked> {
+    let mut live = LIVE.with(|l| {
+        l.borrow()
+            .iter()
+            .map(|(&(object_type, handle), &location)| Tracked { object_type, handle, location })
+            .collect::<Vec<_>>()
+    });
+
+    live.sort_by_key(|t| (t.location.file(), t.location.line(), t.handle));
+    live
+}
+
+/// Logs every handle that is still live (to be called right before the
+/// logical device is destroyed) and returns how many there are.
+pub fn report_leaks() -> usize {
+    let live = live();
+    for tracked in &live {
+        error!("Leaked {:?} {:#x} created at {}.", tracked.object_type, tracked.handle, tracked.location);
+    }
+
+    if TRACKING_ENABLED && live.is_empty() {
+        debug!("No leaked Vulkan handles.");
+    }
+
+    live.len()
+}
+
+#[cfg(all(test, debug_assertions))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn destroyed_handles_are_forgotten() {
+        let buffer = vk::Buffer::from_raw(0x10);
+        track(buffer, Location::caller());
+        assert_eq!(live().len(), 1);
+        assert_eq!(live()[0].object_type, vk::ObjectType::BUFFER);
+
+        assert!(untrack(buffer));
+        assert!(live().is_empty());
+        assert_eq!(report_leaks(), 0);
+    }
+
+    #[test]
+    fn handles_destroyed_twice_are_reported() {
+        let fence = vk::Fence::from_raw(0x20);
+        track(fence, Location::caller());
+
+        assert!(untrack(fence));
+        assert!(!untrack(fence));
+    }
+
+    #[test]
+    fn handles_are_keyed_by_type() {
+        let image = vk::Image::from_raw(0x30);
+        let view = vk::ImageView::from_raw(0x30);
+        track(image, Location::caller());
+        track(view, Location::caller());
+        assert_eq!(report_leaks(), 2);
+
+        assert!(untrack(view));
+        assert!(!untrack(view));
+        assert!(untrack(image));
+    }
+}
@@ -0,0 +1,13 @@
+#version 450
+
+layout(binding = 1) uniform texture2D texImage;
+layout(binding = 2) uniform sampler texSampler;
+
+layout(location = 0) in vec3 fragColor;
+layout(location = 1) in vec2 fragTexCoord;
+
+layout(location = 0) out vec4 outColor;
+
+void main() {
+    outColor = texture(sampler2D(texImage, texSampler), fragTexCoord);
+}
@@ -1,9 +1,11 @@
 #version 450
 
-layout(binding = 1) uniform sampler2D texSampler;
+layout(binding = 1) uniform texture2D texImage;
+layout(binding = 2) uniform sampler texSampler;
 
 layout(push_constant) uniform PushConstants {
-    layout(offset = 64) float opacity;
+    mat4 model;
+    float opacity;
 } pcs;
 
 layout(location = 0) in vec3 fragColor;
@@ -12,5 +14,5 @@ layout(location = 1) in vec2 fragTexCoord;
 layout(location = 0) out vec4 outColor;
 
 void main() {
-    outColor = vec4(texture(texSampler, fragTexCoord).rgb, pcs.opacity);
+    outColor = vec4(texture(sampler2D(texImage, texSampler), fragTexCoord).rgb, pcs.opacity);
 }